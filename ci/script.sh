@@ -1,7 +1,51 @@
 set -euxo pipefail
 
 main() {
-    cargo check --target $TARGET
+    if [ -n "${MSRV:-}" ]; then
+        # `nalgebra` and `telemetry` need a newer compiler; see `rust-version`
+        cargo test --target $TARGET
+        cargo test --target $TARGET --features uom,mint,bytemuck,ufmt,async,critical-section,std
+        cargo test --target $TARGET --no-default-features --features float,eh1,async
+        return
+    fi
+
+    # exactly one of `eh0` and `eh1` (`!` would be ignored by `set -e`)
+    if cargo check --target $TARGET --features eh1; then exit 1; fi
+    if cargo check --target $TARGET --no-default-features; then exit 1; fi
+
+    if [ $TARGET != x86_64-unknown-linux-gnu ]; then
+        cargo check --target $TARGET
+        cargo check --target $TARGET --no-default-features --features eh0
+        cargo check --target $TARGET --features uom
+        cargo check --target $TARGET --features nalgebra
+        cargo check --target $TARGET --features mint
+        cargo check --target $TARGET --features bytemuck
+        cargo check --target $TARGET --features ufmt
+        cargo check --target $TARGET --features async
+        cargo check --target $TARGET --no-default-features --features float,eh1
+        cargo check --target $TARGET --no-default-features --features eh1
+        cargo check --target $TARGET --features telemetry
+        cargo check --target $TARGET --features critical-section
+        return
+    fi
+
+    cargo test --target $TARGET
+    cargo test --target $TARGET --no-default-features --features eh0
+    cargo test --target $TARGET --features uom
+    cargo test --target $TARGET --features nalgebra
+    cargo test --target $TARGET --features mint
+    cargo test --target $TARGET --features bytemuck
+    cargo test --target $TARGET --features ufmt
+    cargo test --target $TARGET --features async
+    cargo test --target $TARGET --no-default-features --features float,eh1
+    cargo test --target $TARGET --no-default-features --features eh1
+    cargo test --target $TARGET --no-default-features --features float,eh1,async
+    cargo test --target $TARGET --features serde
+    cargo test --target $TARGET --features telemetry
+    cargo test --target $TARGET --features critical-section
+    cargo test --target $TARGET --features std
+    cargo test --target $TARGET --features linux
+    cargo check --target $TARGET --features linux --examples
 }
 
 main
@@ -0,0 +1,122 @@
+//! Print the measurements of a L3GD20 attached to a Linux host as CSV
+//!
+//! ``` text
+//! cargo run --example gyro_dump --features linux -- /dev/spidev0.0 25
+//! ```
+//!
+//! The sensor is configured for 190 Hz and ±500 dps. The arguments are the
+//! spidev device and the GPIO line used as chip select. Press Ctrl-C to stop:
+//! the register map is dumped to stderr before exiting.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::ops::ControlFlow;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use std::{env, process, thread};
+
+use embedded_hal::blocking::delay::DelayUs;
+use l3gd20::registers::Register;
+use l3gd20::{logging, Bandwidth, Config, Measurements, Odr, OverrunPolicy, Scale};
+
+const SIGINT: c_int = 2;
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" {
+    fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+}
+
+extern "C" fn on_sigint(_: c_int) {
+    STOP.store(true, Ordering::Relaxed);
+}
+
+struct Delay;
+
+impl DelayUs<u32> for Delay {
+    fn delay_us(&mut self, us: u32) {
+        thread::sleep(Duration::from_micros(u64::from(us)));
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let spidev = args.next().unwrap_or_else(|| "/dev/spidev0.0".into());
+    let cs_gpio = match args.next() {
+        Some(line) => line.parse()?,
+        None => 25,
+    };
+
+    let mut l3gd20 = l3gd20::linux::open(&spidev, cs_gpio, 1_000_000)?;
+    let config = Config::default()
+        .odr(Odr::Hz190)
+        .bandwidth(Bandwidth::Medium)
+        .scale(Scale::Dps500);
+    l3gd20.apply_config(&config)?;
+    l3gd20.set_overrun_policy(OverrunPolicy::Count);
+
+    // SAFETY: the handler only stores to an atomic
+    unsafe {
+        signal(SIGINT, on_sigint);
+    }
+
+    let mut line = String::new();
+    logging::write_csv_header(&mut line)?;
+    print!("{}", line);
+
+    let start = Instant::now();
+    let mut delay = Delay;
+    while !STOP.load(Ordering::Relaxed) {
+        let mut gyro = Default::default();
+        l3gd20.on_samples(1, &mut delay, |sample| {
+            gyro = sample;
+            ControlFlow::Break(())
+        })?;
+        let measurements = Measurements {
+            gyro,
+            temp: l3gd20.temp()?,
+        };
+
+        line.clear();
+        let t_us = start.elapsed().as_micros() as u32;
+        logging::write_csv_line(&mut line, t_us, &measurements, config.scale)?;
+        print!("{}", line);
+    }
+
+    eprintln!("overruns: {}", l3gd20.overrun_count());
+    dump_registers(&mut l3gd20)
+}
+
+/// Print `WHO_AM_I` and `CTRL_REG1` to `INT1_DURATION`
+fn dump_registers<SPI, CS>(l3gd20: &mut l3gd20::L3gd20<SPI, CS>) -> Result<(), Box<dyn Error>>
+where
+    SPI: embedded_hal::blocking::spi::Transfer<u8, Error = std::io::Error>
+        + embedded_hal::blocking::spi::Write<u8, Error = std::io::Error>,
+    CS: embedded_hal::digital::v2::OutputPin,
+{
+    let mut who_am_i = [0];
+    l3gd20.read_block(Register::WHO_AM_I, &mut who_am_i)?;
+    let mut block = [0; (Register::INT1_DURATION as usize) - (Register::CTRL_REG1 as usize) + 1];
+    l3gd20.read_block(Register::CTRL_REG1, &mut block)?;
+
+    let blocks = [
+        (Register::WHO_AM_I.addr(), &who_am_i[..]),
+        (Register::CTRL_REG1.addr(), &block[..]),
+    ];
+    for &(start, bytes) in blocks.iter() {
+        for (addr, &value) in (start..).zip(bytes.iter()) {
+            if let Ok(reg) = Register::try_from(addr) {
+                eprintln!("{:#04x} {:<14} {:#04x}", addr, format!("{:?}", reg), value);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Overflow-safe accumulation of measurements
+
+use core::iter::Sum;
+
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::I16x3;
+
+/// Per-axis sums of a series of measurements, for means and variances
+///
+/// The sums are `i64` and the count a `u32`, so no sum can overflow: even
+/// `u32::MAX` full scale samples add up to less than 2^47, and their squares
+/// to less than 2^62.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Accumulator {
+    count: u32,
+    sum: [i64; 3],
+    sum_sq: Option<[i64; 3]>,
+}
+
+impl Accumulator {
+    /// Creates an empty accumulator that only tracks the sums, i.e. the mean
+    pub const fn new() -> Self {
+        Accumulator {
+            count: 0,
+            sum: [0; 3],
+            sum_sq: None,
+        }
+    }
+
+    /// Creates an empty accumulator that also tracks the sums of squares,
+    /// i.e. the variance
+    pub const fn with_variance() -> Self {
+        Accumulator {
+            count: 0,
+            sum: [0; 3],
+            sum_sq: Some([0; 3]),
+        }
+    }
+
+    /// Add a sample
+    ///
+    /// Samples beyond `u32::MAX` are ignored.
+    pub fn push(&mut self, sample: I16x3) {
+        if self.count == u32::MAX {
+            return;
+        }
+
+        self.count += 1;
+        self.add(sample, 1);
+    }
+
+    /// Remove a sample pushed before, e.g. the oldest one of a sliding window
+    ///
+    /// Removing a sample that wasn't pushed makes the statistics meaningless.
+    pub fn remove(&mut self, sample: I16x3) {
+        if self.count == 0 {
+            return;
+        }
+
+        self.count -= 1;
+        self.add(sample, -1);
+    }
+
+    fn add(&mut self, sample: I16x3, sign: i64) {
+        let v = [
+            i64::from(sample.x),
+            i64::from(sample.y),
+            i64::from(sample.z),
+        ];
+        for i in 0..3 {
+            self.sum[i] += sign * v[i];
+            if let Some(sum_sq) = self.sum_sq.as_mut() {
+                sum_sq[i] += sign * v[i] * v[i];
+            }
+        }
+    }
+
+    /// Number of samples
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Per-axis sums, `[x, y, z]`
+    pub fn sums(&self) -> [i64; 3] {
+        self.sum
+    }
+
+    /// Mean, truncated towards zero; `None` if empty
+    pub fn mean(&self) -> Option<I16x3> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let n = i64::from(self.count);
+        Some(I16x3 {
+            x: (self.sum[0] / n) as i16,
+            y: (self.sum[1] / n) as i16,
+            z: (self.sum[2] / n) as i16,
+        })
+    }
+
+    /// Population variance, `[x, y, z]`, in counts squared and truncated
+    ///
+    /// `None` if empty or if the sums of squares are not tracked.
+    pub fn variance(&self) -> Option<[u32; 3]> {
+        let sum_sq = self.sum_sq.filter(|_| self.count > 0)?;
+
+        let n = i128::from(self.count);
+        let axis = |i: usize| {
+            let sum = i128::from(self.sum[i]);
+            ((n * i128::from(sum_sq[i]) - sum * sum) / (n * n)) as u32
+        };
+        Some([axis(0), axis(1), axis(2)])
+    }
+
+    /// Mean, in counts; `None` if empty
+    #[cfg(feature = "float")]
+    pub fn mean_f32(&self) -> Option<F32x3> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let n = self.count as f32;
+        Some(F32x3 {
+            x: self.sum[0] as f32 / n,
+            y: self.sum[1] as f32 / n,
+            z: self.sum[2] as f32 / n,
+        })
+    }
+
+    /// Population variance, in counts squared
+    ///
+    /// `None` if empty or if the sums of squares are not tracked.
+    #[cfg(feature = "float")]
+    pub fn variance_f32(&self) -> Option<F32x3> {
+        let sum_sq = self.sum_sq.filter(|_| self.count > 0)?;
+
+        let n = i128::from(self.count);
+        let axis = |i: usize| {
+            let sum = i128::from(self.sum[i]);
+            (n * i128::from(sum_sq[i]) - sum * sum) as f32 / (n * n) as f32
+        };
+        Some(F32x3 {
+            x: axis(0),
+            y: axis(1),
+            z: axis(2),
+        })
+    }
+
+    /// Discard all the samples
+    pub fn reset(&mut self) {
+        let variance = self.sum_sq.is_some();
+        *self = Accumulator::new();
+        if variance {
+            self.sum_sq = Some([0; 3]);
+        }
+    }
+}
+
+impl Default for Accumulator {
+    /// Same as `Accumulator::with_variance`
+    fn default() -> Self {
+        Accumulator::with_variance()
+    }
+}
+
+impl Extend<I16x3> for Accumulator {
+    fn extend<T: IntoIterator<Item = I16x3>>(&mut self, iter: T) {
+        for sample in iter {
+            self.push(sample);
+        }
+    }
+}
+
+impl Sum<I16x3> for Accumulator {
+    /// Accumulate with variance tracking
+    fn sum<T: Iterator<Item = I16x3>>(iter: T) -> Self {
+        let mut acc = Accumulator::with_variance();
+        acc.extend(iter);
+        acc
+    }
+}
@@ -0,0 +1,71 @@
+//! Offline analysis of recorded measurements
+//!
+//! Meant for characterizing sensors on a host, from data pulled over e.g.
+//! `linux-embedded-hal` or from log files. Requires the `std` feature.
+
+use std::vec::Vec;
+
+use crate::{I16x3, Scale};
+
+/// Overlapping Allan deviation of a rate signal
+///
+/// `samples` are rates taken at `sample_rate_hz`, in any unit. For every
+/// averaging time of `taus`, in seconds, returns the averaging time actually
+/// used (a whole number of samples) and the Allan deviation, in the unit of
+/// `samples`. Averaging times shorter than one sample or longer than half
+/// the recording are skipped.
+///
+/// On a log-log plot, angle random walk is read off the region of slope
+/// -1/2 (at a `tau` of 1 s) and bias instability at the bottom of the curve.
+pub fn allan_deviation(samples: &[f32], sample_rate_hz: f32, taus: &[f32]) -> Vec<(f32, f32)> {
+    let n = samples.len();
+    let fs = f64::from(sample_rate_hz);
+
+    // Integrated signal (angle), with theta[0] = 0
+    let mut theta = Vec::with_capacity(n + 1);
+    let mut angle = 0.0f64;
+    theta.push(angle);
+    for &rate in samples {
+        angle += f64::from(rate) / fs;
+        theta.push(angle);
+    }
+
+    let mut curve = Vec::with_capacity(taus.len());
+    for &tau in taus {
+        let m = (f64::from(tau) * fs).round() as usize;
+        if m == 0 || 2 * m > n {
+            continue;
+        }
+
+        let tau = m as f64 / fs;
+        let terms = n + 1 - 2 * m;
+        let sum: f64 = (0..terms)
+            .map(|k| {
+                let d = theta[k + 2 * m] - 2.0 * theta[k + m] + theta[k];
+                d * d
+            })
+            .sum();
+        let avar = sum / (2.0 * tau * tau * terms as f64);
+
+        curve.push((tau as f32, avar.sqrt() as f32));
+    }
+    curve
+}
+
+/// Allan deviation of each axis of raw measurements taken at `scale`, in
+/// degrees per second
+///
+/// Returns the curves of the X, Y and Z axes, see `allan_deviation`.
+pub fn allan_deviation_axes(
+    samples: &[I16x3],
+    scale: Scale,
+    sample_rate_hz: f32,
+    taus: &[f32],
+) -> [Vec<(f32, f32)>; 3] {
+    let axis = |component: fn(&I16x3) -> i16| {
+        let rates: Vec<f32> = samples.iter().map(|s| scale.degrees(component(s))).collect();
+        allan_deviation(&rates, sample_rate_hz, taus)
+    };
+
+    [axis(|s| s.x), axis(|s| s.y), axis(|s| s.z)]
+}
@@ -0,0 +1,172 @@
+//! Automatic full scale switching
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, I16x3, L3gd20, Scale};
+
+/// Policy for switching the full scale according to the measurements
+///
+/// The scale is stepped up (±250 → ±500 → ±2000 dps) after `step_up_after`
+/// consecutive samples with a saturated axis, and stepped down after
+/// `step_down_after` consecutive samples whose axes all stay below
+/// `step_down_percent` of the range of the next scale down. The gap between
+/// saturation and that percentage is the hysteresis that keeps the driver
+/// from switching back and forth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoRange {
+    step_up_after: u16,
+    step_down_percent: u8,
+    step_down_after: u16,
+}
+
+impl AutoRange {
+    /// Create a policy
+    ///
+    /// `step_up_after` and `step_down_after` are numbers of samples; zero is
+    /// treated as one. `step_down_percent` is capped at 90 %.
+    pub const fn new(step_up_after: u16, step_down_percent: u8, step_down_after: u16) -> Self {
+        AutoRange {
+            step_up_after: if step_up_after == 0 { 1 } else { step_up_after },
+            step_down_percent: if step_down_percent > 90 {
+                90
+            } else {
+                step_down_percent
+            },
+            step_down_after: if step_down_after == 0 {
+                1
+            } else {
+                step_down_after
+            },
+        }
+    }
+}
+
+/// Change of full scale made by the automatic range switching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeChange {
+    /// Scale before the change
+    pub from: Scale,
+    /// Scale after the change
+    pub to: Scale,
+}
+
+/// Gyroscope measurement returned by `gyro_auto_ranged`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangedSample {
+    /// Gyroscope measurement, bias compensated
+    pub gyro: I16x3,
+    /// Scale the measurement was taken at
+    pub scale: Scale,
+    /// Scale change made after this measurement, applying to the next ones
+    pub change: Option<RangeChange>,
+}
+
+/// Runs of samples counted towards a scale change
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RangeRuns {
+    saturated: u16,
+    low: u16,
+}
+
+impl Scale {
+    /// The next coarser scale
+    fn step_up(self) -> Option<Scale> {
+        match self {
+            Scale::Dps250 => Some(Scale::Dps500),
+            Scale::Dps500 => Some(Scale::Dps2000),
+            Scale::Dps2000 => None,
+        }
+    }
+
+    /// The next finer scale
+    fn step_down(self) -> Option<Scale> {
+        match self {
+            Scale::Dps250 => None,
+            Scale::Dps500 => Some(Scale::Dps250),
+            Scale::Dps2000 => Some(Scale::Dps500),
+        }
+    }
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Enable (`Some`) or disable (`None`) automatic range switching
+    ///
+    /// Only `gyro_auto_ranged` switches the scale. The counted runs of
+    /// samples are restarted.
+    pub fn set_auto_range(&mut self, policy: Option<AutoRange>) -> &mut Self {
+        self.auto_range = policy;
+        self.range_runs = RangeRuns::default();
+        self
+    }
+
+    /// Current automatic range switching policy
+    pub fn auto_range(&self) -> Option<AutoRange> {
+        self.auto_range
+    }
+
+    /// Gyroscope measurements, switching the full scale when the
+    /// `AutoRange` policy says so
+    ///
+    /// The scale is only changed after the measurement has been read, so
+    /// every measurement is consistent with its `scale`; the change is
+    /// reported in `change`. Scale changes go through `set_scale`, so the
+    /// bias is rescaled or cleared according to the `BiasPolicy`. Without a
+    /// policy this is `gyro` plus the scale.
+    pub fn gyro_auto_ranged(&mut self) -> Result<RangedSample, Error<E>> {
+        let scale = match self.cached_scale() {
+            Some(scale) => scale,
+            None => self.scale()?,
+        };
+        let raw = self.gyro_raw()?;
+        let gyro = self.adjust(raw);
+
+        let next = match self.auto_range {
+            Some(policy) => self.next_range(policy, scale, raw),
+            None => None,
+        };
+        let change = match next {
+            Some(to) => {
+                self.set_scale(to)?;
+                self.range_runs = RangeRuns::default();
+                Some(RangeChange { from: scale, to })
+            }
+            None => None,
+        };
+
+        Ok(RangedSample {
+            gyro,
+            scale,
+            change,
+        })
+    }
+
+    /// Count `raw`, taken at `scale`, towards a scale change
+    fn next_range(&mut self, policy: AutoRange, scale: Scale, raw: I16x3) -> Option<Scale> {
+        let runs = &mut self.range_runs;
+
+        if raw.saturated_axes().iter().any(|&s| s) {
+            runs.low = 0;
+            runs.saturated = runs.saturated.saturating_add(1);
+            return scale
+                .step_up()
+                .filter(|_| runs.saturated >= policy.step_up_after);
+        }
+        runs.saturated = 0;
+
+        let lower = scale.step_down()?;
+        let limit = i16::MAX as i32 * policy.step_down_percent as i32 / 100;
+        let v = scale.rescale_vec(&raw, lower);
+        let low = [v.x, v.y, v.z].iter().all(|&c| (c as i32).abs() < limit);
+        if low {
+            runs.low = runs.low.saturating_add(1);
+        } else {
+            runs.low = 0;
+        }
+        Some(lower).filter(|_| runs.low >= policy.step_down_after)
+    }
+}
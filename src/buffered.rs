@@ -0,0 +1,495 @@
+//! Interrupt driven acquisition into a ring buffer
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU32, AtomicU8, AtomicUsize, Ordering};
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{I16x3, L3gd20};
+
+/// Driver that stores the measurements read from an interrupt handler
+///
+/// `handle_drdy` is meant to be the whole body of the DRDY interrupt handler:
+/// it reads one measurement in a single burst transaction, without waiting
+/// or floating point math, into a ring buffer of `N` samples. The main loop
+/// then takes the measurements out with `pop_sample` or `drain_into`.
+///
+/// There is no locking inside: sharing the driver between the handler and
+/// the main loop (e.g. with `SharedL3gd20`-like critical sections or the
+/// resource locks of the framework) is up to the caller.
+pub struct BufferedL3gd20<SPI, CS, const N: usize> {
+    l3gd20: L3gd20<SPI, CS>,
+    ring: [I16x3; N],
+    // index of the oldest sample
+    head: usize,
+    len: usize,
+    overflows: u32,
+}
+
+impl<SPI, CS, E, const N: usize> BufferedL3gd20<SPI, CS, N>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Wrap `l3gd20` with an empty ring buffer
+    ///
+    /// `N` must be at least 1.
+    pub fn new(l3gd20: L3gd20<SPI, CS>) -> Self {
+        const { assert!(N > 0) };
+
+        BufferedL3gd20 {
+            l3gd20,
+            ring: [I16x3::default(); N],
+            head: 0,
+            len: 0,
+            overflows: 0,
+        }
+    }
+
+    /// Read one measurement into the ring buffer
+    ///
+    /// The stored bias is subtracted, as for `L3gd20::gyro`. If the buffer
+    /// is full the oldest sample is dropped and counted, see `overflows`.
+    pub fn handle_drdy(&mut self) -> Result<(), E> {
+        let sample = self.l3gd20.gyro()?;
+
+        if self.len == N {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            self.overflows = self.overflows.saturating_add(1);
+        }
+        self.ring[(self.head + self.len) % N] = sample;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Take the oldest stored measurement
+    pub fn pop_sample(&mut self) -> Option<I16x3> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let sample = self.ring[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(sample)
+    }
+
+    /// Move the stored measurements, oldest first, into `out`
+    ///
+    /// Returns the number of measurements moved; the ones that didn't fit
+    /// stay in the buffer.
+    pub fn drain_into(&mut self, out: &mut [I16x3]) -> usize {
+        let mut moved = 0;
+        for slot in out.iter_mut() {
+            match self.pop_sample() {
+                Some(sample) => *slot = sample,
+                None => break,
+            }
+            moved += 1;
+        }
+        moved
+    }
+
+    /// Number of stored measurements
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// `true` if no measurement is stored
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of measurements dropped because the buffer was full
+    pub fn overflows(&self) -> u32 {
+        self.overflows
+    }
+
+    /// Access the underlying driver, e.g. to change its configuration
+    pub fn driver(&mut self) -> &mut L3gd20<SPI, CS> {
+        &mut self.l3gd20
+    }
+
+    /// Release the underlying driver; stored measurements are lost
+    pub fn release(self) -> L3gd20<SPI, CS> {
+        self.l3gd20
+    }
+}
+
+/// Pair of blocks of `N` measurements for block based processing
+///
+/// Measurements are pushed into the back block; when it's full the blocks
+/// are swapped and the completed block becomes available through
+/// `take_ready`, while the next measurements go into the other block. If
+/// the consumer hasn't taken the previous block by then it is replaced by
+/// the newer one and counted, see `dropped_blocks`.
+///
+/// Both blocks are stored inline, so no allocation is needed. `split`
+/// returns a `BlockProducer` and a `BlockConsumer` that can be used from
+/// two execution contexts (e.g. an interrupt handler and the main loop)
+/// without locking; the swap is a single atomic operation.
+pub struct DoubleBuffer<const N: usize> {
+    blocks: [UnsafeCell<[I16x3; N]>; 2],
+    // `BACK`, `READY` and `READING`
+    state: AtomicU8,
+    // written by the producer only
+    filled: AtomicUsize,
+    dropped: AtomicU32,
+}
+
+// Index of the block being filled
+const BACK: u8 = 0b001;
+// The other block is completed and hasn't been taken yet
+const READY: u8 = 0b010;
+// The consumer holds the other block
+const READING: u8 = 0b100;
+
+// SAFETY: the blocks are only accessed through `&mut DoubleBuffer` or
+// through the `BlockProducer`, which only writes the back block, and the
+// `BlockConsumer`, which only reads the other block while `READING` is set;
+// the producer never swaps the blocks while it is.
+unsafe impl<const N: usize> Sync for DoubleBuffer<N> {}
+
+/// State after the producer completed the back block, and whether a block
+/// was dropped
+///
+/// While the consumer holds the other block the completed one is dropped
+/// and filled again.
+const fn completed(state: u8) -> (u8, bool) {
+    if state & READING != 0 {
+        (state, true)
+    } else {
+        ((state ^ BACK) | READY, state & READY != 0)
+    }
+}
+
+impl<const N: usize> DoubleBuffer<N> {
+    /// Create an empty buffer
+    ///
+    /// `N` must be at least 1.
+    pub fn new() -> Self {
+        const { assert!(N > 0) };
+
+        DoubleBuffer {
+            blocks: [
+                UnsafeCell::new([I16x3::default(); N]),
+                UnsafeCell::new([I16x3::default(); N]),
+            ],
+            state: AtomicU8::new(0),
+            filled: AtomicUsize::new(0),
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    /// Store one measurement
+    ///
+    /// Returns `true` if this completed a block.
+    pub fn push(&mut self, sample: I16x3) -> bool {
+        if !self.write_back(sample) {
+            return false;
+        }
+
+        let state = self.state.get_mut();
+        let (next, dropped) = completed(*state);
+        *state = next;
+        if dropped {
+            self.count_dropped();
+        }
+        true
+    }
+
+    /// Store measurements, e.g. those of a FIFO drain
+    ///
+    /// Returns `true` if this completed at least one block.
+    pub fn extend(&mut self, samples: &[I16x3]) -> bool {
+        samples
+            .iter()
+            .fold(false, |completed, &sample| self.push(sample) | completed)
+    }
+
+    /// Take the last completed block, if it hasn't been taken yet
+    pub fn take_ready(&mut self) -> Option<&[I16x3; N]> {
+        let state = self.state.get_mut();
+        let ready = *state & READY != 0;
+        *state &= BACK;
+        if !ready {
+            return None;
+        }
+
+        let front = usize::from(*state ^ BACK);
+        Some(self.blocks[front].get_mut())
+    }
+
+    /// Split the buffer into a `BlockProducer` and a `BlockConsumer`
+    #[cfg(target_has_atomic = "8")]
+    pub fn split(&mut self) -> (BlockProducer<'_, N>, BlockConsumer<'_, N>) {
+        let buffer = &*self;
+        (BlockProducer { buffer }, BlockConsumer { buffer })
+    }
+
+    /// Number of measurements in the block being filled
+    pub fn filled(&self) -> usize {
+        self.filled.load(Ordering::Relaxed)
+    }
+
+    /// Number of completed blocks replaced before they were taken
+    pub fn dropped_blocks(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Discard the stored measurements; the drop counter is kept
+    pub fn reset(&mut self) {
+        *self.filled.get_mut() = 0;
+        *self.state.get_mut() &= BACK;
+    }
+
+    /// Write `sample` into the back block; returns `true` if it completed
+    /// it. Must only be called by the producer.
+    fn write_back(&self, sample: I16x3) -> bool {
+        let back = usize::from(self.state.load(Ordering::Relaxed) & BACK);
+        let filled = self.filled.load(Ordering::Relaxed);
+        // SAFETY: only the producer accesses the back block, see `Sync`
+        unsafe { (*self.blocks[back].get())[filled] = sample };
+
+        let filled = (filled + 1) % N;
+        self.filled.store(filled, Ordering::Relaxed);
+        filled == 0
+    }
+
+    /// Must only be called by the producer
+    fn count_dropped(&self) {
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        self.dropped
+            .store(dropped.saturating_add(1), Ordering::Relaxed);
+    }
+}
+
+impl<const N: usize> Default for DoubleBuffer<N> {
+    fn default() -> Self {
+        DoubleBuffer::new()
+    }
+}
+
+/// Producer half of a `DoubleBuffer`, see `DoubleBuffer::split`
+#[cfg(target_has_atomic = "8")]
+pub struct BlockProducer<'a, const N: usize> {
+    buffer: &'a DoubleBuffer<N>,
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const N: usize> BlockProducer<'_, N> {
+    /// Store one measurement, see `DoubleBuffer::push`
+    ///
+    /// Returns `true` if this completed a block. While the consumer holds
+    /// the previous block (see `BlockConsumer::take_ready`) the completed
+    /// block can't be swapped in: it is dropped instead, counted, and filled
+    /// again.
+    pub fn push(&mut self, sample: I16x3) -> bool {
+        if !self.buffer.write_back(sample) {
+            return false;
+        }
+
+        let mut state = self.buffer.state.load(Ordering::Acquire);
+        loop {
+            let (next, dropped) = completed(state);
+            let result = self.buffer.state.compare_exchange_weak(
+                state,
+                next,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+            match result {
+                Ok(_) => {
+                    if dropped {
+                        self.buffer.count_dropped();
+                    }
+                    return true;
+                }
+                Err(actual) => state = actual,
+            }
+        }
+    }
+
+    /// Store measurements, see `DoubleBuffer::extend`
+    pub fn extend(&mut self, samples: &[I16x3]) -> bool {
+        samples
+            .iter()
+            .fold(false, |completed, &sample| self.push(sample) | completed)
+    }
+
+    /// Number of measurements in the block being filled
+    pub fn filled(&self) -> usize {
+        self.buffer.filled()
+    }
+}
+
+/// Consumer half of a `DoubleBuffer`, see `DoubleBuffer::split`
+#[cfg(target_has_atomic = "8")]
+pub struct BlockConsumer<'a, const N: usize> {
+    buffer: &'a DoubleBuffer<N>,
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const N: usize> BlockConsumer<'_, N> {
+    /// Take the last completed block, if it hasn't been taken yet
+    ///
+    /// The block previously taken, if any, is released. The returned block
+    /// is held until the next call, `release` or the consumer being dropped;
+    /// the blocks the producer completes in the meantime are dropped, so
+    /// process it within one block period.
+    pub fn take_ready(&mut self) -> Option<&[I16x3; N]> {
+        let taken = |state: u8| {
+            if state & READY != 0 {
+                (state & BACK) | READING
+            } else {
+                state & BACK
+            }
+        };
+        let state = self.update(taken);
+        if state & READY == 0 {
+            return None;
+        }
+
+        let front = usize::from((state & BACK) ^ BACK);
+        // SAFETY: the producer doesn't write the front block while
+        // `READING` is set
+        Some(unsafe { &*self.buffer.blocks[front].get() })
+    }
+
+    /// Release the block taken by `take_ready`
+    pub fn release(&mut self) {
+        self.update(|state| state & !READING);
+    }
+
+    /// Number of completed blocks replaced before they were taken
+    pub fn dropped_blocks(&self) -> u32 {
+        self.buffer.dropped_blocks()
+    }
+
+    /// Apply `f` to the state, returning the previous one
+    fn update(&mut self, f: impl Fn(u8) -> u8) -> u8 {
+        let mut state = self.buffer.state.load(Ordering::Acquire);
+        loop {
+            let result = self.buffer.state.compare_exchange_weak(
+                state,
+                f(state),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+            match result {
+                Ok(previous) => return previous,
+                Err(actual) => state = actual,
+            }
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const N: usize> Drop for BlockConsumer<'_, N> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(i: i16) -> I16x3 {
+        I16x3 { x: i, y: 0, z: 0 }
+    }
+
+    fn block<const N: usize>(first: i16) -> [I16x3; N] {
+        let mut block = [I16x3::default(); N];
+        for (i, s) in block.iter_mut().enumerate() {
+            *s = sample(first + i as i16);
+        }
+        block
+    }
+
+    #[test]
+    fn blocks_are_completed_on_exact_boundaries() {
+        let mut buffer = DoubleBuffer::<4>::new();
+        assert!(!buffer.extend(&block::<3>(0)));
+        assert_eq!(buffer.filled(), 3);
+        assert_eq!(buffer.take_ready(), None);
+        assert!(buffer.push(sample(3)));
+        assert_eq!(buffer.filled(), 0);
+        assert_eq!(buffer.take_ready(), Some(&block(0)));
+        assert_eq!(buffer.take_ready(), None);
+
+        assert!(buffer.extend(&block::<4>(4)));
+        assert_eq!(buffer.take_ready(), Some(&block(4)));
+        assert_eq!(buffer.dropped_blocks(), 0);
+    }
+
+    #[test]
+    fn slow_consumers_lose_the_older_block() {
+        let mut buffer = DoubleBuffer::<2>::new();
+        assert!(buffer.extend(&block::<5>(0)));
+        assert_eq!(buffer.dropped_blocks(), 1);
+        assert_eq!(buffer.filled(), 1);
+        assert_eq!(buffer.take_ready(), Some(&block(2)));
+
+        buffer.reset();
+        assert_eq!(buffer.filled(), 0);
+        assert_eq!(buffer.take_ready(), None);
+        assert_eq!(buffer.dropped_blocks(), 1);
+    }
+
+    #[test]
+    fn held_blocks_are_not_overwritten() {
+        let mut buffer = DoubleBuffer::<2>::new();
+        let (mut producer, mut consumer) = buffer.split();
+        assert!(producer.extend(&block::<2>(0)));
+        assert_eq!(consumer.take_ready(), Some(&block(0)));
+
+        // completed while block 0 is held: dropped
+        assert!(producer.extend(&block::<2>(2)));
+        assert_eq!(consumer.dropped_blocks(), 1);
+        consumer.release();
+        assert_eq!(consumer.take_ready(), None);
+
+        assert!(producer.extend(&block::<2>(4)));
+        let held = *consumer.take_ready().unwrap();
+        assert_eq!(held, block(4));
+        // releasing by taking again
+        assert!(producer.extend(&block::<2>(6)));
+        assert_eq!(consumer.dropped_blocks(), 2);
+        assert_eq!(consumer.take_ready(), None);
+        assert!(producer.extend(&block::<2>(8)));
+        assert_eq!(consumer.take_ready(), Some(&block(8)));
+    }
+
+    #[test]
+    fn blocks_cross_threads_untorn() {
+        const N: usize = 16;
+        let mut buffer = DoubleBuffer::<N>::new();
+        let (mut producer, mut consumer) = buffer.split();
+        let blocks = 2_000;
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for i in 0..blocks * N {
+                    producer.push(sample(i as i16));
+                }
+            });
+
+            let mut taken = 0;
+            let mut last = None;
+            while taken + consumer.dropped_blocks() < blocks as u32 {
+                if let Some(block) = consumer.take_ready() {
+                    let first = block[0].x;
+                    for (i, s) in block.iter().enumerate() {
+                        assert_eq!(s.x, first.wrapping_add(i as i16));
+                    }
+                    assert_ne!(last, Some(first));
+                    last = Some(first);
+                    taken += 1;
+                }
+            }
+        });
+    }
+}
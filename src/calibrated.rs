@@ -0,0 +1,149 @@
+//! Driver applying a complete calibration
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::float::DEG_TO_RAD;
+use crate::{AxisMapping, Error, F32x3, Gyroscope, I16x3, L3gd20};
+
+/// Calibration applied by `CalibratedL3gd20`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationData {
+    /// Zero-rate bias in raw counts, in the sensor frame
+    pub bias: F32x3,
+    /// Scale and misalignment correction applied after the bias, row-major;
+    /// the identity if the sensor needs none
+    pub matrix: [[f32; 3]; 3],
+    /// Mapping from the sensor axes to the output axes, applied last
+    pub mapping: AxisMapping,
+}
+
+impl Default for CalibrationData {
+    /// No correction
+    fn default() -> Self {
+        CalibrationData {
+            bias: F32x3::default(),
+            matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            mapping: AxisMapping::IDENTITY,
+        }
+    }
+}
+
+impl CalibrationData {
+    /// Apply the calibration to a raw measurement, giving counts
+    pub fn apply(&self, raw: I16x3) -> F32x3 {
+        let v = [
+            raw.x as f32 - self.bias.x,
+            raw.y as f32 - self.bias.y,
+            raw.z as f32 - self.bias.z,
+        ];
+        let row = |r: [f32; 3]| r[0] * v[0] + r[1] * v[1] + r[2] * v[2];
+        let corrected = F32x3 {
+            x: row(self.matrix[0]),
+            y: row(self.matrix[1]),
+            z: row(self.matrix[2]),
+        };
+        self.mapping.apply_f32(corrected)
+    }
+}
+
+/// Unit of the rates returned by `CalibratedL3gd20::read`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateUnit {
+    /// Degrees per second
+    Dps,
+    /// Radians per second
+    RadPerSec,
+}
+
+/// Driver whose measurements are corrected by a `CalibrationData`
+///
+/// Measurements are read raw: the bias, bias model and axis mapping
+/// configured on the wrapped driver are not applied. Requires the `float`
+/// feature.
+pub struct CalibratedL3gd20<SPI, CS> {
+    l3gd20: L3gd20<SPI, CS>,
+    calibration: CalibrationData,
+    unit: RateUnit,
+}
+
+impl<SPI, CS, E> CalibratedL3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Wrap `l3gd20`; `read` returns degrees per second
+    pub fn new(l3gd20: L3gd20<SPI, CS>, calibration: CalibrationData) -> Self {
+        CalibratedL3gd20 {
+            l3gd20,
+            calibration,
+            unit: RateUnit::Dps,
+        }
+    }
+
+    /// Release the underlying driver
+    pub fn release(self) -> L3gd20<SPI, CS> {
+        self.l3gd20
+    }
+
+    /// Get the current calibration
+    pub fn calibration(&self) -> &CalibrationData {
+        &self.calibration
+    }
+
+    /// Replace the calibration
+    pub fn set_calibration(&mut self, calibration: CalibrationData) -> &mut Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Get the unit of the rates returned by `read`
+    pub fn unit(&self) -> RateUnit {
+        self.unit
+    }
+
+    /// Set the unit of the rates returned by `read`
+    pub fn set_unit(&mut self, unit: RateUnit) -> &mut Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Fully corrected measurements, in the selected unit
+    ///
+    /// Like `L3gd20::gyro_mdps` this uses the cached `Scale`.
+    pub fn read(&mut self) -> Result<F32x3, Error<E>> {
+        let dps = self.read_dps()?;
+        Ok(match self.unit {
+            RateUnit::Dps => dps,
+            RateUnit::RadPerSec => dps.scale(DEG_TO_RAD),
+        })
+    }
+
+    fn read_dps(&mut self) -> Result<F32x3, Error<E>> {
+        let scale = self.l3gd20.current_scale()?;
+        let raw = self.l3gd20.gyro_raw()?;
+        Ok(self
+            .calibration
+            .apply(raw)
+            .scale(scale.degrees_per_count()))
+    }
+}
+
+impl<SPI, CS, E> Gyroscope for CalibratedL3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    type Error = Error<E>;
+
+    /// Corrected measurements in degrees per second, whatever the selected
+    /// unit
+    fn read_rate(&mut self) -> Result<F32x3, Error<E>> {
+        self.read_dps()
+    }
+
+    /// Same as for the wrapped `L3gd20`
+    fn sample_rate_hz(&self) -> f32 {
+        self.l3gd20.sample_rate_hz()
+    }
+}
@@ -0,0 +1,30 @@
+//! Timestamping of measurements
+
+/// Monotonic tick source used to timestamp measurements
+///
+/// The meaning of a tick (CPU cycles, microseconds, ...) is up to the
+/// implementation; the driver only requires that the count never decreases.
+/// Any `FnMut() -> u64` closure is a `Clock`.
+pub trait Clock {
+    /// Current tick count
+    fn now(&mut self) -> u64;
+}
+
+impl<F> Clock for F
+where
+    F: FnMut() -> u64,
+{
+    fn now(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// A measurement with the tick count at which it was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamped<T> {
+    /// The measurement
+    pub sample: T,
+    /// Ticks read from the `Clock` right before the SPI transaction
+    pub ticks: u64,
+}
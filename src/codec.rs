@@ -0,0 +1,289 @@
+//! Pure encoding and decoding of the register representations
+//!
+//! Every type that is stored in a register implements `Codec`. Decoding
+//! never panics: any raw value either decodes or is reported as an
+//! `InvalidValue`. Encoding a value and decoding the result gives the value
+//! back; the reverse doesn't always hold, since some settings have several
+//! encodings (e.g. `FS = 10` and `FS = 11` are both ±2000 dps).
+
+use core::convert::TryFrom;
+
+use crate::registers::{
+    CtrlReg1, CtrlReg2, CtrlReg3, CtrlReg4, CtrlReg5, FifoCtrl, FifoSrc, Int1Cfg, Int1Duration,
+    Int1Src, Int1TshXh, LowOdr, Register,
+};
+use crate::{AxesEnable, Bandwidth, Config, DataPath, InvalidValue, Odr, PowerMode, Scale, Status};
+
+/// Conversion between a value and its raw representation in the sensor
+pub trait Codec: Sized {
+    /// Raw representation
+    type Raw;
+
+    /// Raw representation of `self`
+    fn encode(&self) -> Self::Raw;
+
+    /// Value represented by `raw`
+    fn decode(raw: Self::Raw) -> Result<Self, InvalidValue>;
+}
+
+/// Raw representation of `value`
+pub fn encode<T: Codec>(value: &T) -> T::Raw {
+    value.encode()
+}
+
+/// Value represented by `raw`
+pub fn decode<T: Codec>(raw: T::Raw) -> Result<T, InvalidValue> {
+    T::decode(raw)
+}
+
+/// `BW` field of `CTRL_REG1`, not shifted
+impl Codec for Bandwidth {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        CtrlReg1::default().with_bandwidth(*self).bw()
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        Bandwidth::try_from(raw)
+    }
+}
+
+/// `FS` field of `CTRL_REG4`, not shifted
+impl Codec for Scale {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        CtrlReg4::default().with_scale(*self).fs()
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        Scale::try_from(raw)
+    }
+}
+
+/// `DR` field, with the `Low_ODR` bit as bit 2
+impl Codec for Odr {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        *self as u8
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        Odr::try_from(raw)
+    }
+}
+
+/// Registers, whose every value is valid
+macro_rules! register_codec {
+    ($($ty:ident),*) => {
+        $(
+            impl Codec for $ty {
+                type Raw = u8;
+
+                fn encode(&self) -> u8 {
+                    self.bits()
+                }
+
+                fn decode(raw: u8) -> Result<Self, InvalidValue> {
+                    Ok($ty::from_bits(raw))
+                }
+            }
+        )*
+    };
+}
+
+register_codec!(
+    CtrlReg1,
+    CtrlReg2,
+    CtrlReg3,
+    CtrlReg4,
+    CtrlReg5,
+    FifoCtrl,
+    FifoSrc,
+    Int1Cfg,
+    Int1Duration,
+    Int1Src,
+    Int1TshXh,
+    LowOdr
+);
+
+/// Register address
+impl Codec for Register {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        self.addr()
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        Register::try_from(raw)
+    }
+}
+
+/// `PD` bit
+impl Codec for PowerMode {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        *self as u8
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        match raw {
+            0 => Ok(PowerMode::PowerDown),
+            1 => Ok(PowerMode::Normal),
+            _ => Err(InvalidValue { value: raw }),
+        }
+    }
+}
+
+/// `Zen`, `Yen` and `Xen` bits, in bits 2 to 0
+impl Codec for AxesEnable {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        CtrlReg1::from_bits(0).with_axes(*self).bits()
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        if raw > 0b111 {
+            return Err(InvalidValue { value: raw });
+        }
+        Ok(CtrlReg1::from_bits(raw).axes())
+    }
+}
+
+/// `CTRL_REG5` value with only `HPen` and `Out_Sel` set
+impl Codec for DataPath {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        CtrlReg5::from_bits(0).with_data_path(*self).bits()
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        if raw & !0b1_0011 != 0 {
+            return Err(InvalidValue { value: raw });
+        }
+        Ok(CtrlReg5::from_bits(raw).data_path())
+    }
+}
+
+/// `STATUS_REG` value
+impl Codec for Status {
+    type Raw = u8;
+
+    fn encode(&self) -> u8 {
+        self.bits()
+    }
+
+    fn decode(raw: u8) -> Result<Self, InvalidValue> {
+        Ok(Status::from_bits_truncate(raw))
+    }
+}
+
+/// Values of `CTRL_REG1` to `CTRL_REG5`
+impl Codec for Config {
+    type Raw = [u8; 5];
+
+    fn encode(&self) -> [u8; 5] {
+        self.registers()
+    }
+
+    fn decode(raw: [u8; 5]) -> Result<Self, InvalidValue> {
+        Config::from_registers(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Debug;
+
+    /// Decode every `u8`: valid values must survive an encoding round trip,
+    /// the others must be reported with the raw value; returns the number of
+    /// valid values
+    fn check_all<T>() -> usize
+    where
+        T: Codec<Raw = u8> + Debug + PartialEq,
+    {
+        let mut valid = 0;
+        for raw in 0..=u8::MAX {
+            match decode::<T>(raw) {
+                Ok(value) => {
+                    assert_eq!(decode::<T>(encode(&value)), Ok(value), "{:#04x}", raw);
+                    valid += 1;
+                }
+                Err(error) => assert_eq!(error, InvalidValue { value: raw }),
+            }
+        }
+        valid
+    }
+
+    /// `check_all` for types whose every `u8` is a distinct valid value
+    fn check_bijective<T>()
+    where
+        T: Codec<Raw = u8> + Debug + PartialEq,
+    {
+        assert_eq!(check_all::<T>(), 256);
+        for raw in 0..=u8::MAX {
+            assert_eq!(decode::<T>(raw).map(|value| encode(&value)), Ok(raw));
+        }
+    }
+
+    #[test]
+    fn fields_round_trip() {
+        // `DR = 10` and `DR = 11` are both 50 Hz with `Low_ODR` set
+        assert_eq!(check_all::<Odr>(), 8);
+        assert_eq!(check_all::<Bandwidth>(), 4);
+        // `FS = 10` and `FS = 11` are both ±2000 dps
+        assert_eq!(check_all::<Scale>(), 4);
+        assert_eq!(check_all::<PowerMode>(), 2);
+        assert_eq!(check_all::<AxesEnable>(), 8);
+        assert_eq!(check_all::<DataPath>(), 8);
+        assert_eq!(check_all::<Register>(), 27);
+        assert_eq!(check_all::<Status>(), 256);
+    }
+
+    #[test]
+    fn low_data_rates_keep_the_low_odr_bit() {
+        assert_eq!(encode(&Odr::Hz12_5), 0b100);
+        assert_eq!(decode::<Odr>(encode(&Odr::Hz50)), Ok(Odr::Hz50));
+    }
+
+    #[test]
+    fn configs_round_trip() {
+        for i in 0..5 {
+            for raw in 0..=u8::MAX {
+                let mut regs = [0x0f, 0, 0, 0, 0];
+                regs[i] = raw;
+                if let Ok(config) = decode::<Config>(regs) {
+                    assert_eq!(
+                        decode::<Config>(encode(&config)),
+                        Ok(config),
+                        "{:02x?}",
+                        regs
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn registers_round_trip() {
+        check_bijective::<CtrlReg1>();
+        check_bijective::<CtrlReg2>();
+        check_bijective::<CtrlReg3>();
+        check_bijective::<CtrlReg4>();
+        check_bijective::<CtrlReg5>();
+        check_bijective::<FifoCtrl>();
+        check_bijective::<FifoSrc>();
+        check_bijective::<Int1Cfg>();
+        check_bijective::<Int1Duration>();
+        check_bijective::<Int1Src>();
+        check_bijective::<Int1TshXh>();
+        check_bijective::<LowOdr>();
+    }
+}
@@ -0,0 +1,331 @@
+//! Sensor configuration
+
+use crate::registers::{CtrlReg1, CtrlReg4, CtrlReg5};
+use crate::{Bandwidth, InvalidValue, Odr, Scale, Variant};
+
+/// Complete sensor configuration
+///
+/// A `Config` describes the contents of `CTRL_REG1` to `CTRL_REG5` and can be
+/// written to the sensor in one go with `L3gd20::new_with_config` or
+/// `L3gd20::apply_config`, without any read-modify-write cycles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// Output Data Rate
+    pub odr: Odr,
+    /// Low-pass cut-off
+    pub bandwidth: Bandwidth,
+    /// Full Scale Selection
+    pub scale: Scale,
+    /// Block Data Update: output registers are not updated until both the
+    /// low and the high byte have been read
+    pub bdu: bool,
+    /// Enabled axes
+    pub axes: AxesEnable,
+    /// Power mode
+    pub power: PowerMode,
+    /// Filters applied to the output registers
+    pub data_path: DataPath,
+}
+
+impl Default for Config {
+    /// Sensor powered up with all the axes enabled, 95 Hz data rate, lowest
+    /// cut-off, ±250 dps and no high-pass filter
+    fn default() -> Self {
+        Config {
+            odr: Odr::Hz95,
+            bandwidth: Bandwidth::Low,
+            scale: Scale::Dps250,
+            bdu: false,
+            axes: AxesEnable::ALL,
+            power: PowerMode::Normal,
+            data_path: DataPath::Lpf1,
+        }
+    }
+}
+
+impl Config {
+    /// Set the Output Data Rate
+    pub fn odr(mut self, odr: Odr) -> Self {
+        self.odr = odr;
+        self
+    }
+
+    /// Set the low-pass cut-off
+    pub fn bandwidth(mut self, bandwidth: Bandwidth) -> Self {
+        self.bandwidth = bandwidth;
+        self
+    }
+
+    /// Replace the bandwidth by the lowest equivalent setting at the data
+    /// rate, see `Bandwidth::normalized`
+    pub fn normalized(mut self) -> Self {
+        self.bandwidth = self.bandwidth.normalized(self.odr);
+        self
+    }
+
+    /// Set the Full Scale Selection
+    pub fn scale(mut self, scale: Scale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Enable or disable Block Data Update
+    pub fn bdu(mut self, bdu: bool) -> Self {
+        self.bdu = bdu;
+        self
+    }
+
+    /// Set the enabled axes
+    pub fn axes(mut self, axes: AxesEnable) -> Self {
+        self.axes = axes;
+        self
+    }
+
+    /// Set the power mode
+    pub fn power(mut self, power: PowerMode) -> Self {
+        self.power = power;
+        self
+    }
+
+    /// Set the filters applied to the output registers
+    pub fn data_path(mut self, data_path: DataPath) -> Self {
+        self.data_path = data_path;
+        self
+    }
+
+    /// Configuration of the sensor after power-on or boot, as documented in
+    /// the datasheet
+    ///
+    /// Note that the sensor is powered down in this configuration; unlike
+    /// `Config::default` which matches what `L3gd20::new` programs.
+    pub fn reset_defaults() -> Self {
+        Config {
+            power: PowerMode::PowerDown,
+            ..Config::default()
+        }
+    }
+
+    /// Curated configuration for a typical application on a L3GD20, see
+    /// `Preset`
+    ///
+    /// The result can be tweaked with the setters before being applied.
+    pub fn preset(preset: Preset) -> Self {
+        Config::preset_for(preset, Variant::L3gd20)
+    }
+
+    /// Curated configuration for a typical application on `variant`, see
+    /// `Preset`
+    pub fn preset_for(preset: Preset, variant: Variant) -> Self {
+        match preset {
+            Preset::FlightController => Config {
+                odr: Odr::Hz760,
+                bandwidth: Bandwidth::Maximum,
+                scale: Scale::Dps2000,
+                bdu: true,
+                data_path: DataPath::Lpf2,
+                ..Config::default()
+            },
+            Preset::LowPower => Config {
+                odr: match variant {
+                    Variant::L3gd20 => Odr::Hz95,
+                    Variant::L3gd20h => Odr::Hz12_5,
+                },
+                bandwidth: Bandwidth::Low,
+                scale: Scale::Dps250,
+                data_path: DataPath::Lpf2,
+                ..Config::default()
+            },
+            Preset::VibrationAnalysis => Config {
+                odr: Odr::Hz760,
+                bandwidth: Bandwidth::Maximum,
+                scale: Scale::Dps500,
+                data_path: DataPath::Lpf1,
+                ..Config::default()
+            },
+        }
+    }
+
+    /// Decode the values of `CTRL_REG1` to `CTRL_REG5`
+    ///
+    /// Settings not represented by `Config` are ignored. `Low_ODR` is assumed
+    /// to be clear, so the low data rates of the L3GD20H are not decoded.
+    pub fn from_registers(regs: [u8; 5]) -> Result<Self, InvalidValue> {
+        let ctrl1 = CtrlReg1::from_bits(regs[0]);
+        let ctrl4 = CtrlReg4::from_bits(regs[3]);
+        let ctrl5 = CtrlReg5::from_bits(regs[4]);
+
+        Ok(Config {
+            odr: ctrl1.odr()?,
+            bandwidth: ctrl1.bandwidth()?,
+            scale: ctrl4.scale()?,
+            bdu: ctrl4.bdu(),
+            axes: ctrl1.axes(),
+            power: ctrl1.power(),
+            data_path: ctrl5.data_path(),
+        })
+    }
+
+    /// Compare this configuration against register values read back from
+    /// the sensor, and the `Low_ODR` bit of `LOW_ODR`
+    pub(crate) fn diff(&self, actual: [u8; 5], low_odr: bool) -> Option<ConfigMismatch> {
+        // Bits of CTRL_REG1..CTRL_REG5 that are represented by `Config`
+        const KNOWN: [u8; 5] = [0xFF, 0x00, 0x00, 0b1011_0000, 0b0001_0011];
+
+        let expected = self.registers();
+        let other = expected
+            .iter()
+            .zip(actual.iter())
+            .zip(KNOWN.iter())
+            .any(|((e, a), k)| (e ^ a) & !k != 0);
+
+        // Fields are decoded individually so one undecodable field doesn't
+        // hide the others
+        let ctrl1 = CtrlReg1::from_bits(actual[0]);
+        let ctrl4 = CtrlReg4::from_bits(actual[3]);
+        let ctrl5 = CtrlReg5::from_bits(actual[4]);
+        let mismatch = ConfigMismatch {
+            odr: ctrl1.odr_for(low_odr) != self.odr,
+            bandwidth: ctrl1.bandwidth() != Ok(self.bandwidth),
+            scale: ctrl4.scale() != Ok(self.scale),
+            bdu: ctrl4.bdu() != self.bdu,
+            axes: ctrl1.axes() != self.axes,
+            power: ctrl1.power() != self.power,
+            data_path: ctrl5.data_path() != self.data_path,
+            other,
+            actual,
+        };
+
+        if mismatch.is_empty() {
+            None
+        } else {
+            Some(mismatch)
+        }
+    }
+
+    /// The values of `CTRL_REG1` to `CTRL_REG5` described by this
+    /// configuration
+    pub fn registers(&self) -> [u8; 5] {
+        let ctrl1 = CtrlReg1::default()
+            .with_odr(self.odr)
+            .with_bandwidth(self.bandwidth)
+            .with_power(self.power)
+            .with_axes(self.axes);
+        let ctrl4 = CtrlReg4::default()
+            .with_bdu(self.bdu)
+            .with_scale(self.scale);
+        let ctrl5 = CtrlReg5::default().with_data_path(self.data_path);
+
+        [ctrl1.bits(), 0, 0, ctrl4.bits(), ctrl5.bits()]
+    }
+}
+
+/// Typical applications, see `Config::preset`
+///
+/// The bandwidth only applies when low-pass filter 2 is in the data path;
+/// low-pass filter 1 is set by the data rate alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Fast control loops: 760 Hz, ±2000 dps so aggressive manoeuvres don't
+    /// saturate, low-pass filter 2 at its highest cut-off to limit aliasing
+    /// with the least delay, and Block Data Update so a sample read while
+    /// being updated isn't torn
+    FlightController,
+    /// Slow, low-noise monitoring: the lowest data rate (95 Hz, or 12.5 Hz
+    /// on the L3GD20H), ±250 dps and low-pass filter 2 at its lowest cut-off
+    LowPower,
+    /// Vibration spectra: 760 Hz and ±500 dps, with low-pass filter 1 only
+    /// and no high-pass filter so the widest band reaches the output
+    VibrationAnalysis,
+}
+
+/// Axes enable flags (`Xen`, `Yen` and `Zen` in `CTRL_REG1`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AxesEnable {
+    /// X-axis enabled
+    pub x: bool,
+    /// Y-axis enabled
+    pub y: bool,
+    /// Z-axis enabled
+    pub z: bool,
+}
+
+impl AxesEnable {
+    /// All axes enabled
+    pub const ALL: AxesEnable = AxesEnable {
+        x: true,
+        y: true,
+        z: true,
+    };
+
+    /// No axis enabled
+    pub const NONE: AxesEnable = AxesEnable {
+        x: false,
+        y: false,
+        z: false,
+    };
+}
+
+/// Power mode (`PD` in `CTRL_REG1`)
+///
+/// Normal mode with all the axes disabled corresponds to the sleep mode of the
+/// datasheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerMode {
+    /// Power-down
+    PowerDown = 0,
+    /// Normal mode (or sleep mode if no axis is enabled)
+    Normal = 1,
+}
+
+/// Filters in the output data path (`HPen` and `Out_Sel` in `CTRL_REG5`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataPath {
+    /// Low-pass filter 1 only
+    Lpf1,
+    /// Low-pass filter 1 and high-pass filter
+    Hpf,
+    /// Low-pass filters 1 and 2
+    Lpf2,
+    /// Low-pass filter 1, high-pass filter and low-pass filter 2
+    HpfLpf2,
+}
+
+/// Differences between an expected configuration and the one read back from
+/// the sensor
+///
+/// Each flag is set when the corresponding `Config` field doesn't match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigMismatch {
+    /// `Config::odr` differs
+    pub odr: bool,
+    /// `Config::bandwidth` differs
+    pub bandwidth: bool,
+    /// `Config::scale` differs
+    pub scale: bool,
+    /// `Config::bdu` differs
+    pub bdu: bool,
+    /// `Config::axes` differs
+    pub axes: bool,
+    /// `Config::power` differs
+    pub power: bool,
+    /// `Config::data_path` differs
+    pub data_path: bool,
+    /// Bits not represented by `Config` differ from their default value
+    pub other: bool,
+    /// Values of `CTRL_REG1` to `CTRL_REG5` read back from the sensor
+    pub actual: [u8; 5],
+}
+
+impl ConfigMismatch {
+    /// `true` if no field differs
+    pub fn is_empty(&self) -> bool {
+        !(self.odr
+            || self.bandwidth
+            || self.scale
+            || self.bdu
+            || self.axes
+            || self.power
+            || self.data_path
+            || self.other)
+    }
+}
@@ -0,0 +1,167 @@
+//! Affine correction of scale factor and cross-axis errors
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::{I32x3, L3gd20};
+
+/// One in the Q15 fixed-point format used by `AxisCorrection`
+pub const Q15_ONE: i32 = 1 << 15;
+
+/// Affine correction applied to the scaled measurements
+///
+/// The corrected rate is `matrix · (rate - offset)`, in the output frame
+/// (after the bias and the axis mapping). The correction is kept both in
+/// fixed point, for the integer read paths, and, with the `float` feature,
+/// in floating point for the others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisCorrection {
+    matrix_q15: [[i32; 3]; 3],
+    offset_mdps: I32x3,
+    #[cfg(feature = "float")]
+    matrix: [[f32; 3]; 3],
+    #[cfg(feature = "float")]
+    offset_dps: F32x3,
+}
+
+impl AxisCorrection {
+    /// Correction from a row-major Q15 `matrix`, where `Q15_ONE` is 1.0, and
+    /// an offset in milli-degrees per second
+    pub fn from_q15(matrix: [[i32; 3]; 3], offset_mdps: I32x3) -> Self {
+        AxisCorrection {
+            matrix_q15: matrix,
+            offset_mdps,
+            #[cfg(feature = "float")]
+            matrix: matrix.map(|row| row.map(|m| m as f32 / Q15_ONE as f32)),
+            #[cfg(feature = "float")]
+            offset_dps: F32x3 {
+                x: offset_mdps.x as f32 / 1000.0,
+                y: offset_mdps.y as f32 / 1000.0,
+                z: offset_mdps.z as f32 / 1000.0,
+            },
+        }
+    }
+
+    /// Correction from a row-major `matrix` and an offset in degrees per
+    /// second
+    ///
+    /// The integer read paths use the matrix rounded to Q15 and the offset
+    /// rounded to milli-degrees per second.
+    #[cfg(feature = "float")]
+    pub fn new(matrix: [[f32; 3]; 3], offset_dps: F32x3) -> Self {
+        let round = |v: f32| libm::roundf(v) as i32;
+        AxisCorrection {
+            matrix_q15: matrix.map(|row| row.map(|m| round(m * Q15_ONE as f32))),
+            offset_mdps: I32x3 {
+                x: round(offset_dps.x * 1000.0),
+                y: round(offset_dps.y * 1000.0),
+                z: round(offset_dps.z * 1000.0),
+            },
+            matrix,
+            offset_dps,
+        }
+    }
+
+    /// The matrix in Q15 format
+    pub fn matrix_q15(&self) -> [[i32; 3]; 3] {
+        self.matrix_q15
+    }
+
+    /// The offset in milli-degrees per second
+    pub fn offset_mdps(&self) -> I32x3 {
+        self.offset_mdps
+    }
+
+    /// The matrix
+    #[cfg(feature = "float")]
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        self.matrix
+    }
+
+    /// The offset in degrees per second
+    #[cfg(feature = "float")]
+    pub fn offset_dps(&self) -> F32x3 {
+        self.offset_dps
+    }
+
+    /// Correct a rate in milli-degrees per second
+    ///
+    /// Uses integer math only; the result is rounded to the nearest
+    /// milli-degree, with halves rounded away from zero, and saturates at the
+    /// limits of `i32`.
+    pub fn apply_mdps(&self, rate: I32x3) -> I32x3 {
+        let v = [
+            rate.x as i64 - self.offset_mdps.x as i64,
+            rate.y as i64 - self.offset_mdps.y as i64,
+            rate.z as i64 - self.offset_mdps.z as i64,
+        ];
+        let row = |r: [i32; 3]| {
+            let acc: i64 = r.iter().zip(v.iter()).map(|(&m, &c)| m as i64 * c).sum();
+            let half = if acc < 0 {
+                -(Q15_ONE as i64) / 2
+            } else {
+                Q15_ONE as i64 / 2
+            };
+            ((acc + half) / Q15_ONE as i64).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+        };
+        I32x3 {
+            x: row(self.matrix_q15[0]),
+            y: row(self.matrix_q15[1]),
+            z: row(self.matrix_q15[2]),
+        }
+    }
+
+    /// Correct a rate in degrees per second
+    #[cfg(feature = "float")]
+    pub fn apply_dps(&self, rate: F32x3) -> F32x3 {
+        let v = [
+            rate.x - self.offset_dps.x,
+            rate.y - self.offset_dps.y,
+            rate.z - self.offset_dps.z,
+        ];
+        let row = |r: [f32; 3]| r[0] * v[0] + r[1] * v[1] + r[2] * v[2];
+        F32x3 {
+            x: row(self.matrix[0]),
+            y: row(self.matrix[1]),
+            z: row(self.matrix[2]),
+        }
+    }
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Correct the scaled measurements for scale factor and cross-axis
+    /// errors, see `AxisCorrection`
+    ///
+    /// `matrix` is row-major and `offset` in degrees per second. The
+    /// correction is applied by `gyro_dps`, `gyro_rad_s` and `gyro_mdps`; the
+    /// raw and count read paths are not changed.
+    #[cfg(feature = "float")]
+    pub fn set_calibration(&mut self, matrix: [[f32; 3]; 3], offset: F32x3) -> &mut Self {
+        self.correction = Some(AxisCorrection::new(matrix, offset));
+        self
+    }
+
+    /// Same as `set_calibration` with a Q15 `matrix`, where `Q15_ONE` is 1.0,
+    /// and an offset in milli-degrees per second
+    pub fn set_calibration_q15(&mut self, matrix: [[i32; 3]; 3], offset_mdps: I32x3) -> &mut Self {
+        self.correction = Some(AxisCorrection::from_q15(matrix, offset_mdps));
+        self
+    }
+
+    /// Get the current correction, if any
+    pub fn calibration(&self) -> Option<AxisCorrection> {
+        self.correction
+    }
+
+    /// Remove the correction
+    pub fn clear_calibration(&mut self) -> &mut Self {
+        self.correction = None;
+        self
+    }
+}
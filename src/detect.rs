@@ -0,0 +1,301 @@
+//! Event detectors working on polled measurements
+//!
+//! Like the filters, the detectors work on fixed-size state and never
+//! allocate.
+
+use crate::{Accumulator, I16x3, I32x3};
+
+/// Threshold crossing event on one axis, see `ThresholdDetector`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The rate stayed above the threshold for the minimum duration
+    Started,
+    /// The event is still going on
+    Ongoing,
+    /// The rate dropped below the threshold minus the hysteresis
+    Ended,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct AxisState {
+    // consecutive samples above the threshold, while idle
+    above: u16,
+    active: bool,
+}
+
+impl AxisState {
+    fn update(
+        &mut self,
+        rate: i32,
+        threshold: i32,
+        hysteresis: i32,
+        duration: u16,
+    ) -> Option<Event> {
+        let magnitude = rate.saturating_abs();
+        if self.active {
+            if magnitude < threshold.saturating_sub(hysteresis) {
+                *self = AxisState::default();
+                Some(Event::Ended)
+            } else {
+                Some(Event::Ongoing)
+            }
+        } else if magnitude > threshold {
+            self.above = self.above.saturating_add(1);
+            if self.above >= duration {
+                self.active = true;
+                Some(Event::Started)
+            } else {
+                None
+            }
+        } else {
+            self.above = 0;
+            None
+        }
+    }
+}
+
+/// Software version of the threshold interrupt (`INT1`)
+///
+/// Fed measurements in milli-degrees per second, as returned by
+/// `L3gd20::gyro_mdps` or `Scale::mdps_vec`. An event starts on an axis once
+/// the absolute rate has been above the threshold of that axis for
+/// `duration` consecutive samples, and ends as soon as it drops below the
+/// threshold minus the hysteresis.
+#[derive(Debug, Clone)]
+pub struct ThresholdDetector {
+    thresholds: I32x3,
+    hysteresis: i32,
+    duration: u16,
+    axes: [AxisState; 3],
+}
+
+impl ThresholdDetector {
+    /// Creates a detector
+    ///
+    /// `thresholds` and `hysteresis` are in milli-degrees per second and
+    /// `duration` in samples; a `duration` of 0 is treated as 1.
+    pub fn new(thresholds: I32x3, hysteresis: i32, duration: u16) -> Self {
+        ThresholdDetector {
+            thresholds,
+            hysteresis: hysteresis.max(0),
+            duration: duration.max(1),
+            axes: [AxisState::default(); 3],
+        }
+    }
+
+    /// Feed a measurement, in milli-degrees per second
+    ///
+    /// Returns the event of each axis, `[x, y, z]`; `None` if there is no
+    /// event going on.
+    pub fn push(&mut self, rate: I32x3) -> [Option<Event>; 3] {
+        let rates = [rate.x, rate.y, rate.z];
+        let thresholds = [self.thresholds.x, self.thresholds.y, self.thresholds.z];
+
+        let mut events = [None; 3];
+        for (i, event) in events.iter_mut().enumerate() {
+            *event = self.axes[i].update(rates[i], thresholds[i], self.hysteresis, self.duration);
+        }
+        events
+    }
+
+    /// `true` if an event is going on on any axis
+    pub fn is_active(&self) -> bool {
+        self.axes.iter().any(|axis| axis.active)
+    }
+
+    /// Forget the samples seen so far and end all the events silently
+    pub fn reset(&mut self) {
+        self.axes = [AxisState::default(); 3];
+    }
+}
+
+/// Detects periods during which the sensor is stationary
+///
+/// Fed bias compensated measurements (e.g. from `L3gd20::gyro`), in raw
+/// counts. The sensor is declared still once the window of the last `N`
+/// samples is full and, for `hold` consecutive samples, the mean of every
+/// axis stayed within `max_mean` of zero and its variance under
+/// `max_variance`. A slow, steady rotation has a low variance but is caught
+/// by the mean limit.
+///
+/// While still, the mean of the window is the residual bias: adding it to
+/// the stored bias re-zeroes the sensor, see `bias_correction`.
+#[derive(Debug, Clone)]
+pub struct StillnessDetector<const N: usize> {
+    window: [I16x3; N],
+    next: usize,
+    len: usize,
+    acc: Accumulator,
+    max_mean: i16,
+    max_variance: u32,
+    hold: u32,
+    quiet: u32,
+}
+
+impl<const N: usize> StillnessDetector<N> {
+    /// Creates a detector
+    ///
+    /// `max_mean` is in counts, `max_variance` in counts squared and `hold`
+    /// in samples. `N` must be at least 1 and no larger than 65536.
+    pub const fn new(max_mean: i16, max_variance: u32, hold: u32) -> Self {
+        const { assert!(N > 0 && N <= 65536) };
+
+        StillnessDetector {
+            window: [I16x3 { x: 0, y: 0, z: 0 }; N],
+            next: 0,
+            len: 0,
+            acc: Accumulator::with_variance(),
+            max_mean,
+            max_variance,
+            hold,
+            quiet: 0,
+        }
+    }
+
+    /// Add a sample; returns `true` if the sensor is still
+    pub fn push(&mut self, sample: I16x3) -> bool {
+        if self.len == N {
+            self.acc.remove(self.window[self.next]);
+        } else {
+            self.len += 1;
+        }
+
+        self.window[self.next] = sample;
+        self.next = (self.next + 1) % N;
+        self.acc.push(sample);
+
+        if self.len == N && self.within_limits() {
+            self.quiet = self.quiet.saturating_add(1);
+        } else {
+            self.quiet = 0;
+        }
+        self.is_still()
+    }
+
+    fn within_limits(&self) -> bool {
+        let (mean, variance) = match (self.acc.mean(), self.acc.variance()) {
+            (Some(mean), Some(variance)) => (mean, variance),
+            _ => return false,
+        };
+        let means = [mean.x, mean.y, mean.z];
+        (0..3).all(|i| {
+            i32::from(means[i]).abs() <= i32::from(self.max_mean)
+                && variance[i] <= self.max_variance
+        })
+    }
+
+    /// `true` if the limits have been met for `hold` consecutive samples
+    pub fn is_still(&self) -> bool {
+        self.len == N && self.quiet > 0 && self.quiet >= self.hold
+    }
+
+    /// Mean of the window, to be added to the stored bias, if the sensor is
+    /// still
+    ///
+    /// The mean is truncated towards zero.
+    pub fn bias_correction(&self) -> Option<I16x3> {
+        if !self.is_still() {
+            return None;
+        }
+
+        self.acc.mean()
+    }
+
+    /// Discard all the samples
+    pub fn reset(&mut self) {
+        *self = Self::new(self.max_mean, self.max_variance, self.hold);
+    }
+}
+
+/// Extremes tracked by a `PeakTracker`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Peaks {
+    /// Lowest value of each axis
+    pub min: I16x3,
+    /// Highest value of each axis
+    pub max: I16x3,
+    /// Highest absolute value of each axis; `i16::MIN` counts as `i16::MAX`
+    pub peak: I16x3,
+    /// Number of samples seen
+    pub samples: u32,
+    /// Per-axis flags, `[x, y, z]`, set if a sample was within
+    /// `SATURATION_MARGIN` of the `i16` limits, i.e. the actual extreme may
+    /// have been higher than the one recorded
+    pub saturated: [bool; 3],
+}
+
+/// Records the extreme rates seen since the last reset
+///
+/// Works on raw counts, so is usable from any read path; the extremes can be
+/// converted with `Scale::mdps_vec`.
+#[derive(Debug, Clone)]
+pub struct PeakTracker {
+    peaks: Peaks,
+}
+
+impl Default for PeakTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PeakTracker {
+    /// Creates a tracker that hasn't seen any sample
+    pub const fn new() -> Self {
+        PeakTracker {
+            peaks: Peaks {
+                min: I16x3 {
+                    x: i16::MAX,
+                    y: i16::MAX,
+                    z: i16::MAX,
+                },
+                max: I16x3 {
+                    x: i16::MIN,
+                    y: i16::MIN,
+                    z: i16::MIN,
+                },
+                peak: I16x3 { x: 0, y: 0, z: 0 },
+                samples: 0,
+                saturated: [false; 3],
+            },
+        }
+    }
+
+    /// Record a sample
+    pub fn push(&mut self, sample: I16x3) {
+        let p = &mut self.peaks;
+        p.min = I16x3 {
+            x: p.min.x.min(sample.x),
+            y: p.min.y.min(sample.y),
+            z: p.min.z.min(sample.z),
+        };
+        p.max = I16x3 {
+            x: p.max.x.max(sample.x),
+            y: p.max.y.max(sample.y),
+            z: p.max.z.max(sample.z),
+        };
+        p.peak = I16x3 {
+            x: p.peak.x.max(sample.x.saturating_abs()),
+            y: p.peak.y.max(sample.y.saturating_abs()),
+            z: p.peak.z.max(sample.z.saturating_abs()),
+        };
+        p.samples = p.samples.saturating_add(1);
+        for (seen, railed) in p.saturated.iter_mut().zip(sample.saturated_axes()) {
+            *seen |= railed;
+        }
+    }
+
+    /// The extremes seen so far, or `None` if no sample has been pushed
+    pub fn snapshot(&self) -> Option<Peaks> {
+        if self.peaks.samples == 0 {
+            None
+        } else {
+            Some(self.peaks)
+        }
+    }
+
+    /// Forget the samples seen so far
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
@@ -0,0 +1,122 @@
+//! Duty-cycled polling
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::filter::Decimator;
+use crate::registers::{CtrlReg1, Register};
+use crate::{Error, I16x3, L3gd20, PowerMode};
+
+/// Default time allowed for the sensor to settle after power-up
+pub const DEFAULT_SETTLE_US: u32 = 50_000;
+
+/// Driver that keeps the sensor powered down between readings
+///
+/// Each call to `read` powers the sensor up, waits for it to settle, averages
+/// a few samples, powers it down again and then sleeps for the rest of the
+/// interval. The sensor is powered down when `new` returns and after every
+/// cycle, including failed ones.
+pub struct DutyCycled<SPI, CS> {
+    l3gd20: L3gd20<SPI, CS>,
+    interval_us: u32,
+    settle_us: u32,
+    samples: u16,
+    active_us: u32,
+}
+
+impl<SPI, CS, E> DutyCycled<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Wrap `l3gd20`, reading once every `interval_ms` milliseconds
+    ///
+    /// Each reading averages a single sample, after a settle time of
+    /// `DEFAULT_SETTLE_US`.
+    pub fn new(mut l3gd20: L3gd20<SPI, CS>, interval_ms: u32) -> Result<Self, Error<E>> {
+        set_power(&mut l3gd20, PowerMode::PowerDown)?;
+        Ok(DutyCycled {
+            l3gd20,
+            interval_us: interval_ms.saturating_mul(1000),
+            settle_us: DEFAULT_SETTLE_US,
+            samples: 1,
+            active_us: 0,
+        })
+    }
+
+    /// Set the time allowed for the sensor to settle after power-up
+    ///
+    /// See the turn-on time in the datasheet; filters in the data path may
+    /// need longer.
+    pub fn set_settle_us(&mut self, settle_us: u32) -> &mut Self {
+        self.settle_us = settle_us;
+        self
+    }
+
+    /// Set the number of consecutive samples averaged by each reading; 0 is
+    /// treated as 1
+    pub fn set_samples(&mut self, samples: u16) -> &mut Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Release the underlying driver; the sensor stays powered down
+    pub fn release(self) -> L3gd20<SPI, CS> {
+        self.l3gd20
+    }
+
+    /// Run one cycle and return the averaged measurement
+    ///
+    /// The stored bias is subtracted from the measurement. Returns after the
+    /// whole interval has elapsed, so calling it in a loop reads at the
+    /// configured rate.
+    pub fn read<D: DelayUs<u32>>(&mut self, delay: &mut D) -> Result<I16x3, Error<E>> {
+        let result = self.measure(delay);
+        let powered_down = set_power(&mut self.l3gd20, PowerMode::PowerDown);
+
+        let measurement = result?;
+        powered_down?;
+
+        delay.delay_us(self.interval_us.saturating_sub(self.active_us));
+        Ok(measurement)
+    }
+
+    fn measure<D: DelayUs<u32>>(&mut self, delay: &mut D) -> Result<I16x3, Error<E>> {
+        set_power(&mut self.l3gd20, PowerMode::Normal)?;
+        delay.delay_us(self.settle_us);
+
+        let mut decimator = Decimator::new(self.samples);
+        let average = self.l3gd20.gyro_decimated(&mut decimator, delay)?;
+
+        let sampling_us = u32::from(self.samples).saturating_mul(self.l3gd20.dt_us()?);
+        self.active_us = self.settle_us.saturating_add(sampling_us);
+        Ok(average)
+    }
+
+    /// Estimated fraction of the last cycle during which the sensor was
+    /// powered up, in thousandths
+    ///
+    /// Estimated from the settle time and the nominal sample period; the
+    /// time spent waiting for the first sample is not accounted for.
+    pub fn duty_cycle_permille(&self) -> u16 {
+        if self.interval_us == 0 {
+            return 1000;
+        }
+        let permille = u64::from(self.active_us) * 1000 / u64::from(self.interval_us);
+        permille.min(1000) as u16
+    }
+}
+
+fn set_power<SPI, CS, E>(l3gd20: &mut L3gd20<SPI, CS>, power: PowerMode) -> Result<(), Error<E>>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    l3gd20.verified(|l3gd20| {
+        l3gd20.change_config(Register::CTRL_REG1, |r| {
+            CtrlReg1::from_bits(r).with_power(power).bits()
+        })?;
+        Ok(())
+    })
+}
@@ -0,0 +1,208 @@
+//! embedded-hal 1.0 support
+//!
+//! The driver is written against the embedded-hal 0.2 traits. With the
+//! `eh1` feature, instead of `eh0`, the constructors take an embedded-hal
+//! 1.0 `SpiBus` and `OutputPin`, which `SpiCompat` and `PinCompat` adapt to
+//! them, so the rest of the driver API is shared between both trait
+//! generations:
+//!
+//! ``` ignore
+//! let mut l3gd20 = L3gd20::new(spi_bus, cs)?;
+//! let gyro = l3gd20.gyro()?;
+//! ```
+//!
+//! The chip select is driven by the driver, so the adapter takes a plain
+//! `SpiBus` rather than a `SpiDevice`.
+
+use core::convert::Infallible;
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2;
+use embedded_hal_1::digital::{self, OutputPin};
+use embedded_hal_1::spi::SpiBus;
+
+use crate::interface::{CsInterface, SpiInterface};
+use crate::NoCs;
+
+/// embedded-hal 1.0 `SpiBus` used through the embedded-hal 0.2 SPI traits
+///
+/// Every transfer is flushed before returning, so the chip select is never
+/// deasserted while the bus is still busy.
+pub struct SpiCompat<SPI>(pub SPI);
+
+// A single impl generic over the word conflicts with the blanket impls of
+// embedded-hal 0.2
+macro_rules! spi_compat {
+    ($($word:ty),*) => {$(
+        impl<SPI: SpiBus<$word>> spi::Transfer<$word> for SpiCompat<SPI> {
+            type Error = SPI::Error;
+
+            fn transfer<'w>(&mut self, words: &'w mut [$word]) -> Result<&'w [$word], SPI::Error> {
+                self.0.transfer_in_place(words)?;
+                self.0.flush()?;
+                Ok(words)
+            }
+        }
+
+        impl<SPI: SpiBus<$word>> spi::Write<$word> for SpiCompat<SPI> {
+            type Error = SPI::Error;
+
+            fn write(&mut self, words: &[$word]) -> Result<(), SPI::Error> {
+                self.0.write(words)?;
+                self.0.flush()
+            }
+        }
+    )*};
+}
+
+spi_compat!(u8, u16);
+
+/// embedded-hal 1.0 `OutputPin` used through the embedded-hal 0.2 trait
+pub struct PinCompat<PIN>(pub PIN);
+
+impl<PIN: OutputPin> v2::OutputPin for PinCompat<PIN> {
+    type Error = PIN::Error;
+
+    fn set_low(&mut self) -> Result<(), PIN::Error> {
+        self.0.set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), PIN::Error> {
+        self.0.set_high()
+    }
+}
+
+impl<SPI: SpiBus<W>, W: Copy + 'static> SpiInterface<W> for SPI {
+    type Spi = SpiCompat<SPI>;
+
+    fn into_spi(self) -> SpiCompat<SPI> {
+        SpiCompat(self)
+    }
+}
+
+impl<CS: OutputPin> CsInterface for CS {
+    type Cs = PinCompat<CS>;
+
+    fn into_cs(self) -> PinCompat<CS> {
+        PinCompat(self)
+    }
+}
+
+impl digital::ErrorType for NoCs {
+    type Error = Infallible;
+}
+
+impl OutputPin for NoCs {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{ReplayError, ReplaySpi};
+    use crate::testing::replay;
+    use crate::L3gd20;
+    use embedded_hal::blocking::spi::{Transfer, Write};
+    use embedded_hal_1::spi::{ErrorKind, ErrorType};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    type Log = Rc<RefCell<Vec<&'static str>>>;
+
+    /// embedded-hal 1.0 bus replaying a log and recording its calls
+    struct Bus<'a>(&'a mut ReplaySpi, Log);
+
+    fn kind(error: ReplayError) -> ErrorKind {
+        match error {
+            ReplayError::Injected => ErrorKind::Overrun,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    impl ErrorType for Bus<'_> {
+        type Error = ErrorKind;
+    }
+
+    impl SpiBus for Bus<'_> {
+        fn read(&mut self, _words: &mut [u8]) -> Result<(), ErrorKind> {
+            unimplemented!()
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), ErrorKind> {
+            self.1.borrow_mut().push("write");
+            Write::write(self.0, words).map_err(kind)
+        }
+
+        fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), ErrorKind> {
+            unimplemented!()
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), ErrorKind> {
+            self.1.borrow_mut().push("transfer");
+            Transfer::transfer(self.0, words).map(|_| ()).map_err(kind)
+        }
+
+        fn flush(&mut self) -> Result<(), ErrorKind> {
+            self.1.borrow_mut().push("flush");
+            Ok(())
+        }
+    }
+
+    /// embedded-hal 1.0 chip select recording its levels
+    struct Pin(Log);
+
+    impl digital::ErrorType for Pin {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Pin {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0.borrow_mut().push("low");
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0.borrow_mut().push("high");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn eh1_peripherals_drive_the_driver() {
+        let mut spi = replay(
+            "
+            T e8 00 00 00 00 00 00 -> 00 01 00 02 00 03 00
+            E
+            ",
+        );
+        let log = Log::default();
+        {
+            let bus = Bus(&mut spi, log.clone());
+            let mut l3gd20 = L3gd20::new(bus, Pin(log.clone())).unwrap();
+            let init = [
+                "high", // deselected
+                "low", "transfer", "flush", "high", // WHO_AM_I
+                "low", "write", "flush", "high", // CTRL_REG1
+                "low", "transfer", "flush", "high", // CTRL_REG1..CTRL_REG5
+            ];
+            assert_eq!(*log.borrow(), init);
+            log.borrow_mut().clear();
+
+            let gyro = l3gd20.gyro().unwrap();
+            assert_eq!((gyro.x, gyro.y, gyro.z), (1, 2, 3));
+            assert_eq!(*log.borrow(), ["low", "transfer", "flush", "high"]);
+            log.borrow_mut().clear();
+
+            assert_eq!(l3gd20.gyro().map(|_| ()), Err(ErrorKind::Overrun));
+            assert_eq!(*log.borrow(), ["low", "transfer", "high"]);
+        }
+        spi.finish().unwrap();
+    }
+}
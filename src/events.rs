@@ -0,0 +1,132 @@
+//! Polling of every event source at once
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::{FifoSrc, Int1Src, Register};
+use crate::{L3gd20, Status};
+
+/// Everything that happened since the sources were last read, see
+/// `L3gd20::events`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Events {
+    /// Data status
+    pub status: Status,
+    /// FIFO status
+    pub fifo: FifoSrc,
+    /// Interrupt 1 source; `None` if it wasn't read
+    pub int1: Option<Int1Src>,
+}
+
+impl Events {
+    /// New data is available on at least one axis
+    pub fn new_data(&self) -> bool {
+        self.status.any_new()
+    }
+
+    /// New data overwrote previously unread data
+    pub fn overrun(&self) -> bool {
+        self.status.any_overrun()
+    }
+
+    /// The FIFO filling reached the watermark level
+    pub fn fifo_watermark(&self) -> bool {
+        self.fifo.wtm()
+    }
+
+    /// The FIFO is full and at least one sample was overwritten
+    pub fn fifo_overrun(&self) -> bool {
+        self.fifo.ovrn()
+    }
+
+    /// An interrupt was generated; see `int1` for the axes and directions
+    pub fn interrupt(&self) -> bool {
+        self.int1.is_some_and(|src| src.ia())
+    }
+
+    /// `true` if nothing happened
+    pub fn is_empty(&self) -> bool {
+        !(self.new_data()
+            || self.overrun()
+            || self.fifo_watermark()
+            || self.fifo_overrun()
+            || self.interrupt())
+    }
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Read `STATUS_REG`, `FIFO_SRC_REG` and `INT1_SRC` and decode them
+    ///
+    /// Two transactions: the output registers lie between `STATUS_REG` and
+    /// the other two, and reading them would consume a sample. Reading
+    /// `INT1_SRC` clears a latched interrupt (see `LatchMode::Lir`), so that
+    /// transaction is never retried; use `events_keep_int1` to leave it
+    /// latched.
+    pub fn events(&mut self) -> Result<Events, E> {
+        self.read_events(true)
+    }
+
+    /// Same as `events` but doesn't read `INT1_SRC`, so latched interrupts
+    /// stay latched; `Events::int1` is `None`
+    pub fn events_keep_int1(&mut self) -> Result<Events, E> {
+        self.read_events(false)
+    }
+
+    fn read_events(&mut self, int1: bool) -> Result<Events, E> {
+        let status = self.status()?;
+
+        // FIFO_SRC_REG, INT1_CFG, INT1_SRC
+        let mut bytes = [0u8; 4];
+        let len = if int1 { 4 } else { 2 };
+        self.read_many(Register::FIFO_SRC_REG, &mut bytes[..len])?;
+
+        Ok(Events {
+            status,
+            fifo: FifoSrc::from_bits(bytes[1]),
+            int1: if int1 {
+                Some(Int1Src::from_bits(bytes[3]))
+            } else {
+                None
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::replay;
+    use crate::RetryPolicy;
+
+    #[test]
+    fn reads_of_int1_src_are_not_retried() {
+        let mut spi = replay(
+            "
+            T a7 00 -> 00 00            # STATUS_REG
+            E                           # FIFO_SRC_REG..INT1_SRC
+            T a7 00 -> 00 00
+            E                           # FIFO_SRC_REG
+            T ef 00 -> 00 20            # retried
+            E                           # INT1_SRC
+            E                           # INT1_CFG
+            T b0 00 -> 00 2a            # retried
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_retry(RetryPolicy { attempts: 1 });
+            assert!(l3gd20.events().is_err());
+            let events = l3gd20.events_keep_int1().unwrap();
+            assert!(events.fifo.empty());
+            assert_eq!(events.int1, None);
+            assert!(l3gd20.read_register(Register::INT1_SRC).is_err());
+            assert_eq!(l3gd20.read_register(Register::INT1_CFG).unwrap(), 0x2a);
+            assert_eq!(l3gd20.retry_count(), 2);
+        }
+        spi.finish().unwrap();
+    }
+}
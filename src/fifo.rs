@@ -0,0 +1,319 @@
+//! FIFO modes and the transitions between them
+
+use core::convert::TryFrom;
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::{FifoCtrl, Register};
+use crate::{decode, Error, InvalidValue, L3gd20, Variant};
+
+/// FIFO mode (`FM` in `FIFO_CTRL_REG`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FifoMode {
+    /// The FIFO is not used and stays empty
+    Bypass = 0b000,
+    /// Samples are collected until the FIFO is full, then collection stops
+    Fifo = 0b001,
+    /// Samples are collected continuously, the oldest being overwritten
+    Stream = 0b010,
+    /// Stream mode until an interrupt event, then FIFO mode
+    StreamToFifo = 0b011,
+    /// Bypass mode until an interrupt event, then stream mode
+    BypassToStream = 0b100,
+    /// Stream mode where reading the FIFO empties it, so only samples
+    /// collected since the last read are returned; L3GD20H only
+    DynamicStream = 0b110,
+    /// Bypass mode until an interrupt event, then FIFO mode; L3GD20H only
+    BypassToFifo = 0b111,
+}
+
+impl FifoMode {
+    /// `true` if the FIFO has to go through `Bypass` when switching from
+    /// `from` to `to`
+    ///
+    /// Only `Bypass` empties the FIFO and re-arms the triggers, so it's
+    /// required when leaving `Fifo` mode, which stops collecting once full,
+    /// and when entering a mode that starts collecting from an empty FIFO
+    /// (`Fifo`) or waits for a trigger (`StreamToFifo`, `BypassToStream`,
+    /// `BypassToFifo`). Switching to the current mode or from or to `Bypass`
+    /// never needs it.
+    pub const fn needs_bypass(from: FifoMode, to: FifoMode) -> bool {
+        if from as u8 == to as u8
+            || matches!(from, FifoMode::Bypass)
+            || matches!(to, FifoMode::Bypass)
+        {
+            return false;
+        }
+        matches!(from, FifoMode::Fifo)
+            || matches!(
+                to,
+                FifoMode::Fifo
+                    | FifoMode::StreamToFifo
+                    | FifoMode::BypassToStream
+                    | FifoMode::BypassToFifo
+            )
+    }
+
+    /// `true` if `variant` supports this mode
+    pub const fn is_supported_by(&self, variant: Variant) -> bool {
+        !matches!(self, FifoMode::DynamicStream | FifoMode::BypassToFifo)
+            || matches!(variant, Variant::L3gd20h)
+    }
+}
+
+impl TryFrom<u8> for FifoMode {
+    type Error = InvalidValue;
+
+    /// Convert the (already shifted) `FM` field of `FIFO_CTRL_REG`
+    fn try_from(value: u8) -> Result<Self, InvalidValue> {
+        match value {
+            x if x == FifoMode::Bypass as u8 => Ok(FifoMode::Bypass),
+            x if x == FifoMode::Fifo as u8 => Ok(FifoMode::Fifo),
+            x if x == FifoMode::Stream as u8 => Ok(FifoMode::Stream),
+            x if x == FifoMode::StreamToFifo as u8 => Ok(FifoMode::StreamToFifo),
+            x if x == FifoMode::BypassToStream as u8 => Ok(FifoMode::BypassToStream),
+            x if x == FifoMode::DynamicStream as u8 => Ok(FifoMode::DynamicStream),
+            x if x == FifoMode::BypassToFifo as u8 => Ok(FifoMode::BypassToFifo),
+            _ => Err(InvalidValue { value }),
+        }
+    }
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Fail with `Error::InvalidConfig` if the sensor doesn't support `mode`
+    fn check_fifo_mode(&self, mode: FifoMode) -> Result<(), Error<E>> {
+        if mode.is_supported_by(self.variant) {
+            Ok(())
+        } else {
+            Err(Error::InvalidConfig("the FIFO mode needs a L3GD20H"))
+        }
+    }
+
+    /// Get the current FIFO mode
+    pub fn fifo_mode(&mut self) -> Result<FifoMode, Error<E>> {
+        let reg = self.read_register(Register::FIFO_CTRL_REG)?;
+        decode(Register::FIFO_CTRL_REG, reg, |r| {
+            FifoMode::try_from(FifoCtrl::from_bits(r).fm())
+        })
+    }
+
+    /// Set the FIFO mode, going through `Bypass` if the transition requires
+    /// it (see `FifoMode::needs_bypass`)
+    ///
+    /// The current mode is read from `FIFO_CTRL_REG`; a value that isn't a
+    /// `FifoMode` is always left through `Bypass`. The watermark is
+    /// preserved. Fails with `Error::InvalidConfig`, without touching the
+    /// bus, if `mode` is not supported by the variant. Note that the FIFO is
+    /// only used if it is enabled, see `DataPath` and `FIFO_EN` in
+    /// `CTRL_REG5`.
+    pub fn set_fifo_mode(&mut self, mode: FifoMode) -> Result<&mut Self, Error<E>> {
+        self.check_fifo_mode(mode)?;
+        let current = FifoCtrl::from_bits(self.read_register(Register::FIFO_CTRL_REG)?);
+        let bypass = match FifoMode::try_from(current.fm()) {
+            Ok(from) => FifoMode::needs_bypass(from, mode),
+            Err(_) => true,
+        };
+
+        self.verified(|l3gd20| {
+            if bypass {
+                let reg = current.with_fm(FifoMode::Bypass as u8);
+                l3gd20.write_register(Register::FIFO_CTRL_REG, reg.bits())?;
+            }
+            let reg = current.with_fm(mode as u8);
+            l3gd20.write_register(Register::FIFO_CTRL_REG, reg.bits())
+        })?;
+        Ok(self)
+    }
+
+    /// Set the FIFO mode with a single write, without going through `Bypass`
+    ///
+    /// For callers that manage the transitions themselves. The watermark is
+    /// preserved. Fails like `set_fifo_mode` if `mode` is not supported.
+    pub fn set_fifo_mode_raw(&mut self, mode: FifoMode) -> Result<&mut Self, Error<E>> {
+        self.check_fifo_mode(mode)?;
+        self.verified(|l3gd20| {
+            l3gd20.change_config(Register::FIFO_CTRL_REG, |r| {
+                FifoCtrl::from_bits(r).with_fm(mode as u8).bits()
+            })?;
+            Ok(())
+        })?;
+        Ok(self)
+    }
+
+    /// Empty the FIFO and re-arm its trigger by going through `Bypass`, then
+    /// back to the current mode
+    ///
+    /// Fails with `Error::InvalidRegisterValue` without writing anything if
+    /// the current mode is not a `FifoMode`.
+    pub fn reset_fifo(&mut self) -> Result<&mut Self, Error<E>> {
+        let reg = self.read_register(Register::FIFO_CTRL_REG)?;
+        decode(Register::FIFO_CTRL_REG, reg, |r| {
+            FifoMode::try_from(FifoCtrl::from_bits(r).fm())
+        })?;
+
+        let bypass = FifoCtrl::from_bits(reg).with_fm(FifoMode::Bypass as u8);
+        self.verified(|l3gd20| {
+            l3gd20.write_register(Register::FIFO_CTRL_REG, bypass.bits())?;
+            l3gd20.write_register(Register::FIFO_CTRL_REG, reg)
+        })?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{replay, replay_h};
+    use crate::NoCs;
+
+    const MODES: [FifoMode; 7] = [
+        FifoMode::Bypass,
+        FifoMode::Fifo,
+        FifoMode::Stream,
+        FifoMode::StreamToFifo,
+        FifoMode::BypassToStream,
+        FifoMode::DynamicStream,
+        FifoMode::BypassToFifo,
+    ];
+
+    #[test]
+    fn transitions_needing_bypass() {
+        for &from in MODES.iter() {
+            for &to in MODES.iter() {
+                let expected = from != to
+                    && from != FifoMode::Bypass
+                    && to != FifoMode::Bypass
+                    && (from == FifoMode::Fifo
+                        || (to != FifoMode::Stream && to != FifoMode::DynamicStream));
+                assert_eq!(
+                    FifoMode::needs_bypass(from, to),
+                    expected,
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn modes_convert_from_u8() {
+        for &mode in MODES.iter() {
+            assert_eq!(FifoMode::try_from(mode as u8), Ok(mode));
+        }
+        for value in (8..=u8::MAX).chain(Some(0b101)) {
+            assert_eq!(FifoMode::try_from(value), Err(InvalidValue { value }));
+        }
+    }
+
+    #[test]
+    fn set_fifo_mode_goes_through_bypass() {
+        let mut spi = replay(
+            "
+            T ae 00 -> 00 2a    # Fifo, watermark 10
+            W 2e 0a             # Bypass
+            W 2e 4a             # Stream
+            T ae 00 -> 00 4a
+            W 2e 4a             # Stream again, directly
+            T ae 00 -> 00 4a
+            W 2e 0a             # Bypass, directly
+            T ae 00 -> 00 aa    # not a FifoMode
+            W 2e 0a
+            W 2e 2a
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_fifo_mode(FifoMode::Stream).unwrap();
+            l3gd20.set_fifo_mode(FifoMode::Stream).unwrap();
+            l3gd20.set_fifo_mode(FifoMode::Bypass).unwrap();
+            l3gd20.set_fifo_mode(FifoMode::Fifo).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn reset_fifo_restores_the_mode() {
+        let mut spi = replay(
+            "
+            T ae 00 -> 00 3f    # Fifo, watermark 31
+            W 2e 1f
+            W 2e 3f
+            T ae 00 -> 00 bf    # not a FifoMode
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.reset_fifo().unwrap();
+            let error = Error::InvalidRegisterValue {
+                reg: Register::FIFO_CTRL_REG,
+                value: 0xbf,
+            };
+            assert_eq!(l3gd20.reset_fifo().map(|_| ()), Err(error));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn fifo_mode_writes_are_verified() {
+        let mut spi = replay(
+            "
+            T ae 00 -> 00 2a    # Fifo, watermark 10
+            W 2e 0a
+            T ae 00 -> 00 0a
+            W 2e 4a
+            T ae 00 -> 00 0a    # still in Bypass
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_write_verification(true);
+            let error = Error::VerifyFailed {
+                reg: Register::FIFO_CTRL_REG,
+                wrote: 0x4a,
+                read: 0x0a,
+            };
+            let result = l3gd20.set_fifo_mode(FifoMode::Stream).map(|_| ());
+            assert_eq!(result, Err(error));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn l3gd20h_modes_need_a_l3gd20h() {
+        let mut spi = replay("");
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let error = Error::InvalidConfig("the FIFO mode needs a L3GD20H");
+            for &mode in &[FifoMode::DynamicStream, FifoMode::BypassToFifo] {
+                assert!(!mode.is_supported_by(Variant::L3gd20));
+                assert_eq!(l3gd20.set_fifo_mode(mode).map(|_| ()), Err(error));
+                assert_eq!(l3gd20.set_fifo_mode_raw(mode).map(|_| ()), Err(error));
+            }
+        }
+        spi.finish().unwrap();
+
+        let mut spi = replay_h(
+            "
+            T ae 00 -> 00 4a    # Stream, watermark 10
+            W 2e ca             # DynamicStream, directly
+            T ae 00 -> 00 ca
+            W 2e 0a             # Bypass
+            W 2e ea             # BypassToFifo
+            T ae 00 -> 00 ea
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_l3gd20h(&mut spi, NoCs).unwrap();
+            l3gd20.set_fifo_mode(FifoMode::DynamicStream).unwrap();
+            l3gd20.set_fifo_mode(FifoMode::BypassToFifo).unwrap();
+            assert_eq!(l3gd20.fifo_mode(), Ok(FifoMode::BypassToFifo));
+        }
+        spi.finish().unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+//! Filters for gyroscope measurements
+//!
+//! All the filters in this module work on fixed-size state and never
+//! allocate.
+
+use crate::{Accumulator, I16x3, Odr};
+#[cfg(feature = "float")]
+use crate::F32x3;
+
+/// Moving average over the last `N` samples
+///
+/// Until `N` samples have been pushed the average is taken over the samples
+/// seen so far.
+#[derive(Debug, Clone)]
+pub struct MovingAverage<const N: usize> {
+    window: [I16x3; N],
+    // index of the oldest sample, i.e. the next one to be replaced
+    next: usize,
+    len: usize,
+    sum: [i32; 3],
+}
+
+impl<const N: usize> Default for MovingAverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> MovingAverage<N> {
+    /// Creates an empty moving average
+    ///
+    /// `N` must be at least 1 and no larger than 65536, so the accumulators
+    /// can't overflow.
+    pub const fn new() -> Self {
+        const { assert!(N > 0 && N <= 65536) };
+
+        MovingAverage {
+            window: [I16x3 { x: 0, y: 0, z: 0 }; N],
+            next: 0,
+            len: 0,
+            sum: [0; 3],
+        }
+    }
+
+    /// Add a sample and return the average of the current window
+    pub fn push(&mut self, sample: I16x3) -> I16x3 {
+        if self.len == N {
+            let old = self.window[self.next];
+            self.sum[0] -= i32::from(old.x);
+            self.sum[1] -= i32::from(old.y);
+            self.sum[2] -= i32::from(old.z);
+        } else {
+            self.len += 1;
+        }
+
+        self.window[self.next] = sample;
+        self.next = (self.next + 1) % N;
+        self.sum[0] += i32::from(sample.x);
+        self.sum[1] += i32::from(sample.y);
+        self.sum[2] += i32::from(sample.z);
+
+        self.average()
+    }
+
+    /// Average of the current window, truncated towards zero
+    ///
+    /// Returns zero if no sample has been pushed yet.
+    pub fn average(&self) -> I16x3 {
+        if self.len == 0 {
+            return I16x3::default();
+        }
+
+        let len = self.len as i32;
+        I16x3 {
+            x: (self.sum[0] / len) as i16,
+            y: (self.sum[1] / len) as i16,
+            z: (self.sum[2] / len) as i16,
+        }
+    }
+
+    /// Number of samples in the window; `N` once warmed up
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// `true` if no sample has been pushed yet
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Discard all the samples
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Median-of-3 spike rejection
+///
+/// Each output is the per-axis median of a sample and its two neighbors, so
+/// an isolated spike is replaced by one of its neighbors while a step change
+/// goes through unchanged. The output lags the input by one sample: the
+/// output for a sample is returned when the next one is pushed.
+#[derive(Debug, Clone, Default)]
+pub struct OutlierFilter {
+    // the two last samples, oldest first
+    history: Option<(I16x3, I16x3)>,
+    first: Option<I16x3>,
+    rejected: u32,
+}
+
+impl OutlierFilter {
+    /// Creates a filter that hasn't seen any sample
+    pub const fn new() -> Self {
+        OutlierFilter {
+            history: None,
+            first: None,
+            rejected: 0,
+        }
+    }
+
+    /// Add a sample and return the filtered previous one
+    ///
+    /// Returns `None` for the first sample. The first sample is its own
+    /// left neighbor.
+    pub fn push(&mut self, sample: I16x3) -> Option<I16x3> {
+        let (before, middle) = match (self.history, self.first.take()) {
+            (Some(history), _) => history,
+            (None, Some(first)) => (first, first),
+            (None, None) => {
+                self.first = Some(sample);
+                return None;
+            }
+        };
+        self.history = Some((middle, sample));
+
+        let filtered = I16x3 {
+            x: median3(before.x, middle.x, sample.x),
+            y: median3(before.y, middle.y, sample.y),
+            z: median3(before.z, middle.z, sample.z),
+        };
+        if filtered != middle {
+            self.rejected = self.rejected.saturating_add(1);
+        }
+        Some(filtered)
+    }
+
+    /// Number of samples in which at least one axis was replaced
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    /// Discard the samples seen so far and reset the counter
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+fn median3(a: i16, b: i16, c: i16) -> i16 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+/// FIR filter with `TAPS` fixed-point coefficients
+///
+/// The coefficients are Q15 numbers, i.e. `32767` is (almost) 1.0, applied
+/// with `coefficients[0]` to the newest sample. The products are accumulated
+/// in 64 bits, so no coefficient set can overflow, then rounded to the
+/// nearest count and saturated to `i16`.
+#[derive(Debug, Clone)]
+pub struct Fir<const TAPS: usize> {
+    coefficients: [i16; TAPS],
+    window: [I16x3; TAPS],
+    // index of the oldest sample, i.e. the next one to be replaced
+    next: usize,
+    len: usize,
+}
+
+impl<const TAPS: usize> Fir<TAPS> {
+    /// Creates a filter from Q15 `coefficients`
+    ///
+    /// `TAPS` must be at least 1.
+    pub const fn new(coefficients: [i16; TAPS]) -> Self {
+        const { assert!(TAPS > 0) };
+
+        Fir {
+            coefficients,
+            window: [I16x3 { x: 0, y: 0, z: 0 }; TAPS],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a sample and return the filter output
+    ///
+    /// Returns `None` until `TAPS` samples have been pushed.
+    pub fn push(&mut self, sample: I16x3) -> Option<I16x3> {
+        self.window[self.next] = sample;
+        self.next = (self.next + 1) % TAPS;
+        if self.len < TAPS {
+            self.len += 1;
+            if self.len < TAPS {
+                return None;
+            }
+        }
+
+        let mut acc = [0i64; 3];
+        for (i, &c) in self.coefficients.iter().enumerate() {
+            // i-th newest sample
+            let s = self.window[(self.next + TAPS - 1 - i) % TAPS];
+            acc[0] += i64::from(c) * i64::from(s.x);
+            acc[1] += i64::from(c) * i64::from(s.y);
+            acc[2] += i64::from(c) * i64::from(s.z);
+        }
+
+        Some(I16x3 {
+            x: q15_to_i16(acc[0]),
+            y: q15_to_i16(acc[1]),
+            z: q15_to_i16(acc[2]),
+        })
+    }
+
+    /// Discard all the samples
+    pub fn reset(&mut self) {
+        *self = Self::new(self.coefficients);
+    }
+}
+
+/// Round a Q15 accumulator to the nearest integer, saturating to `i16`
+fn q15_to_i16(acc: i64) -> i16 {
+    let rounded = (acc + (1 << 14)) >> 15;
+    rounded.clamp(i64::from(i16::MIN), i64::from(i16::MAX)) as i16
+}
+
+/// Second order IIR section with the coefficients of the RBJ "Audio EQ
+/// Cookbook"
+///
+/// Requires the `float` feature. `fs` is the sample rate in Hz, e.g.
+/// `Odr::hz` of the configured data rate. Implemented in transposed direct
+/// form II.
+#[cfg(feature = "float")]
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    s1: f32,
+    s2: f32,
+}
+
+#[cfg(feature = "float")]
+impl Biquad {
+    /// Low-pass filter with cut-off `fc` Hz and quality factor `q`
+    /// (`0.7071` for a Butterworth response)
+    pub fn lowpass(fs: f32, fc: f32, q: f32) -> Self {
+        let (cos, alpha) = Self::prewarp(fs, fc, q);
+        let b1 = 1.0 - cos;
+        Self::normalized(b1 / 2.0, b1, b1 / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// High-pass filter with cut-off `fc` Hz and quality factor `q`
+    pub fn highpass(fs: f32, fc: f32, q: f32) -> Self {
+        let (cos, alpha) = Self::prewarp(fs, fc, q);
+        let b1 = -(1.0 + cos);
+        Self::normalized(-b1 / 2.0, b1, -b1 / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// Notch filter rejecting `f0` Hz; a higher quality factor `q` gives a
+    /// narrower notch
+    pub fn notch(fs: f32, f0: f32, q: f32) -> Self {
+        let (cos, alpha) = Self::prewarp(fs, f0, q);
+        Self::normalized(1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    fn prewarp(fs: f32, f: f32, q: f32) -> (f32, f32) {
+        let w0 = 2.0 * core::f32::consts::PI * f / fs;
+        (libm::cosf(w0), libm::sinf(w0) / (2.0 * q))
+    }
+
+    fn normalized(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Biquad {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+
+    /// Filter one sample
+    pub fn push(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.s1;
+        self.s1 = self.b1 * x - self.a1 * y + self.s2;
+        self.s2 = self.b2 * x - self.a2 * y;
+        y
+    }
+
+    /// Clear the state, as if only zeros had been filtered
+    pub fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+}
+
+/// The same `Biquad` applied to each axis
+///
+/// Requires the `float` feature.
+#[cfg(feature = "float")]
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad3 {
+    axes: [Biquad; 3],
+}
+
+#[cfg(feature = "float")]
+impl Biquad3 {
+    /// Creates a filter applying `biquad` to each axis
+    pub fn new(mut biquad: Biquad) -> Self {
+        biquad.reset();
+        Biquad3 { axes: [biquad; 3] }
+    }
+
+    /// Filter one sample
+    pub fn push_f32(&mut self, sample: F32x3) -> F32x3 {
+        F32x3 {
+            x: self.axes[0].push(sample.x),
+            y: self.axes[1].push(sample.y),
+            z: self.axes[2].push(sample.z),
+        }
+    }
+
+    /// Filter one sample in raw counts
+    ///
+    /// The output is rounded to the nearest count and saturated to `i16`.
+    pub fn push(&mut self, sample: I16x3) -> I16x3 {
+        let y = self.push_f32(F32x3::from(sample));
+        // `as` saturates
+        I16x3 {
+            x: libm::roundf(y.x) as i16,
+            y: libm::roundf(y.y) as i16,
+            z: libm::roundf(y.z) as i16,
+        }
+    }
+
+    /// Clear the state of every axis
+    pub fn reset(&mut self) {
+        for axis in &mut self.axes {
+            axis.reset();
+        }
+    }
+}
+
+/// Reduces the data rate by averaging blocks of consecutive samples
+///
+/// Averaging before dropping samples attenuates the frequencies that plain
+/// subsampling would alias into the output.
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    factor: u16,
+    block: Accumulator,
+}
+
+impl Decimator {
+    /// Creates a decimator averaging blocks of `factor` samples
+    ///
+    /// A `factor` of 0 is treated as 1.
+    pub const fn new(factor: u16) -> Self {
+        Decimator {
+            factor: if factor == 0 { 1 } else { factor },
+            block: Accumulator::new(),
+        }
+    }
+
+    /// Creates a decimator reducing the nominal rate of `odr` to about
+    /// `target_hz`
+    ///
+    /// The factor is `odr / target_hz` rounded to the nearest integer, and at
+    /// least 1; e.g. 95 Hz to 10 Hz averages blocks of 10 samples, for an
+    /// actual output rate of 9.5 Hz.
+    pub const fn for_rate(odr: Odr, target_hz: u16) -> Self {
+        let odr = odr.nominal_mhz();
+        let target = if target_hz == 0 { 1 } else { target_hz as u32 } * 1000;
+        let factor = (odr + target / 2) / target;
+        Self::new(factor as u16)
+    }
+
+    /// Number of samples averaged into each output
+    pub fn factor(&self) -> u16 {
+        self.factor
+    }
+
+    /// Number of samples accumulated towards the next output
+    pub fn pending(&self) -> u16 {
+        self.block.count() as u16
+    }
+
+    /// Add a sample; returns the average of the block it completes, if any
+    ///
+    /// The average is truncated towards zero.
+    pub fn push(&mut self, sample: I16x3) -> Option<I16x3> {
+        self.block.push(sample);
+
+        if self.block.count() == u32::from(self.factor) {
+            self.flush()
+        } else {
+            None
+        }
+    }
+
+    /// Average of the samples of the incomplete block, if any, and start a
+    /// new block
+    pub fn flush(&mut self) -> Option<I16x3> {
+        let average = self.block.mean()?;
+        self.reset();
+        Some(average)
+    }
+
+    /// Discard the samples of the incomplete block
+    pub fn reset(&mut self) {
+        self.block.reset();
+    }
+}
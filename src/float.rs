@@ -0,0 +1,815 @@
+//! Floating point conversions
+//!
+//! Everything in this module requires the `float` feature, which is enabled
+//! by default.
+
+use core::fmt;
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::{CtrlReg1, Register};
+use crate::{
+    decode, noise, Accumulator, AngleIntegrator, Axis, Bandwidth, Error, I16x3, L3gd20,
+    NoiseReport, Odr, Scale, Variant, VibrationReport,
+};
+
+/// Sensitivity at `Scale::Dps250`, in degrees per second per LSB
+pub const SENSITIVITY_DPS250: f32 = Scale::Dps250.sensitivity_udps() as f32 / 1_000_000.0;
+/// Sensitivity at `Scale::Dps500`, in degrees per second per LSB
+pub const SENSITIVITY_DPS500: f32 = Scale::Dps500.sensitivity_udps() as f32 / 1_000_000.0;
+/// Sensitivity at `Scale::Dps2000`, in degrees per second per LSB
+pub const SENSITIVITY_DPS2000: f32 = Scale::Dps2000.sensitivity_udps() as f32 / 1_000_000.0;
+
+pub(crate) const DEG_TO_RAD: f32 = core::f32::consts::PI / 180.0;
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Integrate `n_samples` measurements into `integrator`
+    ///
+    /// Each measurement is taken as soon as `STATUS_REG` reports new data;
+    /// the time step is derived from the current `Odr`.
+    pub fn integrate_for<D: DelayUs<u32>>(
+        &mut self,
+        n_samples: u16,
+        delay: &mut D,
+        integrator: &mut AngleIntegrator,
+    ) -> Result<(), Error<E>> {
+        let odr = self.odr()?;
+        for _ in 0..n_samples {
+            self.wait_data_ready(delay)?;
+            let rate = self.gyro_dps()?;
+            integrator.update_odr(rate, odr);
+        }
+        Ok(())
+    }
+
+    /// Measure the noise of the sensor over `samples` measurements
+    ///
+    /// The measurements are read in chunks of 16 consecutive ones, see
+    /// `read_exact`. The statistics are computed on the raw measurements,
+    /// i.e. in the physical sensor frame and without bias compensation, so
+    /// the sensor should be kept still.
+    pub fn measure_noise<D: DelayUs<u32>>(
+        &mut self,
+        samples: u16,
+        delay: &mut D,
+    ) -> Result<NoiseReport, Error<E>> {
+        let (odr, bandwidth) = self.odr_bandwidth()?;
+        let scale = self.current_scale()?;
+
+        let mut stats = noise::NoiseStats::new();
+        self.for_each_raw(samples, delay, |raw| stats.push(raw))?;
+
+        Ok(stats.report(odr, bandwidth, scale))
+    }
+
+    /// Measure the RMS vibration over `window_samples` measurements
+    ///
+    /// The measurements are read as for `measure_noise`, in the physical
+    /// sensor frame. The mean of the window is removed before the RMS is
+    /// computed, so a slow steady rotation (or the bias) doesn't inflate it.
+    pub fn measure_vibration<D: DelayUs<u32>>(
+        &mut self,
+        window_samples: u16,
+        delay: &mut D,
+    ) -> Result<VibrationReport, Error<E>> {
+        let (odr, bandwidth) = self.odr_bandwidth()?;
+        let scale = self.current_scale()?;
+
+        let mut acc = Accumulator::with_variance();
+        self.for_each_raw(window_samples, delay, |raw| acc.push(raw))?;
+
+        Ok(noise::vibration_report(&acc, odr, bandwidth, scale))
+    }
+
+    /// Read `samples` consecutive raw measurements, in chunks of 16, and
+    /// pass them to `f`
+    ///
+    /// Fails with `Error::Overrun` if a sample is lost after the first one,
+    /// including between two chunks.
+    pub(crate) fn for_each_raw<D: DelayUs<u32>>(
+        &mut self,
+        samples: u16,
+        delay: &mut D,
+        mut f: impl FnMut(I16x3),
+    ) -> Result<(), Error<E>> {
+        let mut chunk = [I16x3::default(); 16];
+        let mut remaining = usize::from(samples);
+        while remaining > 0 {
+            let len = remaining.min(chunk.len());
+            let continuing = remaining < usize::from(samples);
+            self.read_exact_raw(&mut chunk[..len], delay, continuing)?;
+            chunk[..len].iter().for_each(|&raw| f(raw));
+            remaining -= len;
+        }
+        Ok(())
+    }
+
+    /// Data rate and bandwidth, decoded from a single read of `CTRL_REG1`
+    fn odr_bandwidth(&mut self) -> Result<(Odr, Bandwidth), Error<E>> {
+        let reg1 = self.read_register(Register::CTRL_REG1)?;
+        let odr = self.odr_of(reg1);
+        let bandwidth = decode(Register::CTRL_REG1, reg1, |r| CtrlReg1::from_bits(r).bandwidth())?;
+        Ok((odr, bandwidth))
+    }
+
+    /// Gyroscope measurements converted to degrees per second
+    ///
+    /// This uses the cached `Scale` (see `cached_scale`). The bias is
+    /// subtracted before the conversion: if a `BiasModel` is set it is
+    /// evaluated at the temperature read in the same burst as the gyroscope
+    /// data, otherwise the stored bias is used. The `AxisCorrection`, if
+    /// any, is applied after the conversion.
+    pub fn gyro_dps(&mut self) -> Result<F32x3, Error<E>> {
+        let scale = self.current_scale()?;
+        Ok(self.gyro_dps_at(scale)?)
+    }
+
+    /// `gyro_dps` converted with `scale`
+    pub(crate) fn gyro_dps_at(&mut self, scale: Scale) -> Result<F32x3, E> {
+        let (gyro, temp) = self.read_all_raw()?;
+
+        let dps = match self.bias_model {
+            Some(model) => self
+                .mapping
+                .apply_f32(model.compensate(gyro, temp))
+                .scale(scale.degrees_per_count()),
+            None => scale.degrees_vec(&self.adjust(gyro)),
+        };
+        Ok(match self.correction {
+            Some(correction) => correction.apply_dps(dps),
+            None => dps,
+        })
+    }
+
+    /// Gyroscope measurement of a single output axis converted to degrees
+    /// per second
+    ///
+    /// See `gyro_axis`. Only the constant bias is subtracted; the
+    /// temperature dependent `BiasModel` is not used.
+    pub fn gyro_axis_dps(&mut self, axis: Axis) -> Result<f32, Error<E>> {
+        let scale = self.current_scale()?;
+        Ok(scale.degrees(self.gyro_axis(axis)?))
+    }
+
+    /// Magnitude of the rotation rate, in degrees per second
+    ///
+    /// This is the norm of the `gyro_dps` measurement.
+    pub fn rotation_rate_dps(&mut self) -> Result<f32, Error<E>> {
+        Ok(self.gyro_dps()?.norm())
+    }
+
+    /// Gyroscope measurements as `uom` angular velocities, `[x, y, z]`
+    ///
+    /// Bias compensation works the same as for `gyro_dps`
+    #[cfg(feature = "uom")]
+    pub fn gyro_angular_velocity(
+        &mut self,
+    ) -> Result<[uom::si::f32::AngularVelocity; 3], Error<E>> {
+        use uom::si::angular_velocity::degree_per_second;
+        use uom::si::f32::AngularVelocity;
+
+        let dps = self.gyro_dps()?;
+        Ok([
+            AngularVelocity::new::<degree_per_second>(dps.x),
+            AngularVelocity::new::<degree_per_second>(dps.y),
+            AngularVelocity::new::<degree_per_second>(dps.z),
+        ])
+    }
+
+    /// Set the interrupt 1 threshold of the physical `axis` as a `uom`
+    /// angular velocity
+    ///
+    /// Same as `set_interrupt_threshold_mdps`, with `threshold` rounded to
+    /// the nearest milli-degree per second. Fails with `Error::InvalidConfig`
+    /// if `threshold` is negative or not a number.
+    #[cfg(feature = "uom")]
+    pub fn set_interrupt_threshold_angular_velocity(
+        &mut self,
+        axis: Axis,
+        threshold: uom::si::f32::AngularVelocity,
+    ) -> Result<&mut Self, Error<E>> {
+        use uom::si::angular_velocity::degree_per_second;
+
+        let mdps = threshold.get::<degree_per_second>() * 1000.0;
+        if mdps.is_nan() || mdps < 0.0 {
+            return Err(Error::InvalidConfig("thresholds can't be negative"));
+        }
+        // `as` saturates, so thresholds beyond `u32::MAX` mdps are still
+        // reported as beyond the full scale
+        self.set_interrupt_threshold_mdps(axis, (mdps + 0.5) as u32)
+    }
+
+    /// Gyroscope measurements as a `nalgebra` vector, in radians per second
+    ///
+    /// Bias compensation works the same as for `gyro_dps`
+    #[cfg(feature = "nalgebra")]
+    pub fn gyro_vector(&mut self) -> Result<nalgebra::Vector3<f32>, Error<E>> {
+        Ok(self.gyro_rad_s()?.into())
+    }
+
+    /// Gyroscope measurements converted to radians per second
+    ///
+    /// Bias compensation works the same as for `gyro_dps`
+    pub fn gyro_rad_s(&mut self) -> Result<F32x3, Error<E>> {
+        Ok(self.gyro_dps()?.scale(DEG_TO_RAD))
+    }
+
+    /// Set a temperature dependent bias model
+    ///
+    /// While a model is set it takes precedence over the constant bias in the
+    /// scaled read paths (e.g. `gyro_dps`). The integer read paths keep using
+    /// the constant bias.
+    pub fn set_bias_model(&mut self, model: BiasModel) -> &mut Self {
+        self.bias_model = Some(model);
+        self
+    }
+
+    /// Get the current temperature dependent bias model, if any
+    pub fn bias_model(&self) -> Option<BiasModel> {
+        self.bias_model
+    }
+
+    /// Remove the temperature dependent bias model
+    pub fn clear_bias_model(&mut self) -> &mut Self {
+        self.bias_model = None;
+        self
+    }
+
+    /// Set the Output Data Rate from a frequency in Hz
+    ///
+    /// A rate supported by the variant of the sensor is chosen according to
+    /// `rounding` and returned, see `Odr::from_hz_for`; on the L3GD20H this
+    /// includes the low data rates. Fails with `Error::UnsupportedRate` if no
+    /// rate satisfies the request, e.g. `AtLeast` 1000 Hz, or if `hz` is not
+    /// a positive, finite number.
+    pub fn set_odr_hz(&mut self, hz: f32, rounding: RateRounding) -> Result<Odr, Error<E>> {
+        let odr = Odr::from_hz_for(hz, rounding, self.variant).ok_or(Error::UnsupportedRate)?;
+        self.set_odr(odr)?;
+        Ok(odr)
+    }
+
+    /// Set the smallest full scale that covers `max_expected_dps` plus a
+    /// margin
+    ///
+    /// `headroom` is the margin as a fraction of the expected rate, e.g.
+    /// `0.2` for 20 %. The scale is changed through `set_scale`, so the bias
+    /// is rescaled or cleared according to the `BiasPolicy`. Fails with
+    /// `Error::InvalidConfig` if `headroom` is negative or not a number, or
+    /// if even ±2000 dps doesn't cover the request; the scale is unchanged
+    /// then.
+    pub fn set_scale_for(
+        &mut self,
+        max_expected_dps: f32,
+        headroom: f32,
+    ) -> Result<Scale, Error<E>> {
+        if headroom.is_nan() || headroom < 0.0 {
+            return Err(Error::InvalidConfig("headroom must be a non-negative fraction"));
+        }
+
+        let scale = Scale::fitting(max_expected_dps * (1.0 + headroom))
+            .ok_or(Error::InvalidConfig("no full scale covers the expected rate"))?;
+        self.set_scale(scale)?;
+        Ok(scale)
+    }
+
+    /// Get the low-pass cut-off frequency, in Hz, resulting from the current
+    /// `Odr` and `Bandwidth` settings
+    ///
+    /// Settings that duplicate a lower one (see `Bandwidth::normalized`)
+    /// report the cut-off they actually select.
+    pub fn effective_bandwidth_hz(&mut self) -> Result<f32, Error<E>> {
+        let reg1 = self.read_register(Register::CTRL_REG1)?;
+        let odr = self.odr_of(reg1);
+        let bw = decode(Register::CTRL_REG1, reg1, |r| CtrlReg1::from_bits(r).bandwidth())?;
+        Ok(bw.cutoff_hz(odr))
+    }
+}
+
+impl Odr {
+    /// All data rates, from slowest to fastest; the first three are the low
+    /// data rates of the L3GD20H
+    const ALL: [Odr; 7] = [
+        Odr::Hz12_5,
+        Odr::Hz25,
+        Odr::Hz50,
+        Odr::Hz95,
+        Odr::Hz190,
+        Odr::Hz380,
+        Odr::Hz760,
+    ];
+
+    /// Data rates supported by `variant`, from slowest to fastest
+    fn supported_by(variant: Variant) -> &'static [Odr] {
+        match variant {
+            Variant::L3gd20 => &Odr::ALL[3..],
+            Variant::L3gd20h => &Odr::ALL,
+        }
+    }
+
+    /// Nominal data rate in Hz
+    pub const fn hz(&self) -> f32 {
+        match *self {
+            Odr::Hz12_5 => 12.5,
+            Odr::Hz25 => 25.0,
+            Odr::Hz50 => 50.0,
+            Odr::Hz95 => 95.0,
+            Odr::Hz190 => 190.0,
+            Odr::Hz380 => 380.0,
+            Odr::Hz760 => 760.0,
+        }
+    }
+
+    /// The data rate of the L3GD20 nearest to `hz`
+    ///
+    /// Only the rates of the L3GD20, 95 Hz and up, are considered: 12.5 Hz
+    /// gives `Odr::Hz95`. Use `from_hz_for` with `Variant::L3gd20h` to
+    /// include the low data rates of the L3GD20H.
+    ///
+    /// Returns `None` if `hz` is not a positive, finite number
+    pub fn from_hz(hz: f32) -> Option<Odr> {
+        Odr::from_hz_for(hz, RateRounding::Nearest, Variant::L3gd20)
+    }
+
+    /// The data rate of the L3GD20 chosen for `hz` according to `rounding`
+    ///
+    /// Only the rates of the L3GD20 are considered, see `from_hz`; use
+    /// `from_hz_for` for the L3GD20H.
+    ///
+    /// Returns `None` if `hz` is not a positive, finite number or if no rate
+    /// satisfies `rounding`
+    pub fn from_hz_rounded(hz: f32, rounding: RateRounding) -> Option<Odr> {
+        Odr::from_hz_for(hz, rounding, Variant::L3gd20)
+    }
+
+    /// The data rate supported by `variant` chosen for `hz` according to
+    /// `rounding`
+    ///
+    /// Returns `None` if `hz` is not a positive, finite number or if no rate
+    /// satisfies `rounding`
+    pub fn from_hz_for(hz: f32, rounding: RateRounding, variant: Variant) -> Option<Odr> {
+        if !(hz.is_finite() && hz > 0.0) {
+            return None;
+        }
+
+        let rates = Odr::supported_by(variant);
+        match rounding {
+            RateRounding::Nearest => {
+                let mut best = rates[0];
+                for &odr in rates.iter() {
+                    if (odr.hz() - hz).abs() < (best.hz() - hz).abs() {
+                        best = odr;
+                    }
+                }
+                Some(best)
+            }
+            RateRounding::AtLeast => rates.iter().copied().find(|odr| odr.hz() >= hz),
+            RateRounding::AtMost => rates.iter().rev().copied().find(|odr| odr.hz() <= hz),
+        }
+    }
+}
+
+/// How a requested frequency is mapped to a supported data rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateRounding {
+    /// Nearest supported rate
+    Nearest,
+    /// Slowest supported rate at or above the requested one
+    AtLeast,
+    /// Fastest supported rate at or below the requested one
+    AtMost,
+}
+
+impl Bandwidth {
+    const ALL: [Bandwidth; 4] = [
+        Bandwidth::Low,
+        Bandwidth::Medium,
+        Bandwidth::High,
+        Bandwidth::Maximum,
+    ];
+
+    /// Low-pass cut-off frequency in Hz when running at `odr`
+    ///
+    /// Values are taken from the "DR and BW configuration setting" table of
+    /// the datasheet. At the low data rates of the L3GD20H the setting is
+    /// ignored: 50 Hz has a cut-off of 16.6 Hz, and the datasheet gives none
+    /// for 12.5 and 25 Hz, which report the Nyquist frequency (half the data
+    /// rate).
+    pub const fn cutoff_hz(&self, odr: Odr) -> f32 {
+        match (odr, *self) {
+            (Odr::Hz12_5, _) => 6.25,
+            (Odr::Hz25, _) => 12.5,
+            (Odr::Hz50, _) => 16.6,
+            (Odr::Hz95, Bandwidth::Low) => 12.5,
+            (Odr::Hz95, _) => 25.0,
+            (Odr::Hz190, Bandwidth::Low) => 12.5,
+            (Odr::Hz190, Bandwidth::Medium) => 25.0,
+            (Odr::Hz190, Bandwidth::High) => 50.0,
+            (Odr::Hz190, Bandwidth::Maximum) => 70.0,
+            (Odr::Hz380, Bandwidth::Low) => 20.0,
+            (Odr::Hz380, Bandwidth::Medium) => 25.0,
+            (Odr::Hz380, Bandwidth::High) => 50.0,
+            (Odr::Hz380, Bandwidth::Maximum) => 100.0,
+            (Odr::Hz760, Bandwidth::Low) => 30.0,
+            (Odr::Hz760, Bandwidth::Medium) => 35.0,
+            (Odr::Hz760, Bandwidth::High) => 50.0,
+            (Odr::Hz760, Bandwidth::Maximum) => 100.0,
+        }
+    }
+
+    /// The `Bandwidth` whose cut-off at `odr` is nearest to `desired_hz`
+    ///
+    /// If several settings result in the same cut-off the lowest one is
+    /// returned.
+    pub fn for_cutoff(odr: Odr, desired_hz: f32) -> Bandwidth {
+        let mut best = Bandwidth::Low;
+        for &bw in Bandwidth::ALL.iter() {
+            if (bw.cutoff_hz(odr) - desired_hz).abs() < (best.cutoff_hz(odr) - desired_hz).abs() {
+                best = bw;
+            }
+        }
+        best
+    }
+}
+
+impl Scale {
+    /// All full scales, from finest to coarsest
+    const ALL: [Scale; 3] = [Scale::Dps250, Scale::Dps500, Scale::Dps2000];
+
+    /// The smallest full scale whose range covers `max_expected_dps`
+    ///
+    /// The sign is ignored and the nominal range is inclusive, so 250 dps
+    /// still fits `Dps250`. Returns `None` if `max_expected_dps` is beyond
+    /// ±2000 dps or not a number.
+    pub fn fitting(max_expected_dps: f32) -> Option<Scale> {
+        let dps = if max_expected_dps < 0.0 {
+            -max_expected_dps
+        } else {
+            max_expected_dps
+        };
+
+        Scale::ALL
+            .iter()
+            .copied()
+            .find(|scale| dps <= scale.max_dps() as f32)
+    }
+
+    /// Convert a measurement to degrees
+    pub fn degrees(&self, val: i16) -> f32 {
+        val as f32 * self.degrees_per_count()
+    }
+
+    /// Convert a measurement to radians
+    pub fn radians(&self, val: i16) -> f32 {
+        // TODO: Use `to_radians` or other built in method
+        // NOTE: `to_radians` is only exported in `std` (07.02.18)
+        self.degrees(val) * DEG_TO_RAD
+    }
+
+    /// Convert all three axes of a measurement to degrees
+    pub fn degrees_vec(&self, v: &I16x3) -> F32x3 {
+        F32x3 {
+            x: self.degrees(v.x),
+            y: self.degrees(v.y),
+            z: self.degrees(v.z),
+        }
+    }
+
+    /// Convert all three axes of a measurement to radians
+    pub fn radians_vec(&self, v: &I16x3) -> F32x3 {
+        F32x3 {
+            x: self.radians(v.x),
+            y: self.radians(v.y),
+            z: self.radians(v.z),
+        }
+    }
+
+    /// Sensitivity in degrees per second per LSB
+    pub(crate) fn degrees_per_count(&self) -> f32 {
+        match *self {
+            Scale::Dps250 => SENSITIVITY_DPS250,
+            Scale::Dps500 => SENSITIVITY_DPS500,
+            Scale::Dps2000 => SENSITIVITY_DPS2000,
+        }
+    }
+}
+
+/// XYZ triple of scaled values
+///
+/// The layout is that of `[f32; 3]`: 12 bytes, 4-byte aligned, no padding.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct F32x3 {
+    /// X component
+    pub x: f32,
+    /// Y component
+    pub y: f32,
+    /// Z component
+    pub z: f32,
+}
+
+impl F32x3 {
+    /// Euclidean norm
+    pub fn norm(&self) -> f32 {
+        libm::sqrtf(self.x * self.x + self.y * self.y + self.z * self.z)
+    }
+
+    /// Multiply every component by `factor`
+    pub(crate) fn scale(self, factor: f32) -> Self {
+        F32x3 {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+}
+
+impl fmt::Display for F32x3 {
+    /// Signed components, e.g. `[+12.3, -0.4, +998.1]`
+    ///
+    /// The precision of the formatter, if any, applies to every component.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match f.precision() {
+            Some(p) => write!(f, "[{:+.*}, {:+.*}, {:+.*}]", p, self.x, p, self.y, p, self.z),
+            None => write!(f, "[{:+}, {:+}, {:+}]", self.x, self.y, self.z),
+        }
+    }
+}
+
+impl From<I16x3> for F32x3 {
+    fn from(v: I16x3) -> Self {
+        F32x3 {
+            x: v.x as f32,
+            y: v.y as f32,
+            z: v.z as f32,
+        }
+    }
+}
+
+/// Temperature dependent bias model
+///
+/// The bias of each axis is modelled as `offset + coefficient * temp`, in raw
+/// counts, where `temp` is the temperature reading as returned by
+/// `L3gd20::temp`. Like the constant bias, the model depends on the `Scale`
+/// that was active during characterization.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BiasModel {
+    /// Bias at a temperature reading of zero, in counts
+    pub offset: F32x3,
+    /// Change of the bias per unit of temperature reading, in counts
+    pub coefficient: F32x3,
+}
+
+impl BiasModel {
+    /// Subtract the bias at `temp` from a raw measurement
+    fn compensate(&self, gyro: I16x3, temp: i8) -> F32x3 {
+        let bias = self.bias_at(temp);
+        F32x3 {
+            x: gyro.x as f32 - bias.x,
+            y: gyro.y as f32 - bias.y,
+            z: gyro.z as f32 - bias.z,
+        }
+    }
+
+    /// Evaluate the model at the temperature reading `temp`
+    pub fn bias_at(&self, temp: i8) -> F32x3 {
+        let t = temp as f32;
+        F32x3 {
+            x: self.offset.x + self.coefficient.x * t,
+            y: self.offset.y + self.coefficient.y * t,
+            z: self.offset.z + self.coefficient.z * t,
+        }
+    }
+}
+
+/// Build a `BiasModel` from the bias measured at two temperatures
+///
+/// `temp1`/`temp2` are temperature readings as returned by `L3gd20::temp` and
+/// `bias1`/`bias2` the bias in counts measured at each of them. Returns `None`
+/// if both temperatures are equal.
+pub fn fit_bias_model(temp1: i8, bias1: I16x3, temp2: i8, bias2: I16x3) -> Option<BiasModel> {
+    if temp1 == temp2 {
+        return None;
+    }
+
+    let dt = temp2 as f32 - temp1 as f32;
+    let fit = |b1: i16, b2: i16| {
+        let coefficient = (b2 as f32 - b1 as f32) / dt;
+        (b1 as f32 - coefficient * temp1 as f32, coefficient)
+    };
+    let (ox, cx) = fit(bias1.x, bias2.x);
+    let (oy, cy) = fit(bias1.y, bias2.y);
+    let (oz, cz) = fit(bias1.z, bias2.z);
+
+    Some(BiasModel {
+        offset: F32x3 { x: ox, y: oy, z: oz },
+        coefficient: F32x3 { x: cx, y: cy, z: cz },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{replay, replay_h, NoDelay};
+    use crate::NoCs;
+
+    #[test]
+    fn low_data_rates_have_a_single_cutoff() {
+        for &bw in Bandwidth::ALL.iter() {
+            assert_eq!(bw.cutoff_hz(Odr::Hz12_5), 6.25);
+            assert_eq!(bw.cutoff_hz(Odr::Hz25), 12.5);
+            assert_eq!(bw.cutoff_hz(Odr::Hz50), 16.6);
+        }
+        assert_eq!(Bandwidth::for_cutoff(Odr::Hz50, 100.0), Bandwidth::Low);
+    }
+
+    #[test]
+    fn cutoffs_match_the_datasheet() {
+        // cut-off of `Low`, `Medium`, `High` and `Maximum`
+        let table = [
+            (Odr::Hz95, [12.5, 25.0, 25.0, 25.0]),
+            (Odr::Hz190, [12.5, 25.0, 50.0, 70.0]),
+            (Odr::Hz380, [20.0, 25.0, 50.0, 100.0]),
+            (Odr::Hz760, [30.0, 35.0, 50.0, 100.0]),
+        ];
+        for &(odr, cutoffs) in table.iter() {
+            for (&bw, &hz) in Bandwidth::ALL.iter().zip(cutoffs.iter()) {
+                assert_eq!(bw.cutoff_hz(odr), hz, "{:?} at {:?}", bw, odr);
+            }
+        }
+    }
+
+    #[test]
+    fn bandwidth_for_cutoff_is_the_nearest_lowest_one() {
+        use Bandwidth::*;
+
+        let cases = [
+            (Odr::Hz95, 0.0, Low),
+            (Odr::Hz95, 20.0, Medium),
+            // every setting above `Low` is 25 Hz
+            (Odr::Hz95, 100.0, Medium),
+            (Odr::Hz190, 18.0, Low),
+            (Odr::Hz190, 19.0, Medium),
+            // halfway between 50 and 70 Hz
+            (Odr::Hz190, 60.0, High),
+            (Odr::Hz190, 65.0, Maximum),
+            (Odr::Hz380, 22.4, Low),
+            (Odr::Hz380, 75.0, High),
+            (Odr::Hz380, 1000.0, Maximum),
+            (Odr::Hz760, 32.5, Low),
+            (Odr::Hz760, 40.0, Medium),
+            (Odr::Hz760, 80.0, Maximum),
+        ];
+        for &(odr, hz, bw) in cases.iter() {
+            assert_eq!(Bandwidth::for_cutoff(odr, hz), bw, "{} Hz at {:?}", hz, odr);
+        }
+    }
+
+    #[test]
+    fn rates_are_chosen_among_the_supported_ones() {
+        let h = Variant::L3gd20h;
+        assert_eq!(Odr::from_hz(12.5), Some(Odr::Hz95));
+        assert_eq!(Odr::from_hz_for(12.5, RateRounding::Nearest, h), Some(Odr::Hz12_5));
+        assert_eq!(Odr::from_hz_for(20.0, RateRounding::AtLeast, h), Some(Odr::Hz25));
+        assert_eq!(Odr::from_hz_for(60.0, RateRounding::AtMost, h), Some(Odr::Hz50));
+        assert_eq!(Odr::from_hz_rounded(60.0, RateRounding::AtMost), None);
+        assert_eq!(Odr::from_hz_for(10.0, RateRounding::AtMost, h), None);
+    }
+
+    #[test]
+    fn rates_are_chosen_for_every_variant() {
+        use Odr::*;
+
+        // hz, then the rate chosen `Nearest`, `AtLeast` and `AtMost`
+        let l3gd20 = [
+            (1.0, [Some(Hz95), Some(Hz95), None]),
+            (12.5, [Some(Hz95), Some(Hz95), None]),
+            (60.0, [Some(Hz95), Some(Hz95), None]),
+            (95.0, [Some(Hz95); 3]),
+            (100.0, [Some(Hz95), Some(Hz190), Some(Hz95)]),
+            (300.0, [Some(Hz380), Some(Hz380), Some(Hz190)]),
+            (760.0, [Some(Hz760); 3]),
+            (1e4, [Some(Hz760), None, Some(Hz760)]),
+        ];
+        let l3gd20h = [
+            (1.0, [Some(Hz12_5), Some(Hz12_5), None]),
+            (12.5, [Some(Hz12_5); 3]),
+            (20.0, [Some(Hz25), Some(Hz25), Some(Hz12_5)]),
+            (60.0, [Some(Hz50), Some(Hz95), Some(Hz50)]),
+            (95.0, [Some(Hz95); 3]),
+            (100.0, [Some(Hz95), Some(Hz190), Some(Hz95)]),
+            (300.0, [Some(Hz380), Some(Hz380), Some(Hz190)]),
+            (760.0, [Some(Hz760); 3]),
+            (1e4, [Some(Hz760), None, Some(Hz760)]),
+        ];
+        let roundings = [RateRounding::Nearest, RateRounding::AtLeast, RateRounding::AtMost];
+        let tables = [(Variant::L3gd20, &l3gd20[..]), (Variant::L3gd20h, &l3gd20h[..])];
+        for &(variant, cases) in tables.iter() {
+            for &(hz, chosen) in cases {
+                for (&rounding, &odr) in roundings.iter().zip(chosen.iter()) {
+                    let context = (variant, hz, rounding);
+                    assert_eq!(Odr::from_hz_for(hz, rounding, variant), odr, "{:?}", context);
+                }
+            }
+        }
+        // `from_hz` and `from_hz_rounded` only consider the L3GD20
+        for &(hz, chosen) in l3gd20.iter() {
+            assert_eq!(Odr::from_hz(hz), chosen[0]);
+            for (&rounding, &odr) in roundings.iter().zip(chosen.iter()) {
+                assert_eq!(Odr::from_hz_rounded(hz, rounding), odr);
+            }
+        }
+
+        for &hz in [0.0, -95.0, f32::NAN, f32::INFINITY].iter() {
+            for &(variant, _) in tables.iter() {
+                for &rounding in roundings.iter() {
+                    assert_eq!(Odr::from_hz_for(hz, rounding, variant), None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_odr_hz_uses_the_low_data_rates_of_the_l3gd20h() {
+        let mut spi = replay_h(
+            "
+            T b9 00 -> 00 00
+            W 39 01     # LOW_ODR: low data rates
+            W 20 4f     # CTRL_REG1: 25 Hz
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_l3gd20h(&mut spi, NoCs).unwrap();
+            assert_eq!(l3gd20.set_odr_hz(20.0, RateRounding::AtLeast), Ok(Odr::Hz25));
+        }
+        spi.finish().unwrap();
+
+        let mut spi = replay("W 20 0f     # CTRL_REG1: 95 Hz");
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(l3gd20.set_odr_hz(20.0, RateRounding::AtLeast), Ok(Odr::Hz95));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn overruns_between_chunks_are_detected() {
+        // 17 samples, in chunks of 16 and 1; the last one reports an overrun
+        let mut log = std::string::String::new();
+        for i in 0..17 {
+            let status = if i == 16 { "f8" } else { "08" };
+            log.push_str("T a7 00 -> 00 08\n");
+            log.push_str("T e7 00 00 00 00 00 00 00 -> 00 ");
+            log.push_str(status);
+            log.push_str(" 00 00 00 00 00 00\n");
+        }
+        let mut spi = replay(&log);
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let result = l3gd20.for_each_raw(17, &mut NoDelay, |_| ());
+            assert_eq!(result, Err(Error::Overrun { axes: [true; 3] }));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[cfg(feature = "uom")]
+    #[test]
+    fn thresholds_can_be_angular_velocities() {
+        use uom::si::angular_velocity::{degree_per_second, radian_per_second};
+        use uom::si::f32::AngularVelocity;
+
+        let mut spi = replay(
+            "
+            W 74
+            W 2c a5             # Y: 100 dps, 11429 counts
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let dps = AngularVelocity::new::<degree_per_second>;
+            l3gd20
+                .set_interrupt_threshold_angular_velocity(Axis::Y, dps(100.0))
+                .unwrap();
+
+            let negative = Err(Error::InvalidConfig("thresholds can't be negative"));
+            let result = l3gd20.set_interrupt_threshold_angular_velocity(Axis::Z, dps(-1.0));
+            assert_eq!(result.map(|_| ()), negative);
+            let result = l3gd20.set_interrupt_threshold_angular_velocity(Axis::Z, dps(f32::NAN));
+            assert_eq!(result.map(|_| ()), negative);
+            let beyond = Err(Error::InvalidConfig(
+                "the Z-axis threshold is beyond the full scale",
+            ));
+            let fast = AngularVelocity::new::<radian_per_second>(1.0e9);
+            let result = l3gd20.set_interrupt_threshold_angular_velocity(Axis::Z, fast);
+            assert_eq!(result.map(|_| ()), beyond);
+        }
+        spi.finish().unwrap();
+    }
+
+}
@@ -0,0 +1,127 @@
+//! Oscillation frequency estimation from zero crossings
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Accumulator, Axis, Error, I16x3, L3gd20};
+
+/// Counts the crossings of a signal through its mean, with hysteresis
+struct Crossings {
+    mean: i32,
+    hysteresis: i32,
+    // side of the band the signal was last seen on, `true` above
+    above: Option<bool>,
+    index: u32,
+    count: u32,
+    first: u32,
+    last: u32,
+}
+
+impl Crossings {
+    fn new(mean: i16, hysteresis: u16) -> Self {
+        Crossings {
+            mean: i32::from(mean),
+            hysteresis: i32::from(hysteresis),
+            above: None,
+            index: 0,
+            count: 0,
+            first: 0,
+            last: 0,
+        }
+    }
+
+    fn push(&mut self, v: i16) {
+        let v = i32::from(v) - self.mean;
+        let side = if v > self.hysteresis {
+            Some(true)
+        } else if v < -self.hysteresis {
+            Some(false)
+        } else {
+            None
+        };
+
+        if let Some(side) = side {
+            if self.above == Some(!side) {
+                if self.count == 0 {
+                    self.first = self.index;
+                }
+                self.count += 1;
+                self.last = self.index;
+            }
+            self.above = Some(side);
+        }
+        self.index = self.index.saturating_add(1);
+    }
+
+    /// Frequency in Hz, from the time between the first and the last
+    /// crossing; 0 with fewer than two crossings
+    fn frequency(&self, sample_rate_hz: f32) -> f32 {
+        if self.count < 2 {
+            return 0.0;
+        }
+
+        let periods = (self.count - 1) as f32 / 2.0;
+        periods * sample_rate_hz / (self.last - self.first) as f32
+    }
+}
+
+/// Estimate the dominant oscillation frequency of `samples`, taken at
+/// `sample_rate_hz`, from their crossings through their mean
+///
+/// A crossing is counted when the signal goes from below the mean minus
+/// `hysteresis` to above the mean plus `hysteresis`, or the other way
+/// round, so noise smaller than `hysteresis` (in counts) doesn't count as
+/// crossings. Returns the frequency in Hz, or 0 if the signal crosses its
+/// mean fewer than two times.
+pub fn zero_crossing_frequency(samples: &[i16], sample_rate_hz: f32, hysteresis: u16) -> f32 {
+    let mut acc = Accumulator::new();
+    acc.extend(samples.iter().map(|&v| I16x3 { x: v, y: 0, z: 0 }));
+    let mean = acc.mean().map_or(0, |mean| mean.x);
+
+    let mut crossings = Crossings::new(mean, hysteresis);
+    samples.iter().for_each(|&v| crossings.push(v));
+    crossings.frequency(sample_rate_hz)
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Estimate the dominant oscillation frequency, in Hz, of the rate
+    /// about the physical `axis`
+    ///
+    /// Two windows of `window_samples` consecutive measurements are read:
+    /// the first one gives the mean and the RMS of the rate, the crossings
+    /// of the second one through that mean are counted as for
+    /// `zero_crossing_frequency`, with a hysteresis of half the RMS. The
+    /// frequency is derived from the nominal data rate. Returns 0 if the
+    /// rate crosses its mean fewer than two times.
+    pub fn estimate_frequency<D: DelayUs<u32>>(
+        &mut self,
+        axis: Axis,
+        window_samples: u16,
+        delay: &mut D,
+    ) -> Result<f32, Error<E>> {
+        let odr = self.odr()?;
+
+        let mut acc = Accumulator::with_variance();
+        self.for_each_raw(window_samples, delay, |raw| {
+            acc.push(I16x3 {
+                x: raw.component(axis),
+                y: 0,
+                z: 0,
+            })
+        })?;
+        let mean = acc.mean().map_or(0, |mean| mean.x);
+        let variance = acc.variance().map_or(0, |variance| variance[0]);
+        let hysteresis = libm::sqrtf(variance as f32) / 2.0;
+
+        let mut crossings = Crossings::new(mean, hysteresis as u16);
+        self.for_each_raw(window_samples, delay, |raw| {
+            crossings.push(raw.component(axis))
+        })?;
+        Ok(crossings.frequency(odr.hz()))
+    }
+}
@@ -0,0 +1,40 @@
+//! Chip independent gyroscope interface
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, F32x3, L3gd20};
+
+/// A three-axis gyroscope
+///
+/// Write application code against this trait to support several gyroscope
+/// chips. The trait is object safe, so `&mut dyn Gyroscope<Error = E>` can be
+/// used as well.
+pub trait Gyroscope {
+    /// Error returned by `read_rate`
+    type Error;
+
+    /// Angular rate in degrees per second, `x`, `y` and `z`
+    fn read_rate(&mut self) -> Result<F32x3, Self::Error>;
+
+    /// Rate at which new measurements are produced, in Hz
+    fn sample_rate_hz(&self) -> f32;
+}
+
+impl<SPI, CS, E> Gyroscope for L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    type Error = Error<E>;
+
+    /// Same as `L3gd20::gyro_dps`
+    fn read_rate(&mut self) -> Result<F32x3, Error<E>> {
+        self.gyro_dps()
+    }
+
+    /// Nominal data rate of the last programmed `Odr`; doesn't touch the bus
+    fn sample_rate_hz(&self) -> f32 {
+        self.odr_of(self.shadow[0]).hz()
+    }
+}
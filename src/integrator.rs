@@ -0,0 +1,174 @@
+//! Angle integration
+
+use crate::{Axis, F32x3, Odr};
+
+/// Integrates angular rate over time into angles
+///
+/// Angles are accumulated per axis, in degrees, using the rectangle rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AngleIntegrator {
+    angles: F32x3,
+    wrap: bool,
+}
+
+impl AngleIntegrator {
+    /// Creates an integrator with all the angles at zero
+    pub const fn new() -> Self {
+        AngleIntegrator {
+            angles: F32x3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            wrap: false,
+        }
+    }
+
+    /// Wrap the angles into the `[-180, 180)` degree range
+    pub const fn wrapping(mut self) -> Self {
+        self.wrap = true;
+        self
+    }
+
+    /// Add a rate sample, in degrees per second, held for `dt` seconds
+    pub fn update(&mut self, rate_dps: F32x3, dt: f32) {
+        self.angles.x += rate_dps.x * dt;
+        self.angles.y += rate_dps.y * dt;
+        self.angles.z += rate_dps.z * dt;
+
+        if self.wrap {
+            self.angles.x = wrap_deg(self.angles.x);
+            self.angles.y = wrap_deg(self.angles.y);
+            self.angles.z = wrap_deg(self.angles.z);
+        }
+    }
+
+    /// Add a rate sample, in degrees per second, taken at the data rate `odr`
+    pub fn update_odr(&mut self, rate_dps: F32x3, odr: Odr) {
+        self.update(rate_dps, 1.0 / odr.hz());
+    }
+
+    /// Accumulated angles, in degrees
+    pub fn angles_deg(&self) -> F32x3 {
+        self.angles
+    }
+
+    /// Set all the angles back to zero
+    pub fn reset(&mut self) {
+        self.angles = F32x3::default();
+    }
+}
+
+/// Counts the full turns about one axis
+///
+/// The rate of the chosen axis is integrated with the rectangle rule, like
+/// with `AngleIntegrator`, but the angle is kept as a number of whole
+/// revolutions plus a remainder of less than one turn, so the precision
+/// doesn't degrade over hours of spinning. The remainder has the sign of
+/// the total angle, so an angle of -370° is -1 revolution and -10°.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RevolutionCounter {
+    axis: Axis,
+    revolutions: i32,
+    remainder: f32,
+}
+
+impl RevolutionCounter {
+    /// Creates a counter at zero for the rate about `axis`
+    pub const fn new(axis: Axis) -> Self {
+        RevolutionCounter {
+            axis,
+            revolutions: 0,
+            remainder: 0.0,
+        }
+    }
+
+    /// Add a rate sample, in degrees per second, held for `dt` seconds
+    pub fn update(&mut self, rate_dps: F32x3, dt: f32) {
+        let rate = match self.axis {
+            Axis::X => rate_dps.x,
+            Axis::Y => rate_dps.y,
+            Axis::Z => rate_dps.z,
+        };
+        self.add(rate * dt);
+    }
+
+    /// Add a rate sample, in degrees per second, taken at the data rate `odr`
+    pub fn update_odr(&mut self, rate_dps: F32x3, odr: Odr) {
+        self.update(rate_dps, 1.0 / odr.hz());
+    }
+
+    /// Add a batch of rate samples, in degrees per second, that together
+    /// span `dt` seconds
+    ///
+    /// Use this when samples may have been missed, with `dt` measured by
+    /// the caller: every sample is held for an equal share of `dt`.
+    pub fn update_batch(&mut self, rates_dps: &[F32x3], dt: f32) {
+        if rates_dps.is_empty() {
+            return;
+        }
+
+        let dt = dt / rates_dps.len() as f32;
+        for &rate in rates_dps {
+            self.update(rate, dt);
+        }
+    }
+
+    fn add(&mut self, angle: f32) {
+        if !angle.is_finite() {
+            return;
+        }
+
+        // the angle of a single update could be many turns
+        let remainder = self.remainder + angle % 360.0;
+        let turns = (angle / 360.0) as i32;
+        let mut revolutions = self.revolutions.saturating_add(turns);
+
+        let (remainder, carry) = if remainder >= 360.0 {
+            (remainder - 360.0, 1)
+        } else if remainder <= -360.0 {
+            (remainder + 360.0, -1)
+        } else {
+            (remainder, 0)
+        };
+        revolutions = revolutions.saturating_add(carry);
+
+        // give the remainder the sign of the total
+        let (remainder, borrow) = if revolutions > 0 && remainder < 0.0 {
+            (remainder + 360.0, -1)
+        } else if revolutions < 0 && remainder > 0.0 {
+            (remainder - 360.0, 1)
+        } else {
+            (remainder, 0)
+        };
+        self.revolutions = revolutions.saturating_add(borrow);
+        self.remainder = remainder;
+    }
+
+    /// Number of whole revolutions, positive in the direction of positive
+    /// rates; saturates at the limits of `i32`
+    pub fn revolutions(&self) -> i32 {
+        self.revolutions
+    }
+
+    /// Angle of the incomplete revolution, in degrees, in `(-360, 360)`
+    pub fn remainder_deg(&self) -> f32 {
+        self.remainder
+    }
+
+    /// Set the count back to zero
+    pub fn reset(&mut self) {
+        self.revolutions = 0;
+        self.remainder = 0.0;
+    }
+}
+
+/// Wrap an angle into `[-180, 180)` degrees
+fn wrap_deg(angle: f32) -> f32 {
+    let a = (angle + 180.0) % 360.0;
+    if a < 0.0 {
+        a + 180.0
+    } else {
+        a - 180.0
+    }
+}
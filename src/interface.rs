@@ -0,0 +1,62 @@
+//! Peripherals taken by the constructors
+//!
+//! The driver talks to the sensor through the embedded-hal 0.2 SPI and
+//! digital traits. Exactly one of the `eh0` and `eh1` features must be
+//! enabled, and selects the peripherals `L3gd20::new` and the other
+//! constructors take:
+//!
+//! - `eh0` (the default): embedded-hal 0.2 `Transfer` + `Write` and
+//!   `OutputPin`, used as they are
+//! - `eh1`: embedded-hal 1.0 `SpiBus` and `OutputPin`, adapted by
+//!   `eh1::SpiCompat` and `eh1::PinCompat`
+//!
+//! Everything past the constructors is the same with both features. For
+//! embedded-hal 1.0:
+//!
+//! ``` toml
+//! l3gd20 = { version = "0.3", default-features = false, features = ["float", "eh1"] }
+//! ```
+
+#[cfg(feature = "eh0")]
+use embedded_hal::blocking::spi::{Transfer, Write};
+#[cfg(feature = "eh0")]
+use embedded_hal::digital::v2::OutputPin;
+
+/// SPI peripheral with `W` frames taken by the constructors
+pub trait SpiInterface<W = u8> {
+    /// Peripheral used by the driver
+    type Spi;
+
+    /// Adapt the peripheral for the driver
+    fn into_spi(self) -> Self::Spi;
+}
+
+/// Chip select taken by the constructors
+pub trait CsInterface {
+    /// Pin used by the driver
+    type Cs;
+
+    /// Adapt the pin for the driver
+    fn into_cs(self) -> Self::Cs;
+}
+
+#[cfg(feature = "eh0")]
+impl<SPI, W, E> SpiInterface<W> for SPI
+where
+    SPI: Transfer<W, Error = E> + Write<W, Error = E>,
+{
+    type Spi = SPI;
+
+    fn into_spi(self) -> SPI {
+        self
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<CS: OutputPin> CsInterface for CS {
+    type Cs = CS;
+
+    fn into_cs(self) -> CS {
+        self
+    }
+}
@@ -0,0 +1,184 @@
+//! Integration with third-party crates
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_impls {
+    use nalgebra::Vector3;
+
+    use crate::{F32x3, I16x3};
+
+    impl From<I16x3> for Vector3<i16> {
+        fn from(v: I16x3) -> Self {
+            Vector3::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl From<F32x3> for Vector3<f32> {
+        fn from(v: F32x3) -> Self {
+            Vector3::new(v.x, v.y, v.z)
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+mod mint_impls {
+    use mint::Vector3;
+
+    #[cfg(feature = "float")]
+    use crate::F32x3;
+    use crate::I16x3;
+
+    impl From<I16x3> for Vector3<i16> {
+        fn from(v: I16x3) -> Self {
+            Vector3 {
+                x: v.x,
+                y: v.y,
+                z: v.z,
+            }
+        }
+    }
+
+    impl From<Vector3<i16>> for I16x3 {
+        fn from(v: Vector3<i16>) -> Self {
+            I16x3 {
+                x: v.x,
+                y: v.y,
+                z: v.z,
+            }
+        }
+    }
+
+    #[cfg(feature = "float")]
+    impl From<F32x3> for Vector3<f32> {
+        fn from(v: F32x3) -> Self {
+            Vector3 {
+                x: v.x,
+                y: v.y,
+                z: v.z,
+            }
+        }
+    }
+
+    #[cfg(feature = "float")]
+    impl From<Vector3<f32>> for F32x3 {
+        fn from(v: Vector3<f32>) -> Self {
+            F32x3 {
+                x: v.x,
+                y: v.y,
+                z: v.z,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+mod bytemuck_impls {
+    use bytemuck::{Pod, Zeroable};
+
+    #[cfg(feature = "float")]
+    use crate::F32x3;
+    use crate::{I16x3, I32x3};
+
+    // SAFETY: `#[repr(C)]` structs of three fields of the same `Pod` type, so
+    // there is no padding and every bit pattern is valid
+    unsafe impl Zeroable for I16x3 {}
+    unsafe impl Pod for I16x3 {}
+
+    unsafe impl Zeroable for I32x3 {}
+    unsafe impl Pod for I32x3 {}
+
+    #[cfg(feature = "float")]
+    unsafe impl Zeroable for F32x3 {}
+    #[cfg(feature = "float")]
+    unsafe impl Pod for F32x3 {}
+}
+
+#[cfg(feature = "ufmt")]
+mod ufmt_impls {
+    use ufmt::{uDisplay, uWrite, uwrite, Formatter};
+
+    use crate::{Bandwidth, I16x3, I32x3, Measurements, Odr, Scale, Status};
+
+    /// Write `value` with an explicit sign, like `{:+}`
+    fn signed<W: uWrite + ?Sized>(f: &mut Formatter<'_, W>, value: i32) -> Result<(), W::Error> {
+        if value >= 0 {
+            f.write_str("+")?;
+        }
+        uwrite!(f, "{}", value)
+    }
+
+    /// Same output as `Display`, e.g. `[+12, -4, +998]`
+    impl uDisplay for I16x3 {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            f.write_str("[")?;
+            signed(f, self.x.into())?;
+            f.write_str(", ")?;
+            signed(f, self.y.into())?;
+            f.write_str(", ")?;
+            signed(f, self.z.into())?;
+            f.write_str("]")
+        }
+    }
+
+    /// Same output as `Display`, e.g. `[+12300, -400, +998100]`
+    impl uDisplay for I32x3 {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            f.write_str("[")?;
+            signed(f, self.x)?;
+            f.write_str(", ")?;
+            signed(f, self.y)?;
+            f.write_str(", ")?;
+            signed(f, self.z)?;
+            f.write_str("]")
+        }
+    }
+
+    /// Same output as `Display`, e.g. `gyro: [+12, -4, +998] counts, temp: 27`
+    impl uDisplay for Measurements {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            uwrite!(f, "gyro: {} counts, temp: {}", self.gyro, self.temp)
+        }
+    }
+
+    /// Same output as `Display`, e.g. `ZYXDA|XDA`
+    impl uDisplay for Status {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            let mut first = true;
+            for name in self.flag_names() {
+                if !first {
+                    f.write_str("|")?;
+                }
+                f.write_str(name)?;
+                first = false;
+            }
+            if first {
+                f.write_str("-")?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Same output as `Display`, e.g. `760 Hz` or `12.5 Hz`
+    impl uDisplay for Odr {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            let mhz = self.nominal_mhz();
+            match mhz % 1000 {
+                0 => uwrite!(f, "{} Hz", mhz / 1000),
+                frac => uwrite!(f, "{}.{} Hz", mhz / 1000, frac / 100),
+            }
+        }
+    }
+
+    /// Same output as `Display`, e.g. `\u{b1}2000 dps`
+    impl uDisplay for Scale {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            uwrite!(f, "\u{b1}{} dps", self.max_dps())
+        }
+    }
+
+    /// Same output as `Display`, e.g. `low`
+    impl uDisplay for Bandwidth {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            f.write_str(self.name())
+        }
+    }
+}
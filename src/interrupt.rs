@@ -0,0 +1,719 @@
+//! Interrupt generator settings
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::{CtrlReg4, Int1Cfg, Int1Duration, Int1TshXh, Register};
+use crate::{Axis, Error, L3gd20, Scale, Variant};
+
+/// Largest value of the 15-bit interrupt thresholds, in counts
+pub const MAX_THRESHOLD: u16 = 0x7fff;
+
+/// Smallest non-zero threshold accepted by
+/// `L3gd20::set_interrupt_threshold_mdps`, in counts
+///
+/// Rounding to counts changes a threshold of at least 10 counts by at most
+/// 5 %; below that, the threshold is too coarse to mean what was asked for.
+pub const MIN_THRESHOLD_COUNTS: u16 = 10;
+
+/// What `L3gd20::set_scale` (and a staged change of scale) does when a
+/// threshold set with `L3gd20::set_interrupt_threshold_mdps` doesn't fit the
+/// new scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThresholdPolicy {
+    /// Fail with `Error::InvalidConfig` naming the axis, without writing
+    /// anything
+    Strict,
+    /// Change the scale anyway, clipping the threshold and flagging it in
+    /// `L3gd20::clipped_thresholds`
+    Clip,
+}
+
+/// What the interrupt duration counter does when the rate drops below the
+/// threshold (`DCRM` in `INT1_TSH_XH`, L3GD20H only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterMode {
+    /// The counter restarts from zero
+    Reset,
+    /// The counter is decremented, so short dips in a burst of rotation
+    /// don't restart the duration
+    Decrement,
+}
+
+/// How the interrupt 1 request is latched
+///
+/// The two latching mechanisms, `LIR` in `INT1_CFG` and `IMPen` in
+/// `CTRL_REG4`, are mutually exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatchMode {
+    /// Not latched: the request follows the interrupt condition
+    None,
+    /// Latched until `INT1_SRC` is read (`LIR`)
+    Lir,
+    /// Level sensitive latched (`IMPen`); L3GD20H only
+    LevelSensitive,
+}
+
+/// Largest minimum duration of an interrupt event, in samples
+const MAX_DURATION: u8 = 0x7f;
+
+/// Interrupt 1 generator configuration, see `L3gd20::configure_interrupt`
+///
+/// Describes `INT1_CFG`, the thresholds, `INT1_DURATION` and the
+/// latching, so the interrupt generator can be set up in one call:
+///
+/// ``` ignore
+/// let config = Int1Config::default()
+///     .high(Axis::Z)
+///     .threshold(Axis::Z, 5_000)
+///     .duration(4)
+///     .counter_mode(CounterMode::Decrement);
+/// l3gd20.configure_interrupt(&config)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Int1Config {
+    /// Axes on which a rate above the threshold is an event, indexed by
+    /// `Axis` (`XHIE`, `YHIE`, `ZHIE`)
+    pub high: [bool; 3],
+    /// Axes on which a rate below the threshold is an event, indexed by
+    /// `Axis` (`XLIE`, `YLIE`, `ZLIE`)
+    pub low: [bool; 3],
+    /// Request an interrupt when all the enabled events occur rather than
+    /// any of them (`AND/OR`)
+    pub all_events: bool,
+    /// Thresholds of the physical axes, in counts, indexed by `Axis`
+    pub thresholds: [u16; 3],
+    /// Minimum duration of an event, in samples; at most 127 (`D`)
+    pub duration: u8,
+    /// Keep the request until the condition has been gone for `duration`
+    /// samples too (`WAIT`)
+    pub wait: bool,
+    /// How the request is latched
+    pub latch: LatchMode,
+    /// What the duration counter does when the condition goes away (`DCRM`);
+    /// `CounterMode::Decrement` needs a L3GD20H
+    pub counter_mode: CounterMode,
+}
+
+impl Default for Int1Config {
+    /// No event enabled, zero thresholds and duration, not latched, and the
+    /// duration counter reset when the condition goes away
+    fn default() -> Self {
+        Int1Config {
+            high: [false; 3],
+            low: [false; 3],
+            all_events: false,
+            thresholds: [0; 3],
+            duration: 0,
+            wait: false,
+            latch: LatchMode::None,
+            counter_mode: CounterMode::Reset,
+        }
+    }
+}
+
+impl Int1Config {
+    /// Enable the event of a rate above the threshold on `axis`
+    pub fn high(mut self, axis: Axis) -> Self {
+        self.high[axis as usize] = true;
+        self
+    }
+
+    /// Enable the event of a rate below the threshold on `axis`
+    pub fn low(mut self, axis: Axis) -> Self {
+        self.low[axis as usize] = true;
+        self
+    }
+
+    /// Request an interrupt when all the enabled events occur, rather than
+    /// any of them
+    pub fn all_events(mut self, all_events: bool) -> Self {
+        self.all_events = all_events;
+        self
+    }
+
+    /// Set the threshold of `axis`, in counts
+    pub fn threshold(mut self, axis: Axis, counts: u16) -> Self {
+        self.thresholds[axis as usize] = counts;
+        self
+    }
+
+    /// Set the minimum duration of an event, in samples
+    pub fn duration(mut self, samples: u8) -> Self {
+        self.duration = samples;
+        self
+    }
+
+    /// Keep the request until the condition has been gone for the duration
+    pub fn wait(mut self, wait: bool) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    /// Set how the request is latched
+    pub fn latch(mut self, latch: LatchMode) -> Self {
+        self.latch = latch;
+        self
+    }
+
+    /// Set the mode of the duration counter
+    pub fn counter_mode(mut self, mode: CounterMode) -> Self {
+        self.counter_mode = mode;
+        self
+    }
+
+    /// `INT1_CFG` value
+    fn int1_cfg(&self) -> Int1Cfg {
+        Int1Cfg::default()
+            .with_and_or(self.all_events)
+            .with_lir(self.latch == LatchMode::Lir)
+            .with_xlie(self.low[Axis::X as usize])
+            .with_xhie(self.high[Axis::X as usize])
+            .with_ylie(self.low[Axis::Y as usize])
+            .with_yhie(self.high[Axis::Y as usize])
+            .with_zlie(self.low[Axis::Z as usize])
+            .with_zhie(self.high[Axis::Z as usize])
+    }
+
+    /// Values of `INT1_TSH_XH` to `INT1_DURATION`
+    fn threshold_registers(&self) -> [u8; 7] {
+        let [x, y, z] = self.thresholds;
+        let [xh, xl] = x.to_be_bytes();
+        let [yh, yl] = y.to_be_bytes();
+        let [zh, zl] = z.to_be_bytes();
+        let xh = Int1TshXh::default()
+            .with_dcrm(self.counter_mode == CounterMode::Decrement)
+            .with_thsx(xh);
+        let duration = Int1Duration::default()
+            .with_wait(self.wait)
+            .with_d(self.duration);
+        [xh.bits(), xl, yh, yl, zh, zl, duration.bits()]
+    }
+}
+
+/// Thresholds set with `L3gd20::set_interrupt_threshold_mdps`, kept so they
+/// can be converted when the scale changes
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ArmedThresholds {
+    mdps: [Option<u32>; 3],
+    clipped: [bool; 3],
+}
+
+/// `INT1_TSH_*H` register of `axis`; the low byte follows it
+fn threshold_register(axis: Axis) -> Register {
+    match axis {
+        Axis::X => Register::INT1_TSH_XH,
+        Axis::Y => Register::INT1_TSH_YH,
+        Axis::Z => Register::INT1_TSH_ZH,
+    }
+}
+
+/// `mdps` in counts of `scale`, rounded to the nearest count
+fn threshold_counts(mdps: u32, scale: Scale) -> u64 {
+    let udps = u64::from(scale.sensitivity_udps());
+    (u64::from(mdps) * 1000 + udps / 2) / udps
+}
+
+/// Why a threshold doesn't fit a scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Misfit {
+    /// Beyond the full scale: the interrupt could never fire
+    Beyond,
+    /// Below `MIN_THRESHOLD_COUNTS`
+    Coarse,
+}
+
+/// `mdps` in counts of `scale`, if it fits
+fn fitting_counts(mdps: u32, scale: Scale) -> Result<u16, Misfit> {
+    let counts = threshold_counts(mdps, scale);
+    if mdps > u32::from(scale.max_dps()) * 1000 || counts > u64::from(MAX_THRESHOLD) {
+        Err(Misfit::Beyond)
+    } else if mdps != 0 && counts < u64::from(MIN_THRESHOLD_COUNTS) {
+        Err(Misfit::Coarse)
+    } else {
+        Ok(counts as u16)
+    }
+}
+
+/// `Error::InvalidConfig` for an `axis` threshold that doesn't fit
+fn threshold_error<E>(axis: Axis, misfit: Misfit) -> Error<E> {
+    Error::InvalidConfig(match (axis, misfit) {
+        (Axis::X, Misfit::Beyond) => "the X-axis threshold is beyond the full scale",
+        (Axis::Y, Misfit::Beyond) => "the Y-axis threshold is beyond the full scale",
+        (Axis::Z, Misfit::Beyond) => "the Z-axis threshold is beyond the full scale",
+        (Axis::X, Misfit::Coarse) => "the X-axis threshold is below MIN_THRESHOLD_COUNTS",
+        (Axis::Y, Misfit::Coarse) => "the Y-axis threshold is below MIN_THRESHOLD_COUNTS",
+        (Axis::Z, Misfit::Coarse) => "the Z-axis threshold is below MIN_THRESHOLD_COUNTS",
+    })
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Fail with `Error::InvalidConfig` unless the sensor is a L3GD20H
+    fn require_l3gd20h(&self, what: &'static str) -> Result<(), Error<E>> {
+        match self.variant {
+            Variant::L3gd20h => Ok(()),
+            Variant::L3gd20 => Err(Error::InvalidConfig(what)),
+        }
+    }
+
+    /// Set the mode of the interrupt duration counter
+    ///
+    /// Fails with `Error::InvalidConfig` on the L3GD20, which always resets
+    /// the counter. See also `Int1Config::counter_mode`.
+    pub fn set_duration_counter_mode(&mut self, mode: CounterMode) -> Result<&mut Self, Error<E>> {
+        self.require_l3gd20h("the duration counter mode needs a L3GD20H")?;
+        self.verified(|l3gd20| {
+            l3gd20
+                .change_config(Register::INT1_TSH_XH, |r| {
+                    Int1TshXh::from_bits(r)
+                        .with_dcrm(mode == CounterMode::Decrement)
+                        .bits()
+                })
+                .map(|_| ())
+        })?;
+        Ok(self)
+    }
+
+    /// Get the mode of the interrupt duration counter
+    ///
+    /// Always `CounterMode::Reset` on the L3GD20; doesn't touch the bus then.
+    pub fn duration_counter_mode(&mut self) -> Result<CounterMode, Error<E>> {
+        if self.variant == Variant::L3gd20 {
+            return Ok(CounterMode::Reset);
+        }
+
+        let reg = Int1TshXh::from_bits(self.read_register(Register::INT1_TSH_XH)?);
+        Ok(if reg.dcrm() {
+            CounterMode::Decrement
+        } else {
+            CounterMode::Reset
+        })
+    }
+
+    /// Set how the interrupt 1 request is latched
+    ///
+    /// `LIR` and `IMPen` are updated together, the one being cleared first,
+    /// so they are never both set. Fails with `Error::InvalidConfig` if
+    /// `LatchMode::LevelSensitive` is requested on the L3GD20.
+    pub fn set_interrupt_latch_mode(&mut self, mode: LatchMode) -> Result<&mut Self, Error<E>> {
+        let lir = mode == LatchMode::Lir;
+        let impen = mode == LatchMode::LevelSensitive;
+        if impen {
+            self.require_l3gd20h("level sensitive latched interrupts need a L3GD20H")?;
+        }
+
+        let set_lir = |l3gd20: &mut Self| {
+            l3gd20
+                .change_config(Register::INT1_CFG, |r| {
+                    Int1Cfg::from_bits(r).with_lir(lir).bits()
+                })
+                .map(|_| ())
+        };
+        let set_impen = |l3gd20: &mut Self| {
+            l3gd20
+                .change_config(Register::CTRL_REG4, |r| {
+                    CtrlReg4::from_bits(r).with_impen(impen).bits()
+                })
+                .map(|_| ())
+        };
+
+        let variant = self.variant;
+        self.verified(|l3gd20| {
+            if variant == Variant::L3gd20 {
+                set_lir(l3gd20)
+            } else if impen {
+                set_lir(l3gd20)?;
+                set_impen(l3gd20)
+            } else {
+                set_impen(l3gd20)?;
+                set_lir(l3gd20)
+            }
+        })?;
+        Ok(self)
+    }
+
+    /// Get how the interrupt 1 request is latched
+    ///
+    /// Fails with `Error::InvalidConfig` if both `LIR` and `IMPen` are set.
+    pub fn interrupt_latch_mode(&mut self) -> Result<LatchMode, Error<E>> {
+        let lir = Int1Cfg::from_bits(self.read_register(Register::INT1_CFG)?).lir();
+        self.latch_mode(lir)
+    }
+
+    /// Latch mode given `LIR`, reading `IMPen` on the L3GD20H
+    fn latch_mode(&mut self, lir: bool) -> Result<LatchMode, Error<E>> {
+        let impen = self.variant == Variant::L3gd20h
+            && CtrlReg4::from_bits(self.read_register(Register::CTRL_REG4)?).impen();
+
+        match (lir, impen) {
+            (false, false) => Ok(LatchMode::None),
+            (true, false) => Ok(LatchMode::Lir),
+            (false, true) => Ok(LatchMode::LevelSensitive),
+            (true, true) => Err(Error::InvalidConfig("both LIR and IMPen are set")),
+        }
+    }
+
+    /// Set up the interrupt 1 generator
+    ///
+    /// Events are disabled while the thresholds, the duration and the
+    /// latching are written, and enabled last, so no interrupt is requested
+    /// with a partial configuration; `LIR` and `IMPen` are never both set.
+    /// Fails with `Error::InvalidConfig` without writing anything if a
+    /// threshold exceeds `MAX_THRESHOLD`, the duration exceeds 127 samples,
+    /// or the configuration needs a L3GD20H. The thresholds set with
+    /// `set_interrupt_threshold_mdps` are forgotten.
+    pub fn configure_interrupt(&mut self, config: &Int1Config) -> Result<&mut Self, Error<E>> {
+        if config.thresholds.iter().any(|&t| t > MAX_THRESHOLD) {
+            return Err(Error::InvalidConfig("thresholds are limited to 15 bits"));
+        }
+        if config.duration > MAX_DURATION {
+            return Err(Error::InvalidConfig("the duration is limited to 7 bits"));
+        }
+        if config.counter_mode == CounterMode::Decrement {
+            self.require_l3gd20h("the duration counter mode needs a L3GD20H")?;
+        }
+        let impen = config.latch == LatchMode::LevelSensitive;
+        if impen {
+            self.require_l3gd20h("level sensitive latched interrupts need a L3GD20H")?;
+        }
+
+        let variant = self.variant;
+        self.verified(|l3gd20| {
+            l3gd20.write_register(Register::INT1_CFG, 0)?;
+            if variant == Variant::L3gd20h {
+                l3gd20.change_config(Register::CTRL_REG4, |r| {
+                    CtrlReg4::from_bits(r).with_impen(impen).bits()
+                })?;
+            }
+            l3gd20.write_registers(Register::INT1_TSH_XH, &config.threshold_registers())?;
+            l3gd20.write_register(Register::INT1_CFG, config.int1_cfg().bits())
+        })?;
+        self.clear_thresholds();
+        Ok(self)
+    }
+
+    /// Get the configuration of the interrupt 1 generator
+    ///
+    /// Fails with `Error::InvalidConfig` if both `LIR` and `IMPen` are set.
+    pub fn interrupt_config(&mut self) -> Result<Int1Config, Error<E>> {
+        let cfg = Int1Cfg::from_bits(self.read_register(Register::INT1_CFG)?);
+        let latch = self.latch_mode(cfg.lir())?;
+        let mut regs = [0; 7];
+        self.read_into(Register::INT1_TSH_XH, &mut regs)?;
+
+        let xh = Int1TshXh::from_bits(regs[0]);
+        let threshold = |high: u8, low: u8| u16::from_be_bytes([high, low]) & MAX_THRESHOLD;
+        let duration = Int1Duration::from_bits(regs[6]);
+        let decrement = self.variant == Variant::L3gd20h && xh.dcrm();
+        Ok(Int1Config {
+            high: [cfg.xhie(), cfg.yhie(), cfg.zhie()],
+            low: [cfg.xlie(), cfg.ylie(), cfg.zlie()],
+            all_events: cfg.and_or(),
+            thresholds: [
+                threshold(xh.thsx(), regs[1]),
+                threshold(regs[2], regs[3]),
+                threshold(regs[4], regs[5]),
+            ],
+            duration: duration.d(),
+            wait: duration.wait(),
+            latch,
+            counter_mode: if decrement {
+                CounterMode::Decrement
+            } else {
+                CounterMode::Reset
+            },
+        })
+    }
+
+    /// Set the interrupt 1 threshold of the physical `axis`, in counts
+    ///
+    /// The threshold is compared against the raw output of the axis, before
+    /// the axis mapping. Fails with `Error::InvalidConfig` if `counts`
+    /// exceeds `MAX_THRESHOLD`. `DCRM` is preserved. A threshold set with
+    /// `set_interrupt_threshold_mdps` for this axis is forgotten.
+    pub fn set_interrupt_threshold(
+        &mut self,
+        axis: Axis,
+        counts: u16,
+    ) -> Result<&mut Self, Error<E>> {
+        if counts > MAX_THRESHOLD {
+            return Err(Error::InvalidConfig("thresholds are limited to 15 bits"));
+        }
+
+        self.verified(|l3gd20| l3gd20.write_threshold(axis, counts))?;
+        self.thresholds.mdps[axis as usize] = None;
+        self.thresholds.clipped[axis as usize] = false;
+        Ok(self)
+    }
+
+    /// Get the interrupt 1 threshold of the physical `axis`, in counts
+    pub fn interrupt_threshold(&mut self, axis: Axis) -> Result<u16, E> {
+        let mut bytes = [0; 2];
+        self.read_into(threshold_register(axis), &mut bytes)?;
+        Ok(u16::from_be_bytes(bytes) & MAX_THRESHOLD)
+    }
+
+    /// Set the interrupt 1 threshold of the physical `axis`, in milli-degrees
+    /// per second
+    ///
+    /// The threshold is converted to counts of the current `Scale`. Fails with
+    /// `Error::InvalidConfig` naming the axis if `mdps` is beyond the full
+    /// scale, so the interrupt could never fire, or below
+    /// `MIN_THRESHOLD_COUNTS`, so it is too coarse; nothing is written then.
+    /// A threshold of zero is accepted.
+    ///
+    /// The threshold is converted again by `set_scale` (and a staged change
+    /// of scale), which handles thresholds that don't fit the new scale
+    /// according to the `ThresholdPolicy`.
+    pub fn set_interrupt_threshold_mdps(
+        &mut self,
+        axis: Axis,
+        mdps: u32,
+    ) -> Result<&mut Self, Error<E>> {
+        let scale = self.current_scale()?;
+        let counts = fitting_counts(mdps, scale).map_err(|misfit| threshold_error(axis, misfit))?;
+
+        self.verified(|l3gd20| l3gd20.write_threshold(axis, counts))?;
+        self.thresholds.mdps[axis as usize] = Some(mdps);
+        self.thresholds.clipped[axis as usize] = false;
+        Ok(self)
+    }
+
+    /// Set what a change of scale does with the thresholds set with
+    /// `set_interrupt_threshold_mdps` that don't fit the new scale
+    pub fn set_threshold_policy(&mut self, policy: ThresholdPolicy) -> &mut Self {
+        self.threshold_policy = policy;
+        self
+    }
+
+    /// Get the current threshold policy
+    pub fn threshold_policy(&self) -> ThresholdPolicy {
+        self.threshold_policy
+    }
+
+    /// Axes whose threshold set with `set_interrupt_threshold_mdps` didn't
+    /// fit after the last change of scale with `ThresholdPolicy::Clip`,
+    /// indexed by `Axis`
+    ///
+    /// The threshold of such an axis was clipped to the full scale, where the
+    /// interrupt can't fire anymore, raised to one count, or is below
+    /// `MIN_THRESHOLD_COUNTS`. The flag is cleared when the axis threshold is
+    /// set again.
+    pub fn clipped_thresholds(&self) -> [bool; 3] {
+        self.thresholds.clipped
+    }
+
+    /// Fail with `Error::InvalidConfig` if a threshold set with
+    /// `set_interrupt_threshold_mdps` doesn't fit `scale` and the policy is
+    /// `ThresholdPolicy::Strict`
+    pub(crate) fn check_thresholds(&self, scale: Scale) -> Result<(), Error<E>> {
+        if self.threshold_policy == ThresholdPolicy::Clip {
+            return Ok(());
+        }
+        for &axis in [Axis::X, Axis::Y, Axis::Z].iter() {
+            if let Some(mdps) = self.thresholds.mdps[axis as usize] {
+                fitting_counts(mdps, scale).map_err(|misfit| threshold_error(axis, misfit))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget the thresholds set with `set_interrupt_threshold_mdps`, e.g.
+    /// after the sensor was reset
+    pub(crate) fn clear_thresholds(&mut self) {
+        self.thresholds = ArmedThresholds::default();
+    }
+
+    /// Convert the thresholds set with `set_interrupt_threshold_mdps` to
+    /// `scale`, clipping and flagging those that don't fit; see
+    /// `check_thresholds` for the `ThresholdPolicy::Strict` check
+    pub(crate) fn rescale_thresholds(&mut self, scale: Scale) -> Result<(), E> {
+        for &axis in [Axis::X, Axis::Y, Axis::Z].iter() {
+            if let Some(mdps) = self.thresholds.mdps[axis as usize] {
+                let counts = threshold_counts(mdps, scale);
+                let clipped = counts.clamp(u64::from(mdps != 0), u64::from(MAX_THRESHOLD));
+                self.write_threshold(axis, clipped as u16)?;
+                self.thresholds.clipped[axis as usize] = fitting_counts(mdps, scale).is_err();
+            }
+        }
+        Ok(())
+    }
+
+    fn write_threshold(&mut self, axis: Axis, counts: u16) -> Result<(), E> {
+        let reg = threshold_register(axis);
+        let [high, low] = counts.to_be_bytes();
+        let high = if axis == Axis::X {
+            let current = Int1TshXh::from_bits(self.read_register(reg)?);
+            current.with_thsx(high).bits()
+        } else {
+            high
+        };
+        self.write_registers(reg, &[high, low])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{replay, replay_h};
+    use crate::NoCs;
+
+    #[test]
+    fn thresholds_fit_clip_or_underflow_at_every_scale() {
+        // (scale, fitting mdps and counts, beyond the full scale, too coarse)
+        let regions = [
+            (Scale::Dps250, 100_000, 11_429, 251_000, 50),
+            (Scale::Dps500, 100_000, 5_714, 501_000, 100),
+            (Scale::Dps2000, 1_000_000, 14_286, 2_001_000, 500),
+        ];
+        for &(scale, fits, counts, beyond, coarse) in regions.iter() {
+            assert_eq!(fitting_counts(fits, scale), Ok(counts), "{:?}", scale);
+            assert_eq!(
+                fitting_counts(beyond, scale),
+                Err(Misfit::Beyond),
+                "{:?}",
+                scale
+            );
+            assert_eq!(
+                fitting_counts(coarse, scale),
+                Err(Misfit::Coarse),
+                "{:?}",
+                scale
+            );
+            assert_eq!(fitting_counts(0, scale), Ok(0));
+        }
+    }
+
+    #[test]
+    fn thresholds_are_checked_when_the_scale_changes() {
+        let mut spi = replay(
+            "
+            W 23 30             # CTRL_REG4: 2000 dps
+            T b2 00 -> 00 00    # INT1_TSH_XH
+            W 72
+            W 37 ce             # X: 1000 dps, 14286 counts
+            W 23 00             # CTRL_REG4: 250 dps
+            T b2 00 -> 00 37
+            W 72
+            W 7f ff             # X: clipped to MAX_THRESHOLD
+            W 74
+            W 00 0b             # Y: 0.1 dps, 11 counts
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_scale(Scale::Dps2000).unwrap();
+            l3gd20
+                .set_interrupt_threshold_mdps(Axis::X, 1_000_000)
+                .unwrap();
+            assert_eq!(l3gd20.threshold_policy(), ThresholdPolicy::Strict);
+
+            let beyond = Err(threshold_error(Axis::X, Misfit::Beyond));
+            assert_eq!(l3gd20.set_scale(Scale::Dps250).map(|_| ()), beyond);
+            let mut staged = l3gd20.begin_config();
+            staged.scale(Scale::Dps500);
+            assert_eq!(staged.commit(), beyond);
+            assert_eq!(l3gd20.cached_scale(), Some(Scale::Dps2000));
+
+            l3gd20.set_threshold_policy(ThresholdPolicy::Clip);
+            l3gd20.set_scale(Scale::Dps250).unwrap();
+            assert_eq!(l3gd20.clipped_thresholds(), [true, false, false]);
+
+            let coarse = Err(threshold_error(Axis::Y, Misfit::Coarse));
+            l3gd20.set_interrupt_threshold_mdps(Axis::Y, 100).unwrap();
+            l3gd20.set_threshold_policy(ThresholdPolicy::Strict);
+            assert_eq!(l3gd20.set_scale(Scale::Dps2000).map(|_| ()), coarse);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn interrupt_generator_is_configured_in_one_call() {
+        let mut spi = replay(
+            "
+            W 30 00                     # events disabled
+            W 72
+            W 12 34 00 00 7f ff 84      # thresholds, WAIT, 4 samples
+            W 30 e1                     # AND, LIR, ZHIE, XLIE
+            T b0 00 -> 00 e1
+            W f2
+            T 00 00 00 00 00 00 00 -> 12 34 00 00 7f ff 84
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let config = Int1Config::default()
+                .high(Axis::Z)
+                .low(Axis::X)
+                .all_events(true)
+                .threshold(Axis::X, 0x1234)
+                .threshold(Axis::Z, MAX_THRESHOLD)
+                .duration(4)
+                .wait(true)
+                .latch(LatchMode::Lir);
+            l3gd20.configure_interrupt(&config).unwrap();
+            assert_eq!(l3gd20.interrupt_config(), Ok(config));
+
+            let invalid = [
+                (
+                    config.threshold(Axis::Y, MAX_THRESHOLD + 1),
+                    "thresholds are limited to 15 bits",
+                ),
+                (config.duration(128), "the duration is limited to 7 bits"),
+                (
+                    config.counter_mode(CounterMode::Decrement),
+                    "the duration counter mode needs a L3GD20H",
+                ),
+                (
+                    config.latch(LatchMode::LevelSensitive),
+                    "level sensitive latched interrupts need a L3GD20H",
+                ),
+            ];
+            for (config, error) in invalid.iter() {
+                let result = l3gd20.configure_interrupt(config).map(|_| ());
+                assert_eq!(result, Err(Error::InvalidConfig(error)));
+            }
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn interrupt_config_sets_dcrm_and_impen_on_the_l3gd20h() {
+        let mut spi = replay_h(
+            "
+            W 30 00
+            W 23 08                     # IMPen
+            W 72
+            W 80 00 00 64 00 00 00      # DCRM, Y: 100 counts
+            W 30 08                     # YHIE
+            T b0 00 -> 00 08
+            T a3 00 -> 00 08
+            W f2
+            T 00 00 00 00 00 00 00 -> 80 00 00 64 00 00 00
+            W 30 00
+            W 23 00                     # IMPen cleared before LIR is set
+            W 72
+            W 00 00 00 00 00 00 00
+            W 30 40
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_l3gd20h(&mut spi, NoCs).unwrap();
+            let config = Int1Config::default()
+                .high(Axis::Y)
+                .threshold(Axis::Y, 100)
+                .latch(LatchMode::LevelSensitive)
+                .counter_mode(CounterMode::Decrement);
+            l3gd20.configure_interrupt(&config).unwrap();
+            assert_eq!(l3gd20.interrupt_config(), Ok(config));
+
+            let lir = Int1Config::default().latch(LatchMode::Lir);
+            l3gd20.configure_interrupt(&lir).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+}
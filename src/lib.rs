@@ -1,6 +1,9 @@
 //! A platform agnostic driver to interface with the L3GD20 (gyroscope)
 //!
-//! This driver was built using [`embedded-hal`] traits.
+//! This driver was built using [`embedded-hal`] traits. The constructors
+//! take embedded-hal 0.2 peripherals with the `eh0` feature (the default),
+//! or embedded-hal 1.0 ones with the `eh1` feature instead; see the
+//! `interface` module.
 //!
 //! [`embedded-hal`]: https://docs.rs/embedded-hal/0.2
 //!
@@ -14,20 +17,345 @@
 #![deny(warnings)]
 #![no_std]
 
+#[cfg(all(feature = "eh0", feature = "eh1"))]
+compile_error!("the `eh0` and `eh1` features are mutually exclusive");
+#[cfg(not(any(feature = "eh0", feature = "eh1")))]
+compile_error!("one of the `eh0` and `eh1` features must be enabled");
 
+#[cfg(any(test, feature = "std"))]
+extern crate std;
 
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::ControlFlow;
+
+use embedded_hal::blocking::delay::DelayUs;
 use embedded_hal::blocking::spi::{Transfer, Write};
 use embedded_hal::digital::v2::OutputPin;
 use embedded_hal::spi::{Mode};
 
+#[cfg(feature = "std")]
+pub mod analysis;
+mod accumulator;
+mod autorange;
+mod buffered;
+#[cfg(feature = "float")]
+mod calibrated;
+mod clock;
+#[doc(hidden)]
+pub mod codec;
+mod config;
+mod correction;
+pub mod detect;
+mod duty;
+#[cfg(feature = "eh1")]
+pub mod eh1;
+mod events;
+mod fifo;
+pub mod filter;
+#[cfg(feature = "float")]
+mod float;
+#[cfg(feature = "float")]
+mod frequency;
+#[cfg(feature = "float")]
+mod gyroscope;
+#[cfg(feature = "float")]
+mod integrator;
+pub mod interface;
+mod interop;
+mod interrupt;
+pub mod logging;
+mod mapping;
+#[cfg(feature = "float")]
+mod noise;
+#[cfg(feature = "float")]
+mod poll;
+pub mod power;
+mod rate;
+pub mod registers;
+#[cfg(all(feature = "linux", target_os = "linux"))]
+pub mod linux;
+#[cfg(any(test, feature = "std"))]
+pub mod replay;
+pub mod scale;
+#[cfg(feature = "critical-section")]
+mod shared;
+mod spi16;
+#[cfg(feature = "critical-section")]
+mod split;
+mod staged;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(test)]
+mod testing;
+mod variant;
+mod watchdog;
+pub mod wire;
+
+pub use crate::accumulator::Accumulator;
+pub use crate::autorange::{AutoRange, RangeChange, RangedSample};
+#[cfg(target_has_atomic = "8")]
+pub use crate::buffered::{BlockConsumer, BlockProducer};
+pub use crate::buffered::{BufferedL3gd20, DoubleBuffer};
+#[cfg(feature = "float")]
+pub use crate::calibrated::{CalibratedL3gd20, CalibrationData, RateUnit};
+pub use crate::clock::{Clock, Timestamped};
+pub use crate::config::{AxesEnable, Config, ConfigMismatch, DataPath, PowerMode, Preset};
+pub use crate::correction::{AxisCorrection, Q15_ONE};
+pub use crate::duty::{DutyCycled, DEFAULT_SETTLE_US};
+pub use crate::events::Events;
+pub use crate::fifo::FifoMode;
+#[cfg(feature = "float")]
+pub use crate::float::{
+    fit_bias_model, BiasModel, F32x3, RateRounding, SENSITIVITY_DPS2000, SENSITIVITY_DPS250,
+    SENSITIVITY_DPS500,
+};
+#[cfg(feature = "float")]
+pub use crate::frequency::zero_crossing_frequency;
+#[cfg(feature = "float")]
+pub use crate::gyroscope::Gyroscope;
+#[cfg(feature = "float")]
+pub use crate::integrator::{AngleIntegrator, RevolutionCounter};
+pub use crate::interrupt::{
+    CounterMode, Int1Config, LatchMode, ThresholdPolicy, MAX_THRESHOLD, MIN_THRESHOLD_COUNTS,
+};
+pub use crate::mapping::{Axis, AxisMapping, AxisSource};
+#[cfg(feature = "float")]
+pub use crate::noise::{NoiseReport, VibrationReport};
+#[cfg(feature = "float")]
+pub use crate::poll::PolledSample;
+pub use crate::rate::{AngularRate, ScaledMeasurements};
+#[cfg(feature = "critical-section")]
+pub use crate::shared::SharedL3gd20;
+pub use crate::spi16::{Spi16, Spi16Error};
+#[cfg(feature = "critical-section")]
+pub use crate::split::{ConfigHandle, Reader};
+pub use crate::staged::StagedConfig;
+#[cfg(feature = "async")]
+pub use crate::stream::{FifoError, FifoStream, SampleStream, StreamError};
+pub use crate::variant::Variant;
+pub use crate::watchdog::FreezeWatchdog;
+
+use crate::autorange::RangeRuns;
+use crate::detect::PeakTracker;
+use crate::filter::{Decimator, OutlierFilter};
+use crate::interface::{CsInterface, SpiInterface};
+use crate::interrupt::ArmedThresholds;
+use crate::registers::{CtrlReg1, CtrlReg4, CtrlReg5, FifoSrc, LowOdr, Register};
+
+/// Time allowed for the software reset of the L3GD20H to complete
+const SW_RESET_TIME_US: u32 = 1_000;
+
 /// SPI mode
 pub const MODE: Mode = embedded_hal::spi::MODE_3;
 
+/// Chip select that does nothing, for SPI peripherals that drive NSS in
+/// hardware
+///
+/// See `L3gd20::new_hw_cs`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoCs;
+
+/// Level of the chip select pin that selects the sensor
+///
+/// See `L3gd20::new_with_cs_polarity`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsPolarity {
+    /// The sensor is selected while the pin is low, as on the NCS pin itself
+    #[default]
+    ActiveLow,
+    /// The sensor is selected while the pin is high, e.g. behind an
+    /// inverting level shifter
+    ActiveHigh,
+}
+
+impl OutputPin for NoCs {
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Errors returned by the higher level operations of the driver
+///
+/// The measurement and status accessors (e.g. `gyro` or `status`) return
+/// the bare bus error `E`; the operations that can fail for other reasons
+/// (timeouts, identity or configuration checks, write verification, ...)
+/// return this type, which wraps bus errors in `Error::Bus`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error<E> {
+    /// SPI bus error
+    Bus(E),
+    /// New data overwrote previously unread data
+    Overrun {
+        /// Per-axis overrun flags, `[x, y, z]`
+        axes: [bool; 3],
+    },
+    /// The configuration read back from the sensor doesn't match the
+    /// expected one
+    ConfigMismatch(ConfigMismatch),
+    /// A block read would run past the last register (`INT1_DURATION`)
+    BlockOutOfRange {
+        /// First register of the block
+        start: Register,
+        /// Requested number of bytes
+        len: usize,
+    },
+    /// A register holds a value that doesn't correspond to any setting
+    InvalidRegisterValue {
+        /// The register
+        reg: Register,
+        /// Value read from the register
+        value: u8,
+    },
+    /// No supported data rate satisfies the request
+    UnsupportedRate,
+    /// The sensor didn't reach the awaited state before the timeout expired
+    Timeout {
+        /// What was being waited for
+        waited_for: WaitTarget,
+    },
+    /// The outputs repeated the same sample too many times, see
+    /// `FreezeWatchdog`
+    SensorFrozen {
+        /// Number of consecutive identical samples
+        repeats: u32,
+    },
+    /// A value written to the sensor wasn't read back, see
+    /// `L3gd20::check_comms`
+    CommFault {
+        /// Value written
+        wrote: u8,
+        /// Value read back
+        read: u8,
+    },
+    /// `WHO_AM_I` doesn't identify the expected variant
+    WrongDevice {
+        /// The variant that was expected
+        expected: Variant,
+        /// Value read from `WHO_AM_I`
+        who_am_i: u8,
+    },
+    /// The sensor lost its configuration, e.g. after a brown-out; see
+    /// `L3gd20::set_reset_detection`
+    ConfigLost,
+    /// The requested configuration is invalid, or not supported by the
+    /// variant of the sensor
+    InvalidConfig(&'static str),
+    /// A register didn't read back as written, see
+    /// `L3gd20::set_write_verification`
+    VerifyFailed {
+        /// The register
+        reg: Register,
+        /// Value written
+        wrote: u8,
+        /// Value read back
+        read: u8,
+    },
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Bus(e) => write!(f, "bus error: {:?}", e),
+            Error::Overrun { axes } => write!(f, "data overrun on axes {:?}", axes),
+            Error::ConfigMismatch(_) => f.write_str("configuration read back doesn't match"),
+            Error::BlockOutOfRange { start, len } => {
+                write!(f, "block of {} bytes from {:?} out of range", len, start)
+            }
+            Error::InvalidRegisterValue { reg, value } => {
+                write!(f, "invalid value {:#04x} in {:?}", value, reg)
+            }
+            Error::UnsupportedRate => f.write_str("unsupported data rate"),
+            Error::Timeout { waited_for } => write!(f, "timeout waiting for {:?}", waited_for),
+            Error::SensorFrozen { repeats } => {
+                write!(f, "sensor frozen: {} identical samples", repeats)
+            }
+            Error::CommFault { wrote, read } => {
+                write!(f, "wrote {:#04x} but read back {:#04x}", wrote, read)
+            }
+            Error::WrongDevice { expected, who_am_i } => {
+                write!(f, "expected {:?} but WHO_AM_I is {:#04x}", expected, who_am_i)
+            }
+            Error::ConfigLost => f.write_str("sensor lost its configuration"),
+            Error::InvalidConfig(reason) => write!(f, "invalid configuration: {}", reason),
+            Error::VerifyFailed { reg, wrote, read } => {
+                write!(f, "wrote {:#04x} to {:?} but read back {:#04x}", wrote, reg, read)
+            }
+        }
+    }
+}
+
+/// Condition a wait loop of the driver was waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WaitTarget {
+    /// New data in `STATUS_REG`
+    DataReady,
+    /// Unread samples in the FIFO
+    FifoSamples,
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::Bus(e)
+    }
+}
 
 /// L3GD20 driver
 pub struct L3gd20<SPI, CS> {
     spi: SPI,
     cs: CS,
+    bias: I16x3,
+    #[cfg(feature = "float")]
+    bias_model: Option<BiasModel>,
+    saturation_count: u32,
+    // Last known values of CTRL_REG1..CTRL_REG5
+    shadow: [u8; 5],
+    mapping: AxisMapping,
+    retry: RetryPolicy,
+    retry_count: u32,
+    timeout_us: u32,
+    overrun_policy: OverrunPolicy,
+    overrun_count: u32,
+    discard_after_config: u8,
+    pending_discard: u8,
+    variant: Variant,
+    // `Low_ODR` as last written to `LOW_ODR`; always clear on the L3GD20
+    low_odr: bool,
+    bias_policy: BiasPolicy,
+    bias_stale: bool,
+    stats: Stats,
+    missed_samples: u32,
+    canary_interval: u16,
+    canary_countdown: u16,
+    reset_policy: ResetPolicy,
+    resets_detected: u32,
+    auto_range: Option<AutoRange>,
+    range_runs: RangeRuns,
+    correction: Option<AxisCorrection>,
+    cs_polarity: CsPolarity,
+    bandwidth_policy: BandwidthPolicy,
+    thresholds: ArmedThresholds,
+    threshold_policy: ThresholdPolicy,
+    verify_writes: bool,
+    verify_failures: u32,
+    // Register, value written and value read back of the last mismatch
+    verify_failure: Option<(Register, u8, u8)>,
+    // Ticks of the previous `poll_at_hz_with_clock`
+    #[cfg(feature = "float")]
+    last_poll: Option<u64>,
 }
 
 impl<SPI, CS, E> L3gd20<SPI, CS>
@@ -36,409 +364,3119 @@ where
     CS: OutputPin,
 {
     /// Creates a new driver from a SPI peripheral and a NCS pin
-    pub fn new(spi: SPI, cs: CS) -> Result<Self, E> {
-        let mut l3gd20 = L3gd20 { spi, cs };
+    ///
+    /// The peripherals are embedded-hal 0.2 or 1.0 ones, depending on the
+    /// `eh0` or `eh1` feature, see the `interface` module.
+    ///
+    /// The variant is detected from `WHO_AM_I` and initialised as
+    /// `new_l3gd20` or `new_l3gd20h` would. An unknown identity is treated
+    /// as a plain L3GD20.
+    pub fn new<S, P>(spi: S, cs: P) -> Result<Self, E>
+    where
+        S: SpiInterface<Spi = SPI>,
+        P: CsInterface<Cs = CS>,
+    {
+        L3gd20::new_with_cs_polarity(spi, cs, CsPolarity::ActiveLow)
+    }
 
-        // power up and enable all the axes
-        l3gd20.write_register(Register::CTRL_REG1, 0b00_00_1_111)?;
+    /// Same as `new`, with a chip select pin of the given `polarity`
+    ///
+    /// The pin is driven to its deselected level before the first
+    /// transaction.
+    pub fn new_with_cs_polarity<S, P>(spi: S, cs: P, polarity: CsPolarity) -> Result<Self, E>
+    where
+        S: SpiInterface<Spi = SPI>,
+        P: CsInterface<Cs = CS>,
+    {
+        L3gd20::connect(spi.into_spi(), cs.into_cs(), polarity)
+    }
+
+    /// `new_with_cs_polarity`, with the peripherals used by the driver
+    pub(crate) fn connect(spi: SPI, cs: CS, polarity: CsPolarity) -> Result<Self, E> {
+        let mut l3gd20 = L3gd20::from_parts(spi, cs);
+        l3gd20.cs_polarity = polarity;
+        l3gd20.deselect();
+
+        let id = l3gd20.who_am_i()?;
+        let variant = Variant::from_who_am_i(id).unwrap_or(Variant::L3gd20);
+        l3gd20.init(variant)?;
 
         Ok(l3gd20)
     }
 
-    /// Temperature measurement + gyroscope measurements
-    pub fn all(&mut self) -> Result<Measurements, E> {
-        let mut bytes = [0u8; 9];
-        self.read_many(Register::OUT_TEMP, &mut bytes)?;
+    /// Creates a new driver for a L3GD20
+    ///
+    /// Fails with `Error::WrongDevice` if `WHO_AM_I` doesn't read `0xD4`. The
+    /// chip select is deselected first, as for `new`.
+    pub fn new_l3gd20<S, P>(spi: S, cs: P) -> Result<Self, Error<E>>
+    where
+        S: SpiInterface<Spi = SPI>,
+        P: CsInterface<Cs = CS>,
+    {
+        L3gd20::new_variant(spi.into_spi(), cs.into_cs(), Variant::L3gd20)
+    }
 
-        Ok(Measurements {
-            gyro: I16x3 {
-                x: (bytes[3] as u16 + ((bytes[4] as u16) << 8)) as i16,
-                y: (bytes[5] as u16 + ((bytes[6] as u16) << 8)) as i16,
-                z: (bytes[7] as u16 + ((bytes[8] as u16) << 8)) as i16,
-            },
-            temp: bytes[1] as i8,
-        })
+    /// Creates a new driver for a L3GD20H
+    ///
+    /// Fails with `Error::WrongDevice` if `WHO_AM_I` doesn't read `0xD7`. The
+    /// chip select is deselected first, as for `new`.
+    /// Besides `CTRL_REG1`, `LOW_ODR` is cleared so the data rates are the
+    /// ones of the L3GD20, whatever was programmed before.
+    pub fn new_l3gd20h<S, P>(spi: S, cs: P) -> Result<Self, Error<E>>
+    where
+        S: SpiInterface<Spi = SPI>,
+        P: CsInterface<Cs = CS>,
+    {
+        L3gd20::new_variant(spi.into_spi(), cs.into_cs(), Variant::L3gd20h)
     }
 
-    /// Gyroscope measurements
-    pub fn gyro(&mut self) -> Result<I16x3, E> {
-        let mut bytes = [0u8; 7];
-        self.read_many(Register::OUT_X_L, &mut bytes)?;
+    fn new_variant(spi: SPI, cs: CS, expected: Variant) -> Result<Self, Error<E>> {
+        let mut l3gd20 = L3gd20::from_parts(spi, cs);
+        l3gd20.deselect();
 
-        Ok(I16x3 {
-            x: (bytes[1] as u16 + ((bytes[2] as u16) << 8)) as i16,
-            y: (bytes[3] as u16 + ((bytes[4] as u16) << 8)) as i16,
-            z: (bytes[5] as u16 + ((bytes[6] as u16) << 8)) as i16,
-        })
+        let who_am_i = l3gd20.who_am_i()?;
+        if who_am_i != expected.who_am_i() {
+            return Err(Error::WrongDevice { expected, who_am_i });
+        }
+        l3gd20.init(expected)?;
+
+        Ok(l3gd20)
     }
 
-    /// Temperature sensor measurement
-    pub fn temp(&mut self) -> Result<i8, E> {
-        Ok(self.read_register(Register::OUT_TEMP)? as i8)
+    /// Program the defaults of the driver for `variant` and record it
+    fn init(&mut self, variant: Variant) -> Result<(), E> {
+        self.variant = variant;
+
+        // power up and enable all the axes
+        #[allow(clippy::unusual_byte_groupings)]
+        self.write_register(Register::CTRL_REG1, 0b00_00_1_111)?;
+        if variant == Variant::L3gd20h {
+            // high data rates, SPI enabled
+            self.write_register(Register::LOW_ODR, 0)?;
+        }
+        self.low_odr = false;
+        self.resync_shadow()?;
+        Ok(())
     }
 
-    /// Reads the WHO_AM_I register; should return `0xD4`
-    pub fn who_am_i(&mut self) -> Result<u8, E> {
-        self.read_register(Register::WHO_AM_I)
+    /// Return the sensor to its power-on state without power cycling it
+    ///
+    /// These are the datasheet defaults, not the configuration programmed
+    /// by `new`: the sensor is left powered down. The L3GD20H is reset with
+    /// `SW_RES`; on the L3GD20 the reset values are written to `CTRL_REG1`
+    /// to `CTRL_REG5`, `REFERENCE`, `FIFO_CTRL_REG`, `INT1_CFG` and the
+    /// `INT1` thresholds and duration, in that order.
+    ///
+    /// The stored bias (and bias model) and any pending discards are
+    /// cleared; the axis mapping and the settings of the driver are kept.
+    pub fn reset_to_defaults<D: DelayUs<u32>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<&mut Self, Error<E>> {
+        self.verified(|l3gd20| match l3gd20.variant {
+            Variant::L3gd20h => {
+                let reset = LowOdr::default().with_sw_res(true);
+                l3gd20.write_register(Register::LOW_ODR, reset.bits())?;
+                delay.delay_us(SW_RESET_TIME_US);
+                Ok(())
+            }
+            Variant::L3gd20 => {
+                // CTRL_REG1..CTRL_REG5, then REFERENCE
+                let mut ctrl = [0u8; 6];
+                ctrl[..5].copy_from_slice(&Config::reset_defaults().registers());
+                l3gd20.write_registers(Register::CTRL_REG1, &ctrl)?;
+                l3gd20.write_register(Register::FIFO_CTRL_REG, 0)?;
+                l3gd20.write_register(Register::INT1_CFG, 0)?;
+                // INT1_TSH_XH..INT1_DURATION
+                l3gd20.write_registers(Register::INT1_TSH_XH, &[0; 7])
+            }
+        })?;
+
+        self.shadow = Config::reset_defaults().registers();
+        self.low_odr = false;
+        self.clear_thresholds();
+        self.pending_discard = 0;
+        self.bias = I16x3::default();
+        #[cfg(feature = "float")]
+        {
+            self.bias_model = None;
+        }
+
+        Ok(self)
     }
 
-    /// Read `STATUS_REG` of sensor
-    pub fn status(&mut self) -> Result<Status, E> {
-        let sts = self.read_register(Register::STATUS_REG)?;
-        Ok(Status::from_u8(sts))
+    /// Check that the bus works in both directions
+    ///
+    /// Writes distinctive patterns to `REFERENCE`, reads each back and fails
+    /// with `Error::CommFault` on the first mismatch, which catches buses
+    /// stuck at 0 or 1 that `WHO_AM_I` alone could miss. The original value of
+    /// `REFERENCE` is restored in every case, including failures; while the
+    /// check runs the high-pass filter (if enabled in reference mode) sees
+    /// the patterns as its reference. With write verification enabled, a
+    /// restore that doesn't read back fails with `Error::VerifyFailed`.
+    pub fn check_comms(&mut self) -> Result<(), Error<E>> {
+        const PATTERNS: [u8; 2] = [0b1010_0101, 0b0101_1010];
+
+        let original = self.read_register(Register::REFERENCE)?;
+        let mut result = Ok(());
+        for &wrote in PATTERNS.iter() {
+            result = self
+                .write_register(Register::REFERENCE, wrote)
+                .and_then(|_| self.read_register(Register::REFERENCE))
+                .map_err(Error::Bus)
+                .and_then(|read| {
+                    if read == wrote {
+                        Ok(())
+                    } else {
+                        Err(Error::CommFault { wrote, read })
+                    }
+                });
+            if result.is_err() {
+                break;
+            }
+        }
+
+        let restored =
+            self.verified(|l3gd20| l3gd20.write_register(Register::REFERENCE, original));
+        result?;
+        restored
     }
 
-    /// Get the current Output Data Rate
-    pub fn odr(&mut self) -> Result<Odr, E> {
-        // Read control register
-        let reg1 = self.read_register(Register::CTRL_REG1)?;
-        Ok(Odr::from_u8(reg1))
+    /// Detect sensor resets from the paced reads (e.g. `on_samples`)
+    ///
+    /// Every `interval` samples, `CTRL_REG1` is read (one extra 2-byte
+    /// transaction) and compared against the cached value; a sensor that
+    /// browned out reads back its power-on defaults. On a mismatch `policy`
+    /// decides between failing with `Error::ConfigLost` and writing the
+    /// cached `CTRL_REG1` to `CTRL_REG5` back, after which the samples set
+    /// by `discard_after_config` are dropped. An `interval` of 0 disables
+    /// the detection, which is the default.
+    pub fn set_reset_detection(&mut self, interval: u16, policy: ResetPolicy) -> &mut Self {
+        self.canary_interval = interval;
+        self.canary_countdown = interval;
+        self.reset_policy = policy;
+        self
     }
 
-    /// Set the Output Data Rate
-    pub fn set_odr(&mut self, odr: Odr) -> Result<&mut Self, E> {
-        self.change_config(Register::CTRL_REG1, odr)
+    /// Number of sensor resets detected, see `set_reset_detection`
+    pub fn resets_detected(&self) -> u32 {
+        self.resets_detected
     }
 
-    /// Get current Bandwidth
-    pub fn bandwidth(&mut self) -> Result<Bandwidth, E> {
-        let reg1 = self.read_register(Register::CTRL_REG1)?;
-        Ok(Bandwidth::from_u8(reg1))
+    /// Cheap check that the sensor is still responding and configured
+    ///
+    /// Two transactions: `WHO_AM_I` is compared against the recorded
+    /// `variant`, then `CTRL_REG1` to `STATUS_REG` are read in one burst and
+    /// the control registers compared against the cached values. A floating
+    /// bus (all bits read high or low) reports `Health::NotResponding`.
+    pub fn health_check(&mut self) -> Result<Health, Error<E>> {
+        if self.who_am_i()? != self.variant.who_am_i() {
+            return Ok(Health::NotResponding);
+        }
+
+        // CTRL_REG1..CTRL_REG5, REFERENCE, OUT_TEMP, STATUS_REG
+        let mut bytes = [0u8; 9];
+        self.read_many(Register::CTRL_REG1, &mut bytes)?;
+        if bytes[1..6] != self.shadow {
+            return Ok(Health::ConfigLost);
+        }
+
+        Ok(Health::Healthy)
     }
 
-    /// Set low-pass cut-off frequency (i.e. bandwidth)
+    /// Variant of the sensor
     ///
-    /// See `Bandwidth` for further explanation
-    pub fn set_bandwidth(&mut self, bw: Bandwidth) -> Result<&mut Self, E> {
-        self.change_config(Register::CTRL_REG1, bw)
+    /// Drivers not created by `new`, `new_l3gd20`, `new_l3gd20h` or
+    /// `new_with_config` assume a L3GD20.
+    pub fn variant(&self) -> Variant {
+        self.variant
     }
 
-    /// Get the current Full Scale Selection
+    /// Creates a new driver and configures the sensor according to `config`
     ///
-    /// This is the sensitivity of the sensor, see `Scale` for more information
-    pub fn scale(&mut self) -> Result<Scale, E> {
-        let scl = self.read_register(Register::CTRL_REG4)?;
-        Ok(Scale::from_u8(scl))
+    /// The sensor is initialised as by `new`, which deselects the chip select
+    /// and detects the variant, then `config` is written with `apply_config`
+    /// (including `LOW_ODR` on the L3GD20H). Fails with
+    /// `Error::InvalidConfig` if the data rate of `config` is not supported
+    /// by the variant.
+    pub fn new_with_config<S, P>(spi: S, cs: P, config: Config) -> Result<Self, Error<E>>
+    where
+        S: SpiInterface<Spi = SPI>,
+        P: CsInterface<Cs = CS>,
+    {
+        let mut l3gd20 = L3gd20::new(spi, cs)?;
+        l3gd20.apply_config(&config)?;
+        Ok(l3gd20)
     }
 
-    /// Set the Full Scale Selection
+    /// Check that the sensor is configured as `expected`
     ///
-    /// This sets the sensitivity of the sensor, see `Scale` for more
-    /// information
-    pub fn set_scale(&mut self, scale: Scale) -> Result<&mut Self, E> {
-        self.change_config(Register::CTRL_REG4, scale)
+    /// Reads back `CTRL_REG1` to `CTRL_REG5` (and `LOW_ODR` on the L3GD20H,
+    /// for the data rate) and returns `Error::ConfigMismatch` describing
+    /// every field that differs. See `config_diff` to get the differences
+    /// without matching on the error.
+    pub fn verify_config(&mut self, expected: &Config) -> Result<(), Error<E>> {
+        match self.config_diff(expected)? {
+            Some(mismatch) => Err(Error::ConfigMismatch(mismatch)),
+            None => Ok(()),
+        }
     }
 
-    fn read_register(&mut self, reg: Register) -> Result<u8, E> {
-        let _ = self.cs.set_low();
+    /// Differences between `expected` and the configuration of the sensor
+    ///
+    /// Reads back the same registers as `verify_config`; returns `None` if
+    /// the sensor is configured as `expected`.
+    pub fn config_diff(&mut self, expected: &Config) -> Result<Option<ConfigMismatch>, E> {
+        let mut bytes = [0u8; 6];
+        self.read_many(Register::CTRL_REG1, &mut bytes)?;
+        let low_odr = match self.variant {
+            Variant::L3gd20 => false,
+            Variant::L3gd20h => LowOdr::from_bits(self.read_register(Register::LOW_ODR)?).low_odr(),
+        };
+
+        let mut actual = [0u8; 5];
+        actual.copy_from_slice(&bytes[1..]);
+        Ok(expected.diff(actual, low_odr))
+    }
 
-        let mut buffer = [reg.addr() | SINGLE | READ, 0];
-        self.spi.transfer(&mut buffer)?;
+    /// Driver with empty state; doesn't touch the bus
+    fn from_parts(spi: SPI, cs: CS) -> Self {
+        L3gd20 {
+            spi,
+            cs,
+            bias: I16x3::default(),
+            #[cfg(feature = "float")]
+            bias_model: None,
+            saturation_count: 0,
+            shadow: Config::reset_defaults().registers(),
+            mapping: AxisMapping::IDENTITY,
+            retry: RetryPolicy::NONE,
+            retry_count: 0,
+            timeout_us: DEFAULT_TIMEOUT_US,
+            overrun_policy: OverrunPolicy::Error,
+            overrun_count: 0,
+            discard_after_config: 0,
+            pending_discard: 0,
+            variant: Variant::L3gd20,
+            low_odr: false,
+            bias_policy: BiasPolicy::Rescale,
+            bias_stale: false,
+            stats: Stats::default(),
+            missed_samples: 0,
+            canary_interval: 0,
+            canary_countdown: 0,
+            reset_policy: ResetPolicy::Error,
+            resets_detected: 0,
+            auto_range: None,
+            range_runs: RangeRuns::default(),
+            correction: None,
+            cs_polarity: CsPolarity::ActiveLow,
+            bandwidth_policy: BandwidthPolicy::Keep,
+            thresholds: ArmedThresholds::default(),
+            threshold_policy: ThresholdPolicy::Strict,
+            verify_writes: false,
+            verify_failures: 0,
+            verify_failure: None,
+            #[cfg(feature = "float")]
+            last_poll: None,
+        }
+    }
 
-        let _ = self.cs.set_high();
+    /// Write a complete configuration to `CTRL_REG1` to `CTRL_REG5`
+    ///
+    /// The registers are written in a single burst transaction. Any setting
+    /// not covered by `Config` is reset to its default value. The bandwidth
+    /// is checked against the data rate according to the `BandwidthPolicy`.
+    /// On the L3GD20H `LOW_ODR` is written first if the data rate needs it;
+    /// a low data rate fails with `Error::InvalidConfig` on the L3GD20.
+    pub fn apply_config(&mut self, config: &Config) -> Result<&mut Self, Error<E>> {
+        self.check_odr(config.odr)?;
+        let bandwidth = self.check_bandwidth(config.odr, config.bandwidth)?;
+        let config = config.bandwidth(bandwidth);
 
-        Ok(buffer[1])
+        // All five registers are written in a single burst, so the sensor
+        // is never left partially configured between transactions
+        let regs = config.registers();
+        self.verified(|l3gd20| {
+            l3gd20.write_low_odr(config.odr.low_odr())?;
+            l3gd20.write_registers(Register::CTRL_REG1, &regs)
+        })?;
+        Ok(self)
     }
 
-    /// Read multiple bytes starting from the `start_reg` register.
-    /// This function will attempt to fill the provided buffer.
-    fn read_many(&mut self,
-                 start_reg: Register,
-                 buffer: &mut [u8])
-                 -> Result<(), E> {
-        let _ = self.cs.set_low();
-        buffer[0] = start_reg.addr() | MULTI | READ ;
-        self.spi.transfer(buffer)?;
-        let _ = self.cs.set_high();
+    /// Temperature measurement + gyroscope measurements
+    ///
+    /// The stored bias (see `set_bias`) is subtracted from the gyroscope
+    /// measurements
+    pub fn all(&mut self) -> Result<Measurements, E> {
+        let (gyro, temp) = self.read_all_raw()?;
 
-        Ok(())
+        Ok(Measurements {
+            gyro: self.adjust(gyro),
+            temp,
+        })
     }
 
+    /// Gyroscope measurements
+    ///
+    /// The stored bias (see `set_bias`) is subtracted from the measurements
+    pub fn gyro(&mut self) -> Result<I16x3, E> {
+        let raw = self.gyro_raw()?;
+        Ok(self.adjust(raw))
+    }
 
-    fn write_register(&mut self, reg: Register, byte: u8) -> Result<(), E> {
-        let _ = self.cs.set_low();
+    /// Gyroscope measurements, timestamped with `clock`
+    ///
+    /// The clock is read once, immediately before the SPI transaction. The
+    /// stored bias is subtracted from the measurements, as for `gyro`.
+    pub fn gyro_timestamped<C: Clock>(
+        &mut self,
+        clock: &mut C,
+    ) -> Result<Timestamped<I16x3>, E> {
+        let ticks = clock.now();
+        let sample = self.gyro()?;
+        Ok(Timestamped { sample, ticks })
+    }
 
-        let buffer = [reg.addr() | SINGLE | WRITE, byte];
-        self.spi.write(&buffer)?;
+    /// Gyroscope measurements, without bias compensation
+    pub fn gyro_raw(&mut self) -> Result<I16x3, E> {
+        let mut bytes = [0u8; 7];
+        self.read_many(Register::OUT_X_L, &mut bytes)?;
 
-        let _ = self.cs.set_high();
+        Ok(I16x3::from_le_bytes(&bytes[1..7]))
+    }
 
-        Ok(())
+    /// X-axis gyroscope measurement, see `gyro_axis`
+    pub fn gyro_x(&mut self) -> Result<i16, E> {
+        self.gyro_axis(Axis::X)
     }
 
-    /// Change configuration in register
-    ///
-    /// Helper function to update a particular part of a register without
-    /// affecting other parts of the register that might contain desired
-    /// configuration. This allows the `L3gd20` struct to be used like
-    /// a builder interface when configuring specific parameters.
-    fn change_config<B: BitValue>(&mut self, reg: Register, bits: B) -> Result<&mut Self, E> {
-        // Create bit mask from width and shift of value
-        let mask = B::mask() << B::shift();
-        // Extract the value as u8
-        let bits = (bits.value() << B::shift()) & mask;
-        // Read current value of register
-        let current = self.read_register(reg)?;
-        // Use supplied mask so we don't affect more than necessary
-        let masked = current & !mask;
-        // Use `or` to apply the new value without affecting other parts
-        let new_reg = masked | bits;
-        self.write_register(reg, new_reg)?;
-        Ok(self)
+    /// Y-axis gyroscope measurement, see `gyro_axis`
+    pub fn gyro_y(&mut self) -> Result<i16, E> {
+        self.gyro_axis(Axis::Y)
     }
-}
 
-/// Trait to represent a value that can be sent to sensor
-trait BitValue {
-    /// The width of the bitfield in bits
-    fn width() -> u8;
-    /// The bit 'mask' of the value
-    fn mask() -> u8 {
-        (1 << Self::width()) - 1
-    }
-    /// The number of bits to shift the mask by
-    fn shift() -> u8;
-    /// Convert the type to a byte value to be sent to sensor
-    ///
-    /// # Note
-    /// This value should not be bit shifted.
-    fn value(&self) -> u8;
-}
-
-#[allow(dead_code)]
-#[allow(non_camel_case_types)]
-#[derive(Clone, Copy)]
-enum Register {
-    WHO_AM_I = 0x0F,
-    CTRL_REG1 = 0x20,
-    CTRL_REG2 = 0x21,
-    CTRL_REG3 = 0x22,
-    CTRL_REG4 = 0x23,
-    CTRL_REG5 = 0x24,
-    REFERENCE = 0x25,
-    OUT_TEMP = 0x26,
-    STATUS_REG = 0x27,
-    OUT_X_L = 0x28,
-    OUT_X_H = 0x29,
-    OUT_Y_L = 0x2A,
-    OUT_Y_H = 0x2B,
-    OUT_Z_L = 0x2C,
-    OUT_Z_H = 0x2D,
-    FIFO_CTRL_REG = 0x2E,
-    FIFO_SRC_REG = 0x2F,
-    INT1_CFG = 0x30,
-    INT1_SRC = 0x31,
-    INT1_TSH_XH = 0x32,
-    INT1_TSH_XL = 0x33,
-    INT1_TSH_YH = 0x34,
-    INT1_TSH_YL = 0x35,
-    INT1_TSH_ZH = 0x36,
-    INT1_TSH_ZL = 0x37,
-    INT1_DURATION = 0x38,
-}
+    /// Z-axis gyroscope measurement, see `gyro_axis`
+    pub fn gyro_z(&mut self) -> Result<i16, E> {
+        self.gyro_axis(Axis::Z)
+    }
+
+    /// Gyroscope measurement of a single output axis
+    ///
+    /// Only the two output registers of the physical axis selected by the
+    /// axis mapping are read, in a single burst. The stored bias of that axis
+    /// is subtracted and the mapping applied, like for `gyro`.
+    ///
+    /// With Block Data Update enabled the low and high bytes are guaranteed
+    /// to belong to the same sample; nothing links the measurements returned
+    /// by consecutive calls for different axes, though.
+    pub fn gyro_axis(&mut self, axis: Axis) -> Result<i16, E> {
+        let source = self.mapping.sources()[axis as usize];
+        let index = source.axis as usize;
+        let raw = self.read_axes_raw(index, index)?;
+        Ok(self.adjust_axis(source, raw.component(source.axis)))
+    }
 
+    /// Gyroscope measurements of the axes with new data only
+    ///
+    /// `STATUS_REG` is read first, then only the output registers of the
+    /// physical axes whose `XDA`/`YDA`/`ZDA` flags are set. Adjacent axes are
+    /// read in a single burst; if only X and Z have new data they are read
+    /// with two bursts so the stale Y registers are skipped. The stored bias
+    /// is subtracted and the axis mapping applied, like for `gyro`.
+    pub fn read_new(&mut self) -> Result<PartialSample, E> {
+        let status = self.status()?;
+        let fresh = [status.x_new(), status.y_new(), status.z_new()];
 
+        let raw = match fresh {
+            [true, false, true] => {
+                let x = self.read_axes_raw(0, 0)?;
+                let z = self.read_axes_raw(2, 2)?;
+                I16x3 { x: x.x, y: 0, z: z.z }
+            }
+            _ => match (
+                fresh.iter().position(|&f| f),
+                fresh.iter().rposition(|&f| f),
+            ) {
+                (Some(first), Some(last)) => self.read_axes_raw(first, last)?,
+                _ => I16x3::default(),
+            },
+        };
 
-/// Output Data Rate
-#[derive(Debug, Clone, Copy)]
-pub enum Odr {
-    /// 95 Hz data rate
-    Hz95 = 0x00,
-    /// 190 Hz data rate
-    Hz190 = 0x01,
-    /// 380 Hz data rate
-    Hz380 = 0x02,
-    /// 760 Hz data rate
-    Hz760 = 0x03,
-}
+        let pick = |source: AxisSource| {
+            if fresh[source.axis as usize] {
+                Some(self.adjust_axis(source, raw.component(source.axis)))
+            } else {
+                None
+            }
+        };
+        let [x, y, z] = self.mapping.sources();
+        Ok(PartialSample {
+            x: pick(x),
+            y: pick(y),
+            z: pick(z),
+        })
+    }
 
-impl BitValue for Odr {
-    fn width() -> u8 {
-        2
+    /// Gyroscope measurement of a single output axis converted to
+    /// milli-degrees per second
+    ///
+    /// See `gyro_axis`; like `gyro_mdps` this uses the cached `Scale`.
+    pub fn gyro_axis_mdps(&mut self, axis: Axis) -> Result<i32, Error<E>> {
+        let scale = self.current_scale()?;
+        Ok(scale.mdps(self.gyro_axis(axis)?))
     }
-    fn shift() -> u8 {
-        6
+
+    /// Gyroscope measurements smoothed by a moving average
+    ///
+    /// Takes a measurement (as `gyro` does), adds it to `state` and returns
+    /// the average of the window.
+    pub fn gyro_smoothed<const N: usize>(
+        &mut self,
+        state: &mut filter::MovingAverage<N>,
+    ) -> Result<I16x3, E> {
+        let sample = self.gyro()?;
+        Ok(state.push(sample))
     }
-    fn value(&self) -> u8 {
-        *self as u8
+
+    /// Gyroscope measurements, failing if data was overrun
+    ///
+    /// `STATUS_REG` is read in the same burst as the gyroscope data. If its
+    /// overrun flag is set, i.e. at least one sample was overwritten before
+    /// being read, `Error::Overrun` is returned. The stored bias is
+    /// subtracted from the measurements.
+    pub fn gyro_strict(&mut self) -> Result<I16x3, Error<E>> {
+        let (status, raw) = self.read_status_and_gyro()?;
+        if status.overrun() {
+            return Err(overrun_error(status));
+        }
+
+        Ok(self.adjust(raw))
     }
-}
 
-impl Odr {
-    fn from_u8(from: u8) -> Self {
-        // Extract ODR value, converting to enum (ROI: 0b1100_0000)
-        match (from >> Odr::shift()) & Odr::mask() {
-            x if x == Odr::Hz95 as u8 => Odr::Hz95,
-            x if x == Odr::Hz190 as u8 => Odr::Hz190,
-            x if x == Odr::Hz380 as u8 => Odr::Hz380,
-            x if x == Odr::Hz760 as u8 => Odr::Hz760,
-            _ => unreachable!(),
+    /// Fill `out` with consecutive measurements
+    ///
+    /// When the FIFO is enabled (`FIFO_EN` in the cached `CTRL_REG5`) the
+    /// samples are drained from the FIFO, otherwise each one is read as soon
+    /// as `STATUS_REG` reports new data. Fails with `Error::Overrun` if a
+    /// sample was lost after the first one was read; an overrun already
+    /// pending at the start is ignored. Each sample must arrive within twice
+    /// the sample period (plus one polling interval), or the default
+    /// timeout if shorter, otherwise `Error::Timeout` is returned. The stored
+    /// bias is subtracted from the measurements.
+    pub fn read_exact<D: DelayUs<u32>>(
+        &mut self,
+        out: &mut [I16x3],
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        self.read_exact_raw(out, delay, false)?;
+        for sample in out.iter_mut() {
+            *sample = self.adjust(*sample);
         }
+        Ok(())
     }
-}
 
-/// Full scale selection
-#[derive(Debug, Clone, Copy)]
-pub enum Scale {
-    /// 250 Degrees Per Second
-    Dps250 = 0x00,
-    /// 500 Degrees Per Second
-    Dps500 = 0x01,
-    /// 2000 Degrees Per Second
-    Dps2000 = 0x03,
-}
+    /// `read_exact` without bias compensation nor axis mapping
+    ///
+    /// With `continuing` an overrun pending at the start fails as well, for
+    /// callers that read a longer sequence in several calls.
+    pub(crate) fn read_exact_raw<D: DelayUs<u32>>(
+        &mut self,
+        out: &mut [I16x3],
+        delay: &mut D,
+        continuing: bool,
+    ) -> Result<(), Error<E>> {
+        let budget = self.dt_us()?.saturating_mul(2).saturating_add(POLL_INTERVAL_US);
+        let default_timeout = self.timeout_us;
+        self.timeout_us = default_timeout.min(budget);
 
-impl BitValue for Scale {
-    fn width() -> u8 {
-        2
-    }
-    fn shift() -> u8 {
-        4
+        let result = if CtrlReg5::from_bits(self.shadow[4]).fifo_en() {
+            self.drain_fifo_exact(out, delay, continuing)
+        } else {
+            self.read_paced_exact(out, delay, continuing)
+        };
+
+        self.timeout_us = default_timeout;
+        result
     }
-    fn value(&self) -> u8 {
-        *self as u8
+
+    fn read_paced_exact<D: DelayUs<u32>>(
+        &mut self,
+        out: &mut [I16x3],
+        delay: &mut D,
+        continuing: bool,
+    ) -> Result<(), Error<E>> {
+        for (i, sample) in out.iter_mut().enumerate() {
+            self.wait_data_ready(delay)?;
+            let (status, raw) = self.read_status_and_gyro()?;
+            if (i > 0 || continuing) && status.overrun() {
+                return Err(overrun_error(status));
+            }
+            *sample = raw;
+        }
+        Ok(())
     }
-}
 
-impl Scale {
-    fn from_u8(from: u8) -> Self {
-        // Extract scale value from register, ensure that we mask with
-        // `0b0000_0011` to extract `FS1-FS2` part of register
-        match (from >> Scale::shift()) & Scale::mask() {
-            x if x == Scale::Dps250 as u8 => Scale::Dps250,
-            x if x == Scale::Dps500 as u8 => Scale::Dps500,
-            x if x == Scale::Dps2000 as u8 => Scale::Dps2000,
-            // Special case for Dps2000
-            0x02 => Scale::Dps2000,
-            _ => unreachable!(),
+    fn drain_fifo_exact<D: DelayUs<u32>>(
+        &mut self,
+        out: &mut [I16x3],
+        delay: &mut D,
+        continuing: bool,
+    ) -> Result<(), Error<E>> {
+        let mut filled = 0;
+        while filled < out.len() {
+            self.wait_until(delay, WaitTarget::FifoSamples, |l3gd20| {
+                Ok(!l3gd20.fifo_status()?.empty())
+            })?;
+
+            let src = self.fifo_status()?;
+            if (filled > 0 || continuing) && src.ovrn() {
+                return Err(Error::Overrun { axes: [true; 3] });
+            }
+            let available = usize::from(src.fss()).min(out.len() - filled);
+            for sample in out[filled..filled + available].iter_mut() {
+                *sample = self.gyro_raw()?;
+            }
+            filled += available;
         }
+        Ok(())
     }
-}
 
-/// Bandwidth of sensor
-///
-/// The bandwidth of the sensor is equal to the cut-off for the low-pass
-/// filter. The cut-off depends on the `Odr` of the sensor, for specific
-/// information consult the data sheet.
-#[derive(Debug, Clone, Copy)]
-pub enum Bandwidth {
-    /// Lowest possible cut-off for any `Odr` configuration
-    Low = 0x00,
-    /// Medium cut-off, can be the same as `High` for some `Odr` configurations
-    Medium = 0x01,
-    /// High cut-off
-    High = 0x02,
-    /// Maximum cut-off for any `Odr` configuration
-    Maximum = 0x03,
-}
+    /// Deliver `count` measurements to `f`, each as soon as `STATUS_REG`
+    /// reports new data
+    ///
+    /// `f` can stop the acquisition early by returning
+    /// `ControlFlow::Break`. Returns the number of measurements delivered,
+    /// including the one for which `f` returned `Break`. Overruns are handled
+    /// according to the overrun policy (see `set_overrun_policy`). The stored
+    /// bias is subtracted from the measurements.
+    pub fn on_samples<D, F>(
+        &mut self,
+        count: u32,
+        delay: &mut D,
+        mut f: F,
+    ) -> Result<u32, Error<E>>
+    where
+        D: DelayUs<u32>,
+        F: FnMut(I16x3) -> ControlFlow<()>,
+    {
+        for delivered in 1..=count {
+            self.wait_data_ready(delay)?;
+            let (status, raw) = self.read_status_and_gyro()?;
+            if status.overrun() {
+                match self.overrun_policy {
+                    OverrunPolicy::Error => return Err(overrun_error(status)),
+                    OverrunPolicy::Count => {
+                        self.overrun_count = self.overrun_count.saturating_add(1);
+                    }
+                }
+            }
 
-impl BitValue for Bandwidth {
-    fn width() -> u8 {
-        2
+            if f(self.adjust(raw)).is_break() {
+                return Ok(delivered);
+            }
+        }
+        Ok(count)
     }
-    fn shift() -> u8 {
-        4
+
+    /// Collect `N` consecutive measurements, each as soon as `STATUS_REG`
+    /// reports new data
+    ///
+    /// Fails with `Error::Overrun`, regardless of the overrun policy, if a
+    /// sample was lost, so the batch has no gaps. The stored bias is
+    /// subtracted from the measurements.
+    pub fn read_batch<const N: usize, D: DelayUs<u32>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<[I16x3; N], Error<E>> {
+        let mut batch = [I16x3::default(); N];
+        for sample in batch.iter_mut() {
+            self.wait_data_ready(delay)?;
+            *sample = self.gyro_strict()?;
+        }
+        Ok(batch)
     }
-    fn value(&self) -> u8 {
-        *self as u8
+
+    /// Gyroscope measurements, checked by `watchdog`
+    ///
+    /// Returns `Error::SensorFrozen` once `watchdog` considers the outputs
+    /// frozen; the sensor can then be recovered with `reset_to_defaults`
+    /// followed by `apply_config`, and the watchdog reset. While the sensor
+    /// is powered down or has no axis enabled (according to the cached
+    /// `CTRL_REG1`) constant outputs are expected, so the watchdog is reset
+    /// instead of fed.
+    pub fn gyro_watched(&mut self, watchdog: &mut FreezeWatchdog) -> Result<I16x3, Error<E>> {
+        let raw = self.gyro_raw()?;
+
+        let ctrl1 = CtrlReg1::from_bits(self.shadow[0]);
+        if ctrl1.power() == PowerMode::PowerDown || ctrl1.axes() == AxesEnable::NONE {
+            watchdog.reset();
+        } else if watchdog.feed(raw) {
+            return Err(Error::SensorFrozen {
+                repeats: watchdog.repeats(),
+            });
+        }
+
+        Ok(self.adjust(raw))
     }
-}
 
-impl Bandwidth {
-    fn from_u8(from: u8) -> Self {
-        // Shift and mask bandwidth of register, (ROI: 0b0011_0000)
-        match (from >> Bandwidth::shift()) & Bandwidth::mask() {
-            x if x == Bandwidth::Low as u8 => Bandwidth::Low,
-            x if x == Bandwidth::Medium as u8 => Bandwidth::Medium,
-            x if x == Bandwidth::High as u8 => Bandwidth::High,
-            x if x == Bandwidth::Maximum as u8 => Bandwidth::Maximum,
-            _ => unreachable!(),
+    /// Next output of `decimator`, fed measurements as `on_samples` delivers
+    /// them
+    ///
+    /// Reads as many samples as needed to complete the current block of
+    /// `decimator`.
+    pub fn gyro_decimated<D: DelayUs<u32>>(
+        &mut self,
+        decimator: &mut Decimator,
+        delay: &mut D,
+    ) -> Result<I16x3, Error<E>> {
+        loop {
+            let mut output = None;
+            self.on_samples(1, delay, |sample| {
+                output = decimator.push(sample);
+                ControlFlow::Continue(())
+            })?;
+            if let Some(output) = output {
+                return Ok(output);
+            }
         }
     }
-}
 
-const READ: u8 = 1 << 7;
-const WRITE: u8 = 0 << 7;
-const MULTI: u8 = 1 << 6;
-const SINGLE: u8 = 0 << 6;
+    /// Gyroscope measurements passed through `filter`
+    ///
+    /// Returns the filtered previous measurement, i.e. the output lags by
+    /// one read; `None` on the first read. The stored bias is subtracted
+    /// before filtering.
+    pub fn gyro_filtered(&mut self, filter: &mut OutlierFilter) -> Result<Option<I16x3>, E> {
+        let sample = self.gyro()?;
+        Ok(filter.push(sample))
+    }
 
-impl Register {
-    fn addr(self) -> u8 {
-        self as u8
+    /// Feed the next `count` measurements to `tracker`, see `on_samples`
+    ///
+    /// Returns the number of measurements fed.
+    pub fn track_peaks_for<D: DelayUs<u32>>(
+        &mut self,
+        count: u32,
+        delay: &mut D,
+        tracker: &mut PeakTracker,
+    ) -> Result<u32, Error<E>> {
+        self.on_samples(count, delay, |sample| {
+            tracker.push(sample);
+            ControlFlow::Continue(())
+        })
     }
-}
 
-impl Scale {
-    /// Convert a measurement to degrees
-    pub fn degrees(&self, val: i16) -> f32 {
-        match *self {
-            Scale::Dps250 => val as f32 * 0.00875,
-            Scale::Dps500 => val as f32 * 0.0175,
-            Scale::Dps2000 => val as f32 * 0.07,
-        }
+    /// Time between two samples at the configured data rate, in
+    /// microseconds
+    ///
+    /// Uses the cached `CTRL_REG1`; doesn't touch the bus.
+    pub fn dt_us(&self) -> Result<u32, Error<E>> {
+        Ok(self.odr_of(self.shadow[0]).period_us())
     }
 
-    /// Convert a measurement to radians
-    pub fn radians(&self, val: i16) -> f32 {
-        // TODO: Use `to_radians` or other built in method
-        // NOTE: `to_radians` is only exported in `std` (07.02.18)
-        self.degrees(val) * (core::f32::consts::PI / 180.0)
+    /// Samples lost because they were overwritten before being read
+    ///
+    /// Counted by the reads that fetch `STATUS_REG` together with the
+    /// measurements (e.g. `on_samples` and the reads built on it). This is a
+    /// lower bound: the overrun flag only tells that at least one sample
+    /// was lost since the previous read, so each overrun counts as one.
+    /// An integrator can use it to stretch its time step when it fell
+    /// behind.
+    pub fn missed_samples(&self) -> u32 {
+        self.missed_samples
     }
-}
 
-/// XYZ triple
-#[derive(Debug)]
-pub struct I16x3 {
-    /// X component
-    pub x: i16,
-    /// Y component
-    pub y: i16,
-    /// Z component
-    pub z: i16,
-}
+    /// Reset the counter returned by `missed_samples` to zero
+    pub fn reset_missed_samples(&mut self) -> &mut Self {
+        self.missed_samples = 0;
+        self
+    }
 
-/// Several measurements
-#[derive(Debug)]
-pub struct Measurements {
-    /// Gyroscope measurements
-    pub gyro: I16x3,
-    /// Temperature sensor measurement
-    pub temp: i8,
-}
+    /// Set how overruns are handled by the acquisition loops (e.g.
+    /// `on_samples`)
+    pub fn set_overrun_policy(&mut self, policy: OverrunPolicy) -> &mut Self {
+        self.overrun_policy = policy;
+        self
+    }
+
+    /// Get the current overrun policy
+    pub fn overrun_policy(&self) -> OverrunPolicy {
+        self.overrun_policy
+    }
+
+    /// Number of overruns counted under `OverrunPolicy::Count`
+    pub fn overrun_count(&self) -> u32 {
+        self.overrun_count
+    }
+
+    /// Reset the overrun counter to zero
+    pub fn reset_overrun_count(&mut self) -> &mut Self {
+        self.overrun_count = 0;
+        self
+    }
+
+    /// Gyroscope measurements plus per-axis saturation flags
+    ///
+    /// An axis is flagged as saturated when its raw (uncompensated) value is
+    /// within `SATURATION_MARGIN` counts of the `i16` limits. Every sample
+    /// with at least one saturated axis increments the counter returned by
+    /// `saturation_count`.
+    pub fn gyro_checked(&mut self) -> Result<CheckedSample, E> {
+        let raw = self.gyro_raw()?;
+        let saturated = raw.saturated_axes();
+
+        if saturated.iter().any(|&s| s) {
+            self.saturation_count = self.saturation_count.saturating_add(1);
+        }
+
+        Ok(CheckedSample {
+            gyro: self.adjust(raw),
+            saturated,
+        })
+    }
+
+    /// Number of saturated samples seen by `gyro_checked`
+    ///
+    /// The counter stops at `u32::MAX`.
+    pub fn saturation_count(&self) -> u32 {
+        self.saturation_count
+    }
+
+    /// Reset the saturation counter to zero
+    pub fn reset_saturation_count(&mut self) -> &mut Self {
+        self.saturation_count = 0;
+        self
+    }
+
+    /// Gyroscope measurements converted to milli-degrees per second
+    ///
+    /// Uses integer math only; the constant bias is subtracted before the
+    /// conversion and the `AxisCorrection`, if any, applied after it. Like
+    /// `gyro_dps` this uses the cached `Scale`.
+    pub fn gyro_mdps(&mut self) -> Result<I32x3, Error<E>> {
+        let scale = self.current_scale()?;
+        Ok(self.gyro_mdps_at(scale)?)
+    }
+
+    /// `gyro_mdps` converted with `scale`
+    pub(crate) fn gyro_mdps_at(&mut self, scale: Scale) -> Result<I32x3, E> {
+        let mdps = scale.mdps_vec(&self.gyro()?);
+        Ok(match self.correction {
+            Some(correction) => correction.apply_mdps(mdps),
+            None => mdps,
+        })
+    }
+
+    /// Gyroscope measurements as an `AngularRate`
+    ///
+    /// The constant bias is subtracted and the axis mapping applied; the
+    /// conversion to a unit is left to the caller. Like `gyro_mdps` this uses
+    /// the cached `Scale`.
+    pub fn gyro_rate(&mut self) -> Result<AngularRate, Error<E>> {
+        let scale = self.current_scale()?;
+        let gyro = self.gyro()?;
+
+        Ok(AngularRate::new(gyro, scale))
+    }
+
+    /// Set the zero-rate bias that is subtracted from every measurement
+    ///
+    /// The bias is expressed in raw counts, i.e. it depends on the `Scale`
+    /// that was active when it was measured. `set_scale` converts or clears
+    /// it according to the `BiasPolicy`.
+    ///
+    /// Subtraction saturates at the limits of `i16` rather than wrapping.
+    pub fn set_bias(&mut self, bias: I16x3) -> &mut Self {
+        self.bias = bias;
+        self.bias_stale = false;
+        self
+    }
+
+    /// Set what `set_scale` does with the stored bias
+    pub fn set_bias_policy(&mut self, policy: BiasPolicy) -> &mut Self {
+        self.bias_policy = policy;
+        self
+    }
+
+    /// Get the current bias policy
+    pub fn bias_policy(&self) -> BiasPolicy {
+        self.bias_policy
+    }
+
+    /// `true` if a change of scale cleared the bias, which has to be
+    /// measured again
+    ///
+    /// Reset by `set_bias`.
+    pub fn bias_stale(&self) -> bool {
+        self.bias_stale
+    }
+
+    /// Get the currently stored bias, in raw counts
+    pub fn bias(&self) -> I16x3 {
+        self.bias
+    }
+
+    /// Reset the stored bias to zero
+    pub fn clear_bias(&mut self) -> &mut Self {
+        self.bias = I16x3::default();
+        self
+    }
+
+    /// Set the mapping from the physical sensor axes to the output axes
+    ///
+    /// The mapping is applied by every read path except the raw ones (e.g.
+    /// `gyro_raw`). The bias is expressed in the physical sensor frame and is
+    /// subtracted before the mapping is applied.
+    pub fn set_axis_mapping(&mut self, mapping: AxisMapping) -> &mut Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Get the current axis mapping
+    pub fn axis_mapping(&self) -> AxisMapping {
+        self.mapping
+    }
+
+    /// Set the policy used to retry SPI transactions that failed
+    ///
+    /// Every transaction is retried except reads of the output registers
+    /// while the FIFO is enabled (`FIFO_EN` in `CTRL_REG5`), which pop
+    /// samples from the FIFO, and reads including `INT1_SRC`, which clear a
+    /// latched interrupt: a retry would silently skip data or lose the
+    /// event. NCS is deasserted between attempts.
+    pub fn set_retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Get the current retry policy
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    /// Number of SPI transactions that were retried
+    pub fn retry_count(&self) -> u32 {
+        self.retry_count
+    }
+
+    /// Reset the retry counter to zero
+    pub fn reset_retry_count(&mut self) -> &mut Self {
+        self.retry_count = 0;
+        self
+    }
+
+    /// Read back every register write and compare it with the value written
+    ///
+    /// Self-clearing bits (`BOOT` in `CTRL_REG5`, `SW_RES` in `LOW_ODR`) are
+    /// not compared; a burst write is checked with one burst read. The
+    /// configuration methods (e.g. `set_odr`, `set_scale`, `set_fifo_mode`,
+    /// `apply_config`, `StagedConfig::commit`) fail with `Error::VerifyFailed`
+    /// on a mismatch, after the remaining writes of the call. Mismatches are
+    /// also counted by `verify_failures`.
+    pub fn set_write_verification(&mut self, enabled: bool) -> &mut Self {
+        self.verify_writes = enabled;
+        self
+    }
+
+    /// `true` if writes are read back, see `set_write_verification`
+    pub fn write_verification(&self) -> bool {
+        self.verify_writes
+    }
+
+    /// Number of writes that didn't read back as written; stops at
+    /// `u32::MAX`
+    pub fn verify_failures(&self) -> u32 {
+        self.verify_failures
+    }
+
+    /// Reset the counter returned by `verify_failures` to zero
+    pub fn reset_verify_failures(&mut self) -> &mut Self {
+        self.verify_failures = 0;
+        self
+    }
+
+    /// Discard the first `n` samples after each configuration change
+    ///
+    /// The first samples after e.g. a change of `Scale` or `Bandwidth` are
+    /// still affected by the old settings. After a write that actually
+    /// changes a control register (other than `CTRL_REG3`), the methods
+    /// paced by `STATUS_REG` (e.g. `on_samples`, `read_batch`) silently read
+    /// and drop the next `n` samples. Other read methods are not affected.
+    /// `n` is a number of samples, so the time it covers scales with the
+    /// `Odr`. Defaults to 0, i.e. nothing is discarded.
+    pub fn discard_after_config(&mut self, n: u8) -> &mut Self {
+        self.discard_after_config = n;
+        self
+    }
+
+    /// Set how long, in microseconds, the driver waits for the sensor
+    /// before returning `Error::Timeout`
+    ///
+    /// This applies to every method that waits for the sensor, e.g.
+    /// `integrate_for`. The time is counted in delay calls, so the time spent
+    /// on the bus is not included. Defaults to 100 ms.
+    pub fn set_default_timeout(&mut self, timeout_us: u32) -> &mut Self {
+        self.timeout_us = timeout_us;
+        self
+    }
+
+    /// Get the current timeout, in microseconds
+    pub fn default_timeout(&self) -> u32 {
+        self.timeout_us
+    }
+
+    /// Read the physical axes `first..=last` (0 is X) in a single burst
+    ///
+    /// The other axes are returned as zero.
+    fn read_axes_raw(&mut self, first: usize, last: usize) -> Result<I16x3, E> {
+        const START: [Register; 3] = [Register::OUT_X_L, Register::OUT_Y_L, Register::OUT_Z_L];
+
+        let mut bytes = [0u8; 7];
+        let len = 1 + 2 * (last - first + 1);
+        self.read_many(START[first], &mut bytes[..len])?;
+
+        let mut raw = [0; 3];
+        for (i, pair) in bytes[1..len].chunks(2).enumerate() {
+            raw[first + i] = i16::from_le_bytes([pair[0], pair[1]]);
+        }
+        Ok(I16x3 {
+            x: raw[0],
+            y: raw[1],
+            z: raw[2],
+        })
+    }
+
+    /// Subtract the bias of `source.axis` from a raw measurement of that
+    /// axis and apply its sign
+    fn adjust_axis(&self, source: AxisSource, raw: i16) -> i16 {
+        let value = raw.saturating_sub(self.bias.component(source.axis));
+        if source.negate {
+            value.saturating_neg()
+        } else {
+            value
+        }
+    }
+
+    /// Subtract the bias from a raw measurement and apply the axis mapping
+    fn adjust(&self, raw: I16x3) -> I16x3 {
+        self.mapping.apply(raw.saturating_sub(self.bias))
+    }
+
+    /// Temperature sensor measurement
+    pub fn temp(&mut self) -> Result<i8, E> {
+        Ok(self.read_register(Register::OUT_TEMP)? as i8)
+    }
+
+    /// Reads the WHO_AM_I register; should return `0xD4` (`0xD7` on the
+    /// L3GD20H)
+    pub fn who_am_i(&mut self) -> Result<u8, E> {
+        self.read_register(Register::WHO_AM_I)
+    }
+
+    /// Read `STATUS_REG` of sensor
+    pub fn status(&mut self) -> Result<Status, E> {
+        let sts = self.read_register(Register::STATUS_REG)?;
+        Ok(self.record_status(Status::from_bits_truncate(sts)))
+    }
+
+    /// Read `FIFO_SRC_REG` of sensor
+    pub fn fifo_status(&mut self) -> Result<FifoSrc, E> {
+        Ok(FifoSrc::from_bits(self.read_register(Register::FIFO_SRC_REG)?))
+    }
+
+    /// Counters of the bus and driver activity since creation (or the last
+    /// `reset_stats`)
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Reset all the counters returned by `stats` to zero
+    pub fn reset_stats(&mut self) -> &mut Self {
+        self.stats = Stats::default();
+        self
+    }
+
+    /// Availability of new data, from `STATUS_REG`
+    pub fn data_ready(&mut self) -> Result<Readiness, E> {
+        Ok(self.status()?.readiness())
+    }
+
+    /// Get the current Output Data Rate
+    ///
+    /// On the L3GD20H `LOW_ODR` is read as well.
+    pub fn odr(&mut self) -> Result<Odr, Error<E>> {
+        // Read control register
+        let reg1 = self.read_register(Register::CTRL_REG1)?;
+        if self.variant == Variant::L3gd20h {
+            self.low_odr = LowOdr::from_bits(self.read_register(Register::LOW_ODR)?).low_odr();
+        }
+        Ok(self.odr_of(reg1))
+    }
+
+    /// Set the Output Data Rate
+    ///
+    /// The current bandwidth is checked against the new data rate according
+    /// to the `BandwidthPolicy`. The low data rates set `Low_ODR` in
+    /// `LOW_ODR`, and fail with `Error::InvalidConfig` on the L3GD20.
+    pub fn set_odr(&mut self, odr: Odr) -> Result<&mut Self, Error<E>> {
+        self.check_odr(odr)?;
+        self.change_ctrl1(odr.low_odr(), |r| r.with_odr(odr))
+    }
+
+    /// Get current Bandwidth
+    pub fn bandwidth(&mut self) -> Result<Bandwidth, Error<E>> {
+        let reg1 = self.read_register(Register::CTRL_REG1)?;
+        decode(Register::CTRL_REG1, reg1, |r| CtrlReg1::from_bits(r).bandwidth())
+    }
+
+    /// Set low-pass cut-off frequency (i.e. bandwidth)
+    ///
+    /// See `Bandwidth` for further explanation. `bw` is checked against the
+    /// current data rate according to the `BandwidthPolicy`.
+    pub fn set_bandwidth(&mut self, bw: Bandwidth) -> Result<&mut Self, Error<E>> {
+        self.change_ctrl1(self.low_odr, |r| r.with_bandwidth(bw))
+    }
+
+    /// Set how bandwidth settings that duplicate another one at the current
+    /// data rate are handled, see `BandwidthPolicy`
+    ///
+    /// The policy applies to `set_odr`, `set_bandwidth`, `set_ctrl1`,
+    /// `apply_config` and `StagedConfig::commit`.
+    pub fn set_bandwidth_policy(&mut self, policy: BandwidthPolicy) -> &mut Self {
+        self.bandwidth_policy = policy;
+        self
+    }
+
+    /// Get the current bandwidth policy
+    pub fn bandwidth_policy(&self) -> BandwidthPolicy {
+        self.bandwidth_policy
+    }
+
+    /// Set data rate, bandwidth, power mode and enabled axes at once
+    ///
+    /// `CTRL_REG1` is composed from the arguments and written in a single
+    /// transaction, without reading it first. `bw` is checked against `odr`
+    /// according to the `BandwidthPolicy`. `LOW_ODR` is updated (L3GD20H
+    /// only) as in `set_odr`.
+    pub fn set_ctrl1(
+        &mut self,
+        odr: Odr,
+        bw: Bandwidth,
+        power: PowerMode,
+        axes: AxesEnable,
+    ) -> Result<&mut Self, Error<E>> {
+        self.check_odr(odr)?;
+        let reg1 = CtrlReg1::default()
+            .with_odr(odr)
+            .with_bandwidth(self.check_bandwidth(odr, bw)?)
+            .with_power(power)
+            .with_axes(axes);
+        self.verified(|l3gd20| {
+            l3gd20.write_low_odr(odr.low_odr())?;
+            l3gd20.write_register(Register::CTRL_REG1, reg1.bits())
+        })?;
+        Ok(self)
+    }
+
+    /// Get the current Full Scale Selection
+    ///
+    /// This is the sensitivity of the sensor, see `Scale` for more information
+    pub fn scale(&mut self) -> Result<Scale, Error<E>> {
+        let scl = self.read_register(Register::CTRL_REG4)?;
+        decode(Register::CTRL_REG4, scl, |r| CtrlReg4::from_bits(r).scale())
+    }
+
+    /// Set the Full Scale Selection
+    ///
+    /// This sets the sensitivity of the sensor, see `Scale` for more
+    /// information
+    ///
+    /// If the scale changes, the stored bias (and bias model) is converted
+    /// or cleared according to the `BiasPolicy`, see `set_bias_policy`, and
+    /// the thresholds set with `set_interrupt_threshold_mdps` are converted
+    /// according to the `ThresholdPolicy`: with `ThresholdPolicy::Strict`,
+    /// a threshold that doesn't fit fails with `Error::InvalidConfig` before
+    /// anything is written.
+    pub fn set_scale(&mut self, scale: Scale) -> Result<&mut Self, Error<E>> {
+        let previous = self.cached_scale();
+        if previous != Some(scale) {
+            self.check_thresholds(scale)?;
+        }
+        self.verified(|l3gd20| {
+            l3gd20.change_config(Register::CTRL_REG4, |r| {
+                CtrlReg4::from_bits(r).with_scale(scale).bits()
+            })?;
+            if previous != Some(scale) {
+                l3gd20.bias_scale_changed(previous, scale);
+                l3gd20.rescale_thresholds(scale)?;
+            }
+            Ok(())
+        })?;
+        Ok(self)
+    }
+
+    /// Convert or clear the bias after a change of scale from `from` (if
+    /// known) to `to`
+    fn bias_scale_changed(&mut self, from: Option<Scale>, to: Scale) {
+        match (self.bias_policy, from) {
+            (BiasPolicy::Rescale, Some(from)) => {
+                self.bias = from.rescale_vec(&self.bias, to);
+                #[cfg(feature = "float")]
+                {
+                    let factor = from.sensitivity_udps() as f32 / to.sensitivity_udps() as f32;
+                    self.bias_model = self.bias_model.map(|model| BiasModel {
+                        offset: model.offset.scale(factor),
+                        coefficient: model.coefficient.scale(factor),
+                    });
+                }
+            }
+            _ => {
+                #[cfg(feature = "float")]
+                let has_model = self.bias_model.take().is_some();
+                #[cfg(not(feature = "float"))]
+                let has_model = false;
+
+                if self.bias != I16x3::default() || has_model {
+                    self.bias = I16x3::default();
+                    self.bias_stale = true;
+                }
+            }
+        }
+    }
+
+    /// Get the cached Full Scale Selection without touching the bus
+    ///
+    /// The scale is decoded from the shadow copy of `CTRL_REG4` (see
+    /// `resync_shadow`). Returns `None` if the cached value doesn't decode.
+    pub fn cached_scale(&self) -> Option<Scale> {
+        CtrlReg4::from_bits(self.shadow[3]).scale().ok()
+    }
+
+    /// Re-read the cached configuration from the sensor
+    ///
+    /// Use this if something other than this driver may have modified the
+    /// configuration registers. This is the same as `resync_shadow`.
+    pub fn refresh_config_cache(&mut self) -> Result<&mut Self, Error<E>> {
+        self.resync_shadow()?;
+        Ok(self)
+    }
+
+    /// Re-read the shadow copy of `CTRL_REG1` to `CTRL_REG5` from the sensor
+    ///
+    /// The driver keeps a copy of the control registers so configuration
+    /// changes only need a write. The copy is updated by every access to
+    /// those registers made through the driver; call this if something else
+    /// may have modified them, e.g. after a sensor reset.
+    pub fn resync_shadow(&mut self) -> Result<&mut Self, E> {
+        let mut bytes = [0u8; 6];
+        self.read_many(Register::CTRL_REG1, &mut bytes)?;
+        self.shadow.copy_from_slice(&bytes[1..]);
+        Ok(self)
+    }
+
+    /// Scale decoded from the shadow copy of `CTRL_REG4`
+    fn current_scale(&mut self) -> Result<Scale, Error<E>> {
+        decode(Register::CTRL_REG4, self.shadow[3], |r| {
+            CtrlReg4::from_bits(r).scale()
+        })
+    }
+
+    /// Read `buf.len()` consecutive registers starting at `start`
+    ///
+    /// This is a single auto-increment burst read. Requests that would run
+    /// past `INT1_DURATION`, the last register, are rejected with
+    /// `Error::BlockOutOfRange` without touching the bus.
+    pub fn read_block(&mut self, start: Register, buf: &mut [u8]) -> Result<(), Error<E>> {
+        let last = Register::INT1_DURATION.addr() as usize;
+        if start.addr() as usize + buf.len() > last + 1 {
+            return Err(Error::BlockOutOfRange {
+                start,
+                len: buf.len(),
+            });
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        self.read_into(start, buf)?;
+        Ok(())
+    }
+
+    /// Read the raw gyroscope output registers, `OUT_X_L` to `OUT_Z_H`
+    ///
+    /// The bytes are written directly into `buf`, which can then be decoded
+    /// with `parse_gyro`.
+    pub fn read_gyro_raw(&mut self, buf: &mut [u8; 6]) -> Result<(), E> {
+        self.read_into(Register::OUT_X_L, buf)
+    }
+
+    /// Read the raw `OUT_TEMP`, `STATUS_REG` and gyroscope output registers
+    ///
+    /// The bytes are written directly into `buf`, which can then be decoded
+    /// with `parse_frame`.
+    pub fn read_frame_raw(&mut self, buf: &mut [u8; 8]) -> Result<(), E> {
+        self.read_into(Register::OUT_TEMP, buf)
+    }
+
+    /// Burst read into `buf`, which only holds the data bytes
+    fn read_into(&mut self, start: Register, buf: &mut [u8]) -> Result<(), E> {
+        let retry = self.is_idempotent_read(start, buf.len());
+        self.transaction(retry, |spi| {
+            spi.write(&[start.addr() | MULTI | READ])?;
+            spi.transfer(buf)?;
+            Ok(())
+        })?;
+        self.stats.reads = self.stats.reads.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Run one SPI transaction with NCS asserted
+    ///
+    /// If `retry` is set, a failed transaction is repeated according to the
+    /// retry policy before the error is returned.
+    fn transaction<R, F>(&mut self, retry: bool, mut f: F) -> Result<R, E>
+    where
+        F: FnMut(&mut SPI) -> Result<R, E>,
+    {
+        let mut retries = 0;
+        loop {
+            self.select();
+            let result = f(&mut self.spi);
+            self.deselect();
+
+            if result.is_err() {
+                self.stats.bus_errors = self.stats.bus_errors.wrapping_add(1);
+            }
+            match result {
+                Err(_) if retry && retries < self.retry.attempts => {
+                    retries += 1;
+                    self.retry_count = self.retry_count.saturating_add(1);
+                    self.stats.retries = self.stats.retries.wrapping_add(1);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Assert the chip select
+    fn select(&mut self) {
+        let _ = match self.cs_polarity {
+            CsPolarity::ActiveLow => self.cs.set_low(),
+            CsPolarity::ActiveHigh => self.cs.set_high(),
+        };
+    }
+
+    /// Deassert the chip select
+    fn deselect(&mut self) {
+        let _ = match self.cs_polarity {
+            CsPolarity::ActiveLow => self.cs.set_high(),
+            CsPolarity::ActiveHigh => self.cs.set_low(),
+        };
+    }
+
+    /// `false` if a read of `len` bytes from `start` pops samples from the
+    /// FIFO or includes `INT1_SRC`, whose read clears a latched interrupt
+    fn is_idempotent_read(&self, start: Register, len: usize) -> bool {
+        let includes = |reg: Register| {
+            start.addr() <= reg.addr() && reg.addr() as usize - (start.addr() as usize) < len
+        };
+        let fifo_enabled = CtrlReg5::from_bits(self.shadow[4]).fifo_en();
+        let reaches_output = start.addr() as usize + len > Register::OUT_X_L.addr() as usize;
+        let starts_before_end = start.addr() <= Register::OUT_Z_H.addr();
+        let pops_fifo = fifo_enabled && reaches_output && starts_before_end;
+        !pops_fifo && !includes(Register::INT1_SRC)
+    }
+
+    /// Poll `STATUS_REG` until new data is available or the timeout expires
+    ///
+    /// Samples still pending to be discarded after a configuration change
+    /// (see `discard_after_config`) are read and dropped first.
+    fn wait_data_ready<D: DelayUs<u32>>(&mut self, delay: &mut D) -> Result<(), Error<E>> {
+        self.check_canary()?;
+        loop {
+            self.wait_until(delay, WaitTarget::DataReady, |l3gd20| {
+                Ok(l3gd20.data_ready()? != Readiness::NotReady)
+            })?;
+
+            if self.pending_discard == 0 {
+                return Ok(());
+            }
+            self.gyro_raw()?;
+            self.pending_discard -= 1;
+        }
+    }
+
+    /// Compare `CTRL_REG1` against the cached value if reset detection is
+    /// due, see `set_reset_detection`
+    fn check_canary(&mut self) -> Result<(), Error<E>> {
+        if self.canary_interval == 0 {
+            return Ok(());
+        }
+        if self.canary_countdown > 1 {
+            self.canary_countdown -= 1;
+            return Ok(());
+        }
+        self.canary_countdown = self.canary_interval;
+
+        // read without updating the shadow, which is what gets reapplied
+        let mut bytes = [0u8; 2];
+        self.read_many(Register::CTRL_REG1, &mut bytes)?;
+        if bytes[1] == self.shadow[0] {
+            return Ok(());
+        }
+
+        self.resets_detected = self.resets_detected.saturating_add(1);
+        match self.reset_policy {
+            ResetPolicy::Error => Err(Error::ConfigLost),
+            ResetPolicy::Reapply => {
+                let shadow = self.shadow;
+                self.write_registers(Register::CTRL_REG1, &shadow)?;
+                self.pending_discard = self.discard_after_config;
+                Ok(())
+            }
+        }
+    }
+
+    /// Evaluate `ready` every `POLL_INTERVAL_US` until it returns `true`
+    ///
+    /// Returns `Error::Timeout` once the default timeout has been spent
+    /// waiting.
+    fn wait_until<D, F>(
+        &mut self,
+        delay: &mut D,
+        target: WaitTarget,
+        mut ready: F,
+    ) -> Result<(), Error<E>>
+    where
+        D: DelayUs<u32>,
+        F: FnMut(&mut Self) -> Result<bool, E>,
+    {
+        let mut waited_us = 0;
+        while !ready(self)? {
+            if waited_us >= self.timeout_us {
+                return Err(Error::Timeout { waited_for: target });
+            }
+            delay.delay_us(POLL_INTERVAL_US);
+            waited_us = waited_us.saturating_add(POLL_INTERVAL_US);
+        }
+        Ok(())
+    }
+
+    /// Read `STATUS_REG` and gyroscope in a single burst, without bias
+    /// compensation
+    fn read_status_and_gyro(&mut self) -> Result<(Status, I16x3), E> {
+        let mut bytes = [0u8; 8];
+        self.read_many(Register::STATUS_REG, &mut bytes)?;
+
+        let status = self.record_status(Status::from_bits_truncate(bytes[1]));
+        if status.any_overrun() {
+            self.missed_samples = self.missed_samples.saturating_add(1);
+        }
+
+        Ok((status, I16x3::from_le_bytes(&bytes[2..8])))
+    }
+
+    /// Count the overrun reported by a `STATUS_REG` value that was read
+    fn record_status(&mut self, status: Status) -> Status {
+        if status.any_overrun() {
+            self.stats.overruns = self.stats.overruns.wrapping_add(1);
+        }
+        status
+    }
+
+    /// Read temperature and gyroscope in a single burst, without bias
+    /// compensation
+    fn read_all_raw(&mut self) -> Result<(I16x3, i8), E> {
+        let mut bytes = [0u8; 9];
+        self.read_many(Register::OUT_TEMP, &mut bytes)?;
+
+        Ok((I16x3::from_le_bytes(&bytes[3..9]), bytes[1] as i8))
+    }
+
+    fn read_register(&mut self, reg: Register) -> Result<u8, E> {
+        let retry = self.is_idempotent_read(reg, 1);
+        let value = self.transaction(retry, |spi| {
+            let mut buffer = [reg.addr() | SINGLE | READ, 0];
+            spi.transfer(&mut buffer)?;
+            Ok(buffer[1])
+        })?;
+        self.stats.reads = self.stats.reads.wrapping_add(1);
+
+        if let Some(i) = shadow_index(reg) {
+            self.shadow[i] = value;
+        }
+
+        Ok(value)
+    }
+
+    /// Read multiple bytes starting from the `start_reg` register.
+    /// This function will attempt to fill the provided buffer.
+    fn read_many(&mut self,
+                 start_reg: Register,
+                 buffer: &mut [u8])
+                 -> Result<(), E> {
+        let retry = self.is_idempotent_read(start_reg, buffer.len() - 1);
+        self.transaction(retry, |spi| {
+            buffer[0] = start_reg.addr() | MULTI | READ ;
+            spi.transfer(buffer)?;
+            Ok(())
+        })?;
+        self.stats.reads = self.stats.reads.wrapping_add(1);
+        Ok(())
+    }
+
+    fn write_register(&mut self, reg: Register, byte: u8) -> Result<(), E> {
+        self.transaction(true, |spi| {
+            let buffer = [reg.addr() | SINGLE | WRITE, byte];
+            spi.write(&buffer)
+        })?;
+        self.stats.writes = self.stats.writes.wrapping_add(1);
+
+        if let Some(i) = shadow_index(reg) {
+            self.update_shadow(i, byte);
+        }
+
+        if self.verify_writes {
+            let read = self.read_register(reg)?;
+            self.check_written(reg, byte, read);
+        }
+
+        Ok(())
+    }
+
+    /// Write consecutive registers, starting at `start`, in a single
+    /// auto-increment burst
+    fn write_registers(&mut self, start: Register, bytes: &[u8]) -> Result<(), E> {
+        self.transaction(true, |spi| {
+            spi.write(&[start.addr() | MULTI | WRITE])?;
+            spi.write(bytes)
+        })?;
+        self.stats.writes = self.stats.writes.wrapping_add(1);
+
+        for (offset, &byte) in bytes.iter().enumerate() {
+            if let Some(i) = shadow_index_of(start.addr() as usize + offset) {
+                self.update_shadow(i, byte);
+            }
+        }
+
+        if self.verify_writes {
+            // The driver never writes more than one chunk at once
+            let mut read = [0; 8];
+            for (i, chunk) in bytes.chunks(read.len()).enumerate() {
+                let addr = start.addr() + (i * read.len()) as u8;
+                let read = &mut read[..chunk.len()];
+                if let Ok(first) = Register::try_from(addr) {
+                    self.read_into(first, read)?;
+                } else {
+                    continue;
+                }
+                for (offset, (&wrote, &read)) in chunk.iter().zip(read.iter()).enumerate() {
+                    if let Ok(reg) = Register::try_from(addr + offset as u8) {
+                        self.check_written(reg, wrote, read);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record a mismatch between the value written to `reg` and the one read
+    /// back, ignoring the self-clearing bits
+    fn check_written(&mut self, reg: Register, wrote: u8, read: u8) {
+        let mask = match reg {
+            // BOOT
+            Register::CTRL_REG5 => 0x7f,
+            // SW_RES
+            Register::LOW_ODR => !0x04,
+            _ => 0xff,
+        };
+        if (wrote ^ read) & mask != 0 {
+            self.verify_failures = self.verify_failures.saturating_add(1);
+            self.verify_failure = Some((reg, wrote, read));
+        }
+    }
+
+    /// Run the writes of `f` and fail with `Error::VerifyFailed` if one of
+    /// them didn't read back as written
+    pub(crate) fn verified<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<R, E>,
+    ) -> Result<R, Error<E>> {
+        self.verify_failure = None;
+        let result = f(self)?;
+        match self.verify_failure.take() {
+            Some((reg, wrote, read)) => Err(Error::VerifyFailed { reg, wrote, read }),
+            None => Ok(result),
+        }
+    }
+
+    /// Record a value written to a control register
+    ///
+    /// Changing a register that affects the output data (all but `CTRL_REG3`)
+    /// schedules the samples to discard.
+    fn update_shadow(&mut self, index: usize, byte: u8) {
+        if index != 2 && self.shadow[index] != byte {
+            self.pending_discard = self.discard_after_config;
+        }
+        self.shadow[index] = byte;
+    }
+
+    /// Change configuration in register
+    ///
+    /// Helper function to update a particular part of a register without
+    /// affecting other parts of the register that might contain desired
+    /// configuration. This allows the `L3gd20` struct to be used like
+    /// a builder interface when configuring specific parameters.
+    ///
+    /// `update` receives the current value of the register and returns the
+    /// new one; it should use the typed accessors of the `registers` module
+    /// to only touch the intended field. The current value of the control
+    /// registers is taken from the shadow copy, so only a write is needed.
+    fn change_config<F>(&mut self, reg: Register, update: F) -> Result<&mut Self, E>
+    where
+        F: FnOnce(u8) -> u8,
+    {
+        let current = match shadow_index(reg) {
+            Some(i) => self.shadow[i],
+            // Read current value of register
+            None => self.read_register(reg)?,
+        };
+        self.write_register(reg, update(current))?;
+        Ok(self)
+    }
+
+    /// `change_config` on `CTRL_REG1`, with the resulting data rate and
+    /// bandwidth checked according to the `BandwidthPolicy`
+    fn change_ctrl1<F>(&mut self, low_odr: bool, update: F) -> Result<&mut Self, Error<E>>
+    where
+        F: FnOnce(CtrlReg1) -> CtrlReg1,
+    {
+        let reg1 = self.check_ctrl1(update(CtrlReg1::from_bits(self.shadow[0])), low_odr)?;
+        self.verified(|l3gd20| {
+            l3gd20.write_low_odr(low_odr)?;
+            l3gd20.write_register(Register::CTRL_REG1, reg1.bits())
+        })?;
+        Ok(self)
+    }
+
+    /// Apply the `BandwidthPolicy` to the data rate (given `Low_ODR`) and
+    /// bandwidth of `reg1`
+    pub(crate) fn check_ctrl1(&self, reg1: CtrlReg1, low_odr: bool) -> Result<CtrlReg1, Error<E>> {
+        match reg1.bandwidth() {
+            Ok(bw) => {
+                let bw = self.check_bandwidth(reg1.odr_for(low_odr), bw)?;
+                Ok(reg1.with_bandwidth(bw))
+            }
+            Err(_) => Ok(reg1),
+        }
+    }
+
+    /// Fail with `Error::InvalidConfig` if the variant doesn't support `odr`
+    pub(crate) fn check_odr(&self, odr: Odr) -> Result<(), Error<E>> {
+        if odr.is_supported_by(self.variant) {
+            Ok(())
+        } else {
+            Err(Error::InvalidConfig("the low data rates need a L3GD20H"))
+        }
+    }
+
+    /// Data rate selected by the `CTRL_REG1` value `reg1` and the cached
+    /// `Low_ODR`
+    pub(crate) fn odr_of(&self, reg1: u8) -> Odr {
+        CtrlReg1::from_bits(reg1).odr_for(self.low_odr)
+    }
+
+    /// Write `Low_ODR` to `LOW_ODR` if it differs from the cached value;
+    /// does nothing on the L3GD20
+    pub(crate) fn write_low_odr(&mut self, low_odr: bool) -> Result<(), E> {
+        if self.variant == Variant::L3gd20h && low_odr != self.low_odr {
+            self.change_config(Register::LOW_ODR, |r| {
+                LowOdr::from_bits(r).with_low_odr(low_odr).bits()
+            })?;
+            self.low_odr = low_odr;
+        }
+        Ok(())
+    }
+
+    /// Apply the `BandwidthPolicy` to `bw` running at `odr`
+    fn check_bandwidth(&self, odr: Odr, bw: Bandwidth) -> Result<Bandwidth, Error<E>> {
+        match self.bandwidth_policy {
+            BandwidthPolicy::Keep => Ok(bw),
+            BandwidthPolicy::Normalize => Ok(bw.normalized(odr)),
+            BandwidthPolicy::Strict if bw.is_normalized(odr) => Ok(bw),
+            BandwidthPolicy::Strict => Err(Error::InvalidConfig(
+                "the bandwidth duplicates a lower setting at this data rate",
+            )),
+        }
+    }
+}
+
+impl<SPI, E> L3gd20<SPI, NoCs>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+{
+    /// Creates a new driver from a SPI peripheral that manages NSS in
+    /// hardware
+    ///
+    /// The driver relies on NSS being asserted for the whole duration of
+    /// each transaction: some of them are split into several `write` and
+    /// `transfer` calls (the address byte, then the data) and the sensor
+    /// aborts the transaction as soon as NSS is deasserted.
+    /// Peripherals that toggle NSS between bytes or between calls can't be
+    /// used this way.
+    pub fn new_hw_cs<S: SpiInterface<Spi = SPI>>(spi: S) -> Result<Self, E> {
+        L3gd20::connect(spi.into_spi(), NoCs, CsPolarity::ActiveLow)
+    }
+}
+
+/// `Error::Overrun` with the per-axis flags of `status`
+fn overrun_error<E>(status: Status) -> Error<E> {
+    Error::Overrun {
+        axes: [status.x_overrun(), status.y_overrun(), status.z_overrun()],
+    }
+}
+
+/// Position of `reg` in the shadow copy of the control registers
+fn shadow_index(reg: Register) -> Option<usize> {
+    shadow_index_of(reg.addr() as usize)
+}
+
+/// Position of the register at `addr` in the shadow copy of the control
+/// registers
+fn shadow_index_of(addr: usize) -> Option<usize> {
+    let first = Register::CTRL_REG1.addr() as usize;
+    let last = Register::CTRL_REG5.addr() as usize;
+    if (first..=last).contains(&addr) {
+        Some(addr - first)
+    } else {
+        None
+    }
+}
+
+/// Decode a register value, mapping failures to `Error::InvalidRegisterValue`
+fn decode<T, E>(
+    reg: Register,
+    value: u8,
+    decoder: fn(u8) -> Result<T, InvalidValue>,
+) -> Result<T, Error<E>> {
+    decoder(value).map_err(|_| Error::InvalidRegisterValue { reg, value })
+}
+
+/// Decode the bytes read by `L3gd20::read_gyro_raw`
+///
+/// No bias compensation or axis mapping is applied.
+pub fn parse_gyro(buf: &[u8; 6]) -> I16x3 {
+    I16x3::from_le_bytes(buf)
+}
+
+/// Decode the bytes read by `L3gd20::read_frame_raw`
+///
+/// No bias compensation or axis mapping is applied.
+pub fn parse_frame(buf: &[u8; 8]) -> (Measurements, Status) {
+    let measurements = Measurements {
+        gyro: I16x3::from_le_bytes(&buf[2..8]),
+        temp: buf[0] as i8,
+    };
+    (measurements, Status::from_bits_truncate(buf[1]))
+}
+
+/// Output Data Rate
+///
+/// The value of a variant is the `DR` field of `CTRL_REG1`, with the
+/// `Low_ODR` bit of `LOW_ODR` as bit 2. The low data rates (`Low_ODR` set)
+/// are only available on the L3GD20H.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub enum Odr {
+    /// 12.5 Hz data rate; L3GD20H only
+    Hz12_5 = 0x04,
+    /// 25 Hz data rate; L3GD20H only
+    Hz25 = 0x05,
+    /// 50 Hz data rate; L3GD20H only
+    Hz50 = 0x06,
+    /// 95 Hz data rate
+    Hz95 = 0x00,
+    /// 190 Hz data rate
+    Hz190 = 0x01,
+    /// 380 Hz data rate
+    Hz380 = 0x02,
+    /// 760 Hz data rate
+    Hz760 = 0x03,
+}
+
+impl Odr {
+    /// The data rate selected by the (already shifted) `DR` field of
+    /// `CTRL_REG1` and the `Low_ODR` bit of `LOW_ODR`
+    ///
+    /// Both `DR` values `0b10` and `0b11` select 50 Hz with `Low_ODR` set.
+    pub const fn from_dr(dr: u8, low_odr: bool) -> Odr {
+        match (dr & 0b11, low_odr) {
+            (0b00, false) => Odr::Hz95,
+            (0b01, false) => Odr::Hz190,
+            (0b10, false) => Odr::Hz380,
+            (_, false) => Odr::Hz760,
+            (0b00, true) => Odr::Hz12_5,
+            (0b01, true) => Odr::Hz25,
+            (_, true) => Odr::Hz50,
+        }
+    }
+
+    /// `true` for the low data rates, selected with `Low_ODR` in `LOW_ODR`
+    pub const fn low_odr(&self) -> bool {
+        *self as u8 & 0b100 != 0
+    }
+
+    /// `true` if `variant` supports this data rate
+    pub const fn is_supported_by(&self, variant: Variant) -> bool {
+        !self.low_odr() || matches!(variant, Variant::L3gd20h)
+    }
+
+    /// Nominal data rate in mHz
+    const fn nominal_mhz(&self) -> u32 {
+        match *self {
+            Odr::Hz12_5 => 12_500,
+            Odr::Hz25 => 25_000,
+            Odr::Hz50 => 50_000,
+            Odr::Hz95 => 95_000,
+            Odr::Hz190 => 190_000,
+            Odr::Hz380 => 380_000,
+            Odr::Hz760 => 760_000,
+        }
+    }
+
+    /// Nominal sample period in microseconds, rounded to the nearest
+    /// microsecond
+    pub const fn period_us(&self) -> u32 {
+        match *self {
+            Odr::Hz12_5 => 80_000,
+            Odr::Hz25 => 40_000,
+            Odr::Hz50 => 20_000,
+            Odr::Hz95 => 10_526,
+            Odr::Hz190 => 5_263,
+            Odr::Hz380 => 2_632,
+            Odr::Hz760 => 1_316,
+        }
+    }
+}
+
+impl fmt::Display for Odr {
+    /// Nominal data rate, e.g. `760 Hz` or `12.5 Hz`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mhz = self.nominal_mhz();
+        match mhz % 1000 {
+            0 => write!(f, "{} Hz", mhz / 1000),
+            frac => write!(f, "{}.{} Hz", mhz / 1000, frac / 100),
+        }
+    }
+}
+
+impl TryFrom<u8> for Odr {
+    type Error = InvalidValue;
+
+    /// Convert the (already shifted) `DR` field of `CTRL_REG1`, with the
+    /// `Low_ODR` bit as bit 2, see `Odr::from_dr`
+    fn try_from(value: u8) -> Result<Self, InvalidValue> {
+        if value > 0b111 {
+            return Err(InvalidValue { value });
+        }
+        Ok(Odr::from_dr(value, value & 0b100 != 0))
+    }
+}
+
+/// Full scale selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub enum Scale {
+    /// 250 Degrees Per Second
+    Dps250 = 0x00,
+    /// 500 Degrees Per Second
+    Dps500 = 0x01,
+    /// 2000 Degrees Per Second
+    Dps2000 = 0x03,
+}
+
+impl TryFrom<u8> for Scale {
+    type Error = InvalidValue;
+
+    /// Convert the (already shifted) `FS` field of `CTRL_REG4`
+    fn try_from(value: u8) -> Result<Self, InvalidValue> {
+        match value {
+            x if x == Scale::Dps250 as u8 => Ok(Scale::Dps250),
+            x if x == Scale::Dps500 as u8 => Ok(Scale::Dps500),
+            x if x == Scale::Dps2000 as u8 => Ok(Scale::Dps2000),
+            // Special case for Dps2000
+            0x02 => Ok(Scale::Dps2000),
+            _ => Err(InvalidValue { value }),
+        }
+    }
+}
+
+impl fmt::Display for Scale {
+    /// Full scale range, e.g. `±2000 dps`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\u{b1}{} dps", self.max_dps())
+    }
+}
+
+/// Bandwidth of sensor
+///
+/// The bandwidth of the sensor is equal to the cut-off for the low-pass
+/// filter. The cut-off depends on the `Odr` of the sensor, for specific
+/// information consult the data sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub enum Bandwidth {
+    /// Lowest possible cut-off for any `Odr` configuration
+    Low = 0x00,
+    /// Medium cut-off, can be the same as `High` for some `Odr` configurations
+    Medium = 0x01,
+    /// High cut-off
+    High = 0x02,
+    /// Maximum cut-off for any `Odr` configuration
+    Maximum = 0x03,
+}
+
+impl TryFrom<u8> for Bandwidth {
+    type Error = InvalidValue;
+
+    /// Convert the (already shifted) `BW` field of `CTRL_REG1`
+    fn try_from(value: u8) -> Result<Self, InvalidValue> {
+        match value {
+            x if x == Bandwidth::Low as u8 => Ok(Bandwidth::Low),
+            x if x == Bandwidth::Medium as u8 => Ok(Bandwidth::Medium),
+            x if x == Bandwidth::High as u8 => Ok(Bandwidth::High),
+            x if x == Bandwidth::Maximum as u8 => Ok(Bandwidth::Maximum),
+            _ => Err(InvalidValue { value }),
+        }
+    }
+}
+
+impl Bandwidth {
+    /// The lowest setting that selects the same cut-off as `self` at `odr`
+    ///
+    /// At 95 Hz `Medium`, `High` and `Maximum` all select the same cut-off
+    /// and are normalized to `Medium`; the low data rates of the L3GD20H
+    /// ignore the setting, which is normalized to `Low`. At the other data
+    /// rates every setting is distinct and returned as is.
+    pub const fn normalized(&self, odr: Odr) -> Bandwidth {
+        match (odr, *self) {
+            (Odr::Hz12_5, _) | (Odr::Hz25, _) | (Odr::Hz50, _) => Bandwidth::Low,
+            (Odr::Hz95, Bandwidth::High) | (Odr::Hz95, Bandwidth::Maximum) => Bandwidth::Medium,
+            (_, bw) => bw,
+        }
+    }
+
+    /// `true` if no lower setting selects the same cut-off at `odr`, see
+    /// `normalized`
+    pub const fn is_normalized(&self, odr: Odr) -> bool {
+        *self as u8 == self.normalized(odr) as u8
+    }
+
+    /// Lower case name of the setting
+    const fn name(&self) -> &'static str {
+        match *self {
+            Bandwidth::Low => "low",
+            Bandwidth::Medium => "medium",
+            Bandwidth::High => "high",
+            Bandwidth::Maximum => "maximum",
+        }
+    }
+}
+
+impl fmt::Display for Bandwidth {
+    /// Name of the setting; the cut-off itself depends on the `Odr`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A register field holds a value that doesn't correspond to any setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidValue {
+    /// The offending field value
+    pub value: u8,
+}
+
+/// Time between two polls of `STATUS_REG` while waiting for new data
+const POLL_INTERVAL_US: u32 = 100;
+
+/// Default time the driver waits for the sensor, see
+/// `L3gd20::set_default_timeout`
+const DEFAULT_TIMEOUT_US: u32 = 100_000;
+
+const READ: u8 = 1 << 7;
+const WRITE: u8 = 0 << 7;
+const MULTI: u8 = 1 << 6;
+const SINGLE: u8 = 0 << 6;
+
+impl Scale {
+    /// Sensitivity in micro-degrees per second per LSB
+    ///
+    /// This is the datasheet sensitivity (8.75, 17.5 and 70 mdps/digit)
+    /// multiplied by 1000 so it can be represented exactly as an integer.
+    pub const fn sensitivity_udps(&self) -> u32 {
+        match *self {
+            Scale::Dps250 => 8_750,
+            Scale::Dps500 => 17_500,
+            Scale::Dps2000 => 70_000,
+        }
+    }
+
+    /// Convert a measurement to milli-degrees per second
+    ///
+    /// Uses integer math only. The result is rounded to the nearest
+    /// milli-degree, with halves rounded away from zero.
+    pub const fn mdps(&self, val: i16) -> i32 {
+        let udps = val as i64 * self.sensitivity_udps() as i64;
+        let rounded = if udps < 0 { udps - 500 } else { udps + 500 };
+        (rounded / 1000) as i32
+    }
+
+    /// Convert a measurement taken at this scale to the counts that
+    /// represent the same rate at scale `to`
+    ///
+    /// The result is rounded to the nearest count, with halves rounded away
+    /// from zero, and saturates at the limits of `i16`. Going to a coarser
+    /// scale loses precision: at ±2000 dps one count is 8 counts at ±250 dps.
+    pub const fn rescale(&self, val: i16, to: Scale) -> i16 {
+        let num = val as i64 * self.sensitivity_udps() as i64;
+        let den = to.sensitivity_udps() as i64;
+        let half = if num < 0 { -den / 2 } else { den / 2 };
+        let rounded = (num + half) / den;
+        if rounded > i16::MAX as i64 {
+            i16::MAX
+        } else if rounded < i16::MIN as i64 {
+            i16::MIN
+        } else {
+            rounded as i16
+        }
+    }
+
+    /// Convert all three axes of a measurement to scale `to`, see `rescale`
+    pub const fn rescale_vec(&self, v: &I16x3, to: Scale) -> I16x3 {
+        I16x3 {
+            x: self.rescale(v.x, to),
+            y: self.rescale(v.y, to),
+            z: self.rescale(v.z, to),
+        }
+    }
+
+    /// Convert all three axes of a measurement to milli-degrees per second
+    pub const fn mdps_vec(&self, v: &I16x3) -> I32x3 {
+        I32x3 {
+            x: self.mdps(v.x),
+            y: self.mdps(v.y),
+            z: self.mdps(v.z),
+        }
+    }
+
+    /// Full scale range, in degrees per second
+    pub const fn max_dps(&self) -> u16 {
+        match *self {
+            Scale::Dps250 => 250,
+            Scale::Dps500 => 500,
+            Scale::Dps2000 => 2000,
+        }
+    }
+}
+
+/// XYZ triple
+///
+/// The layout is that of `[i16; 3]`: 6 bytes, 2-byte aligned, no padding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+#[repr(C)]
+pub struct I16x3 {
+    /// X component
+    pub x: i16,
+    /// Y component
+    pub y: i16,
+    /// Z component
+    pub z: i16,
+}
+
+impl I16x3 {
+    /// Assemble a triple from the `OUT_X_L..OUT_Z_H` register bytes
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        I16x3 {
+            x: (bytes[0] as u16 + ((bytes[1] as u16) << 8)) as i16,
+            y: (bytes[2] as u16 + ((bytes[3] as u16) << 8)) as i16,
+            z: (bytes[4] as u16 + ((bytes[5] as u16) << 8)) as i16,
+        }
+    }
+
+    /// Per-axis flags, `[x, y, z]`, set when a component is within
+    /// `SATURATION_MARGIN` of the `i16` limits
+    fn saturated_axes(self) -> [bool; 3] {
+        let railed = |v: i16| v >= i16::MAX - SATURATION_MARGIN || v <= i16::MIN + SATURATION_MARGIN;
+        [railed(self.x), railed(self.y), railed(self.z)]
+    }
+
+    /// The component along `axis`
+    pub const fn component(&self, axis: Axis) -> i16 {
+        match axis {
+            Axis::X => self.x,
+            Axis::Y => self.y,
+            Axis::Z => self.z,
+        }
+    }
+
+    /// Squared Euclidean norm, in counts squared
+    ///
+    /// Exact: the sum of three squared `i16` always fits in a `u64`.
+    pub const fn magnitude_squared(&self) -> u64 {
+        let x = self.x as i64;
+        let y = self.y as i64;
+        let z = self.z as i64;
+        (x * x + y * y + z * z) as u64
+    }
+
+    /// Component-wise saturating subtraction
+    fn saturating_sub(self, other: I16x3) -> Self {
+        I16x3 {
+            x: self.x.saturating_sub(other.x),
+            y: self.y.saturating_sub(other.y),
+            z: self.z.saturating_sub(other.z),
+        }
+    }
+}
+
+impl fmt::Display for I16x3 {
+    /// Signed components, e.g. `[+12, -4, +998]`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{:+}, {:+}, {:+}]", self.x, self.y, self.z)
+    }
+}
+
+/// Distance from the `i16` limits, in counts, within which a raw measurement
+/// is considered saturated
+pub const SATURATION_MARGIN: i16 = 16;
+
+/// How the acquisition loops handle overruns, see
+/// `L3gd20::set_overrun_policy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrunPolicy {
+    /// Stop and return `Error::Overrun`
+    Error,
+    /// Keep going and increment the counter returned by
+    /// `L3gd20::overrun_count`
+    Count,
+}
+
+/// How `L3gd20::set_bandwidth` and the other configuration methods handle a
+/// `Bandwidth` that duplicates a lower setting at the data rate, see
+/// `Bandwidth::normalized`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandwidthPolicy {
+    /// Write the setting as requested
+    Keep,
+    /// Write the lowest equivalent setting instead; the cut-off is the same,
+    /// but `bandwidth` reads back the normalized setting
+    Normalize,
+    /// Fail with `Error::InvalidConfig` without writing anything
+    Strict,
+}
+
+/// What `L3gd20::set_scale` does with the stored bias, which is expressed in
+/// counts of the previous scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BiasPolicy {
+    /// Convert the bias to the new scale, preserving the physical offset; see
+    /// `Scale::rescale` for the rounding
+    Rescale,
+    /// Clear the bias and flag it, see `L3gd20::bias_stale`
+    Clear,
+}
+
+/// What the paced reads do when they detect a sensor reset, see
+/// `L3gd20::set_reset_detection`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetPolicy {
+    /// Fail with `Error::ConfigLost`
+    Error,
+    /// Write the cached configuration back and carry on
+    Reapply,
+}
+
+/// Counters of the bus and driver activity, see `L3gd20::stats`
+///
+/// The counters wrap around on overflow.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Successful read transactions
+    pub reads: u32,
+    /// Successful write transactions
+    pub writes: u32,
+    /// Failed transaction attempts, including the ones that were retried
+    pub bus_errors: u32,
+    /// Transactions repeated according to the `RetryPolicy`
+    pub retries: u32,
+    /// `STATUS_REG` values read with an overrun flag set; only the reads
+    /// the driver already performs are counted, no extra transaction is
+    /// made
+    pub overruns: u32,
+}
+
+/// How often failed SPI transactions are retried, see `L3gd20::set_retry`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt
+    pub attempts: u8,
+}
+
+impl RetryPolicy {
+    /// Never retry; errors are returned immediately
+    pub const NONE: RetryPolicy = RetryPolicy { attempts: 0 };
+}
+
+/// Gyroscope measurement where only the axes with new data are present
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartialSample {
+    /// X-axis measurement, if new data was available
+    pub x: Option<i16>,
+    /// Y-axis measurement, if new data was available
+    pub y: Option<i16>,
+    /// Z-axis measurement, if new data was available
+    pub z: Option<i16>,
+}
+
+/// Gyroscope measurement with saturation information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckedSample {
+    /// Gyroscope measurement, bias compensated
+    pub gyro: I16x3,
+    /// Per-axis saturation flags, `[x, y, z]`
+    pub saturated: [bool; 3],
+}
+
+/// XYZ triple of integer scaled values
+///
+/// The layout is that of `[i32; 3]`: 12 bytes, 4-byte aligned, no padding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+#[repr(C)]
+pub struct I32x3 {
+    /// X component
+    pub x: i32,
+    /// Y component
+    pub y: i32,
+    /// Z component
+    pub z: i32,
+}
+
+impl fmt::Display for I32x3 {
+    /// Signed components, e.g. `[+12300, -400, +998100]`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{:+}, {:+}, {:+}]", self.x, self.y, self.z)
+    }
+}
+
+/// Several measurements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub struct Measurements {
+    /// Gyroscope measurements
+    pub gyro: I16x3,
+    /// Temperature sensor measurement
+    pub temp: i8,
+}
+
+impl Measurements {
+    /// The measurements with the `Scale` they were taken at, e.g.
+    /// `L3gd20::cached_scale`, which displays the rate in degrees per second
+    pub const fn at_scale(&self, scale: Scale) -> ScaledMeasurements {
+        ScaledMeasurements {
+            gyro: AngularRate::new(self.gyro, scale),
+            temp: self.temp,
+        }
+    }
+}
+
+impl fmt::Display for Measurements {
+    /// Raw counts, e.g. `gyro: [+12, -4, +998] counts, temp: 27`; see
+    /// `at_scale` for a rate in degrees per second
+    ///
+    /// The temperature reading has no absolute reference, so no unit is
+    /// printed for it.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "gyro: {} counts, temp: {}", self.gyro, self.temp)
+    }
+}
+
+/// Sensor status (`STATUS_REG`)
+///
+/// A set of flags stored in the layout of the register. Individual flags are
+/// read with the accessor methods (e.g. `overrun`) or tested with `contains`
+/// against the associated constants (e.g. `Status::ZYXOR`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub struct Status(u8);
+
+impl Status {
+    /// `ZYXOR`: overrun on at least one axis
+    pub const ZYXOR: Status = Status(1 << 7);
+    /// `ZOR`: overrun on the Z-axis
+    pub const ZOR: Status = Status(1 << 6);
+    /// `YOR`: overrun on the Y-axis
+    pub const YOR: Status = Status(1 << 5);
+    /// `XOR`: overrun on the X-axis
+    pub const XOR: Status = Status(1 << 4);
+    /// `ZYXDA`: new data on at least one axis
+    pub const ZYXDA: Status = Status(1 << 3);
+    /// `ZDA`: new data on the Z-axis
+    pub const ZDA: Status = Status(1 << 2);
+    /// `YDA`: new data on the Y-axis
+    pub const YDA: Status = Status(1 << 1);
+    /// `XDA`: new data on the X-axis
+    pub const XDA: Status = Status(1 << 0);
+
+    /// Raw value of `STATUS_REG`
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Creates a status from the raw value of `STATUS_REG`
+    ///
+    /// Every bit of the register is a flag, so no bit is actually dropped.
+    pub const fn from_bits_truncate(bits: u8) -> Self {
+        Status(bits)
+    }
+
+    /// `true` if every flag set in `other` is also set in `self`
+    pub const fn contains(&self, other: Status) -> bool {
+        self.0 & other.0 == other.0
+    }
 
-/// Sensor status
-#[derive(Debug, Clone, Copy)]
-pub struct Status {
     /// Overrun (data has overwritten previously unread data)
     /// has occurred on at least one axis
-    pub overrun: bool,
+    pub const fn overrun(&self) -> bool {
+        self.contains(Status::ZYXOR)
+    }
+
     /// Overrun occurred on Z-axis
-    pub z_overrun: bool,
+    pub const fn z_overrun(&self) -> bool {
+        self.contains(Status::ZOR)
+    }
+
     /// Overrun occurred on Y-axis
-    pub y_overrun: bool,
+    pub const fn y_overrun(&self) -> bool {
+        self.contains(Status::YOR)
+    }
+
     /// Overrun occurred on X-axis
-    pub x_overrun: bool,
+    pub const fn x_overrun(&self) -> bool {
+        self.contains(Status::XOR)
+    }
+
     /// New data is available for either X, Y, Z - axis
-    pub new_data: bool,
+    pub const fn new_data(&self) -> bool {
+        self.contains(Status::ZYXDA)
+    }
+
     /// New data is available on Z-axis
-    pub z_new: bool,
+    pub const fn z_new(&self) -> bool {
+        self.contains(Status::ZDA)
+    }
+
     /// New data is available on Y-axis
-    pub y_new: bool,
+    pub const fn y_new(&self) -> bool {
+        self.contains(Status::YDA)
+    }
+
     /// New data is available on X-axis
-    pub x_new: bool,
+    pub const fn x_new(&self) -> bool {
+        self.contains(Status::XDA)
+    }
+
+    /// `true` if new data is available on at least one axis, according to
+    /// either the summary flag or the per-axis flags
+    pub const fn any_new(&self) -> bool {
+        self.0 & (Status::ZYXDA.0 | Status::ZDA.0 | Status::YDA.0 | Status::XDA.0) != 0
+    }
+
+    /// `true` if new data is available on all three axes
+    pub const fn all_new(&self) -> bool {
+        self.x_new() && self.y_new() && self.z_new()
+    }
+
+    /// `true` if an overrun occurred on at least one axis, according to
+    /// either the summary flag or the per-axis flags
+    pub const fn any_overrun(&self) -> bool {
+        self.0 & (Status::ZYXOR.0 | Status::ZOR.0 | Status::YOR.0 | Status::XOR.0) != 0
+    }
+
+    /// Datasheet names of the set flags, most significant bit first
+    fn flag_names(&self) -> impl Iterator<Item = &'static str> {
+        const NAMES: [&str; 8] = ["ZYXOR", "ZOR", "YOR", "XOR", "ZYXDA", "ZDA", "YDA", "XDA"];
+
+        let bits = self.0;
+        NAMES
+            .iter()
+            .enumerate()
+            .filter(move |&(i, _)| bits & (0x80 >> i) != 0)
+            .map(|(_, &name)| name)
+    }
+
+    /// Axes with new data available
+    pub const fn ready_axes(&self) -> AxesEnable {
+        AxesEnable {
+            x: self.x_new(),
+            y: self.y_new(),
+            z: self.z_new(),
+        }
+    }
+
+    /// Axes whose data was overrun
+    pub const fn overrun_axes(&self) -> AxesEnable {
+        AxesEnable {
+            x: self.x_overrun(),
+            y: self.y_overrun(),
+            z: self.z_overrun(),
+        }
+    }
+
+    /// Classify the flags
+    ///
+    /// Overruns take precedence over everything else. If only the `ZYXDA`
+    /// summary flag is set the data is considered `Ready`.
+    pub const fn readiness(&self) -> Readiness {
+        if self.any_overrun() {
+            Readiness::ReadyWithOverrun(self.overrun_axes())
+        } else if self.all_new() {
+            Readiness::Ready
+        } else if self.x_new() || self.y_new() || self.z_new() {
+            Readiness::Partial(self.ready_axes())
+        } else if self.new_data() {
+            Readiness::Ready
+        } else {
+            Readiness::NotReady
+        }
+    }
 }
 
-impl Status {
-    fn from_u8(from: u8) -> Self {
-        Status {
-            overrun: (from & 1 << 7) != 0,
-            z_overrun: (from & 1 << 6) != 0,
-            y_overrun: (from & 1 << 5) != 0,
-            x_overrun: (from & 1 << 4) != 0,
-            new_data: (from & 1 << 3) != 0,
-            z_new: (from & 1 << 2) != 0,
-            y_new: (from & 1 << 1) != 0,
-            x_new: (from & 1 << 0) != 0,
+/// Result of `L3gd20::health_check`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    /// The sensor responds and is configured as expected
+    Healthy,
+    /// The sensor responds but its control registers differ from the cached
+    /// ones, e.g. after a reset; the configuration has to be applied again
+    ConfigLost,
+    /// `WHO_AM_I` doesn't read the expected value; the bus or the sensor
+    /// needs attention
+    NotResponding,
+}
+
+/// Availability of new data, see `Status::readiness`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+    /// No new data
+    NotReady,
+    /// New data on some of the axes only, the ones set
+    Partial(AxesEnable),
+    /// New data on all the axes
+    Ready,
+    /// New data, and at least one sample was overwritten before being read
+    /// on the axes set
+    ReadyWithOverrun(AxesEnable),
+}
+
+impl fmt::Display for Status {
+    /// The set flags, named as in the datasheet and separated by `|`, e.g.
+    /// `ZYXDA|XDA`; `-` if no flag is set
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        for name in self.flag_names() {
+            if !first {
+                f.write_str("|")?;
+            }
+            f.write_str(name)?;
+            first = false;
+        }
+        if first {
+            f.write_str("-")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{hex, replay, replay_h, NoDelay, PinLog};
+
+    // OUT_X_L..OUT_Z_H read as x = 100, y = -50, z = 32767
+    const GYRO: &str = "T e8 00 00 00 00 00 00 -> 00 64 00 ce ff ff 7f";
+
+    #[test]
+    fn bias_is_subtracted() {
+        let mut spi = replay(&[GYRO, GYRO, GYRO].join("\n"));
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(l3gd20.bias(), I16x3::default());
+
+            let bias = I16x3 { x: 10, y: -10, z: -1 };
+            l3gd20.set_bias(bias);
+            assert_eq!(l3gd20.bias(), bias);
+            // z saturates instead of wrapping
+            let expected = I16x3 { x: 90, y: -40, z: i16::MAX };
+            assert_eq!(l3gd20.gyro().unwrap(), expected);
+
+            let raw = I16x3 { x: 100, y: -50, z: i16::MAX };
+            assert_eq!(l3gd20.gyro_raw().unwrap(), raw);
+
+            l3gd20.clear_bias();
+            assert_eq!(l3gd20.gyro().unwrap(), raw);
+        }
+        spi.finish().unwrap();
+    }
+
+    // OUT_TEMP..OUT_Z_H read as temp = 25, x = 100, y = -50, z = -32768
+    const ALL: &str = "T e6 00 00 00 00 00 00 00 00 -> 00 19 0f 64 00 ce ff 00 80";
+
+    #[test]
+    fn bias_is_subtracted_from_all() {
+        let mut spi = replay(ALL);
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_bias(I16x3 { x: 100, y: 50, z: 1 });
+            let measurements = l3gd20.all().unwrap();
+            assert_eq!(measurements.gyro, I16x3 { x: 0, y: -100, z: i16::MIN });
+            assert_eq!(measurements.temp, 25);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn bias_is_subtracted_before_conversion() {
+        let mut spi = replay(ALL);
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_bias(I16x3 { x: 100, y: 50, z: 1 });
+            let dps = l3gd20.gyro_dps().unwrap();
+            assert_eq!(dps.x, 0.0);
+            assert!((dps.y + 0.875).abs() < 1e-6);
+            assert!((dps.z + 286.72).abs() < 1e-3);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn saturated_axes_are_flagged_and_counted() {
+        let zero = "T e8 00 00 00 00 00 00 -> 00 00 00 00 00 00 00";
+        let mut spi = replay(&[GYRO, GYRO, zero].join("\n"));
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let sample = l3gd20.gyro_checked().unwrap();
+            assert_eq!(sample.saturated, [false, false, true]);
+            assert_eq!(l3gd20.saturation_count(), 1);
+
+            l3gd20.saturation_count = u32::MAX;
+            l3gd20.gyro_checked().unwrap();
+            assert_eq!(l3gd20.saturation_count(), u32::MAX);
+
+            l3gd20.reset_saturation_count();
+            assert_eq!(l3gd20.gyro_checked().unwrap().saturated, [false; 3]);
+            assert_eq!(l3gd20.saturation_count(), 0);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn mapping_is_applied_after_the_bias() {
+        // OUT_Z_L..OUT_Z_H, then OUT_X_L..OUT_X_H
+        let z = "T ec 00 00 -> 00 ff 7f";
+        let x = "T e8 00 00 -> 00 64 00";
+        let mut spi = replay(&[GYRO, GYRO, z, x].join("\n"));
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let mapping = AxisMapping::new(
+                AxisSource::neg(Axis::Z),
+                AxisSource::pos(Axis::X),
+                AxisSource::pos(Axis::Y),
+            )
+            .unwrap();
+            l3gd20.set_axis_mapping(mapping).set_bias(I16x3 { x: 10, y: 20, z: -1 });
+
+            // bias in the physical frame: (90, -70, 32767)
+            assert_eq!(l3gd20.gyro().unwrap(), I16x3 { x: -32767, y: 90, z: -70 });
+            // raw reads aren't mapped
+            assert_eq!(l3gd20.gyro_raw().unwrap(), I16x3 { x: 100, y: -50, z: i16::MAX });
+            // single axis reads only touch the physical axis
+            assert_eq!(l3gd20.gyro_x().unwrap(), -32767);
+            assert_eq!(l3gd20.gyro_y().unwrap(), 90);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn fields_convert_from_u8() {
+        let odrs = [
+            Odr::Hz12_5,
+            Odr::Hz25,
+            Odr::Hz50,
+            Odr::Hz95,
+            Odr::Hz190,
+            Odr::Hz380,
+            Odr::Hz760,
+        ];
+        for odr in odrs {
+            assert_eq!(Odr::try_from(odr as u8), Ok(odr));
+        }
+        // both encodings of 50 Hz
+        assert_eq!(Odr::try_from(0b111), Ok(Odr::Hz50));
+        for bw in [Bandwidth::Low, Bandwidth::Medium, Bandwidth::High, Bandwidth::Maximum] {
+            assert_eq!(Bandwidth::try_from(bw as u8), Ok(bw));
+        }
+        for scale in [Scale::Dps250, Scale::Dps500, Scale::Dps2000] {
+            assert_eq!(Scale::try_from(scale as u8), Ok(scale));
+        }
+        // both encodings of the 2000 dps range
+        assert_eq!(Scale::try_from(0b10), Ok(Scale::Dps2000));
+    }
+
+    #[test]
+    fn out_of_range_fields_are_rejected() {
+        for value in 8..=u8::MAX {
+            assert_eq!(Odr::try_from(value), Err(InvalidValue { value }));
+        }
+        for value in 4..=u8::MAX {
+            assert_eq!(Bandwidth::try_from(value), Err(InvalidValue { value }));
+            assert_eq!(Scale::try_from(value), Err(InvalidValue { value }));
+        }
+    }
+
+    #[test]
+    fn undecodable_registers_are_reported() {
+        // FIFO_CTRL_REG with FM = 0b101, a mode of the L3GD20H only
+        let mut spi = replay("T ae 00 -> 00 a0");
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(
+                l3gd20.fifo_mode(),
+                Err(Error::InvalidRegisterValue {
+                    reg: Register::FIFO_CTRL_REG,
+                    value: 0xa0
+                })
+            );
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn control_registers_are_written_from_the_shadow() {
+        let mut spi = replay(
+            "
+            W 20 4f     # CTRL_REG1: 190 Hz
+            W 23 10     # CTRL_REG4: 500 dps
+            W 20 5f     # CTRL_REG1: 190 Hz, medium bandwidth
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_odr(Odr::Hz190).unwrap();
+            l3gd20.set_scale(Scale::Dps500).unwrap();
+            assert_eq!(l3gd20.cached_scale(), Some(Scale::Dps500));
+            l3gd20.set_bandwidth(Bandwidth::Medium).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn low_data_rates_set_low_odr() {
+        let mut spi = replay_h(
+            "
+            T b9 00 -> 00 00
+            W 39 01     # LOW_ODR: low data rates
+            W 20 4f     # CTRL_REG1: 25 Hz
+            T a0 00 -> 00 4f
+            T b9 00 -> 00 01
+            W 20 0f     # CTRL_REG1: 12.5 Hz, LOW_ODR unchanged
+            T b9 00 -> 00 01
+            W 39 00     # LOW_ODR: high data rates
+            W 20 cf     # CTRL_REG1: 760 Hz
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_l3gd20h(&mut spi, NoCs).unwrap();
+            l3gd20.set_odr(Odr::Hz25).unwrap();
+            assert_eq!(l3gd20.odr(), Ok(Odr::Hz25));
+            l3gd20.set_odr(Odr::Hz12_5).unwrap();
+            l3gd20.set_odr(Odr::Hz760).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn constructors_deselect_before_the_first_transaction() {
+        let mut spi = replay("");
+        let levels = PinLog::default();
+        let cs = PinLog(levels.0.clone());
+        L3gd20::new_l3gd20(&mut spi, cs).unwrap();
+        spi.finish().unwrap();
+        assert_eq!(levels.0.borrow().first(), Some(&true));
+
+        let mut spi = replay_h("");
+        let levels = PinLog::default();
+        let cs = PinLog(levels.0.clone());
+        L3gd20::new_l3gd20h(&mut spi, cs).unwrap();
+        spi.finish().unwrap();
+        assert_eq!(levels.0.borrow().first(), Some(&true));
+    }
+
+    #[test]
+    fn read_exact_ignores_only_a_pending_overrun() {
+        let mut spi = replay(
+            "
+            T a7 00 -> 00 08
+            T e7 00 00 00 00 00 00 00 -> 00 f8 01 00 02 00 03 00    # pending overrun
+            T a7 00 -> 00 08
+            T e7 00 00 00 00 00 00 00 -> 00 08 04 00 05 00 06 00
+            T a7 00 -> 00 08
+            T e7 00 00 00 00 00 00 00 -> 00 08 07 00 08 00 09 00
+            T a7 00 -> 00 08
+            T e7 00 00 00 00 00 00 00 -> 00 f8 00 00 00 00 00 00    # lost sample
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let mut out = [I16x3::default(); 2];
+            l3gd20.read_exact(&mut out, &mut NoDelay).unwrap();
+            assert_eq!(out[1], I16x3 { x: 4, y: 5, z: 6 });
+
+            let result = l3gd20.read_exact(&mut out, &mut NoDelay);
+            assert_eq!(result, Err(Error::Overrun { axes: [true; 3] }));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn low_data_rates_need_a_l3gd20h() {
+        let mut spi = replay("");
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let error = Error::InvalidConfig("the low data rates need a L3GD20H");
+            assert_eq!(l3gd20.set_odr(Odr::Hz50).map(|_| ()), Err(error));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn shadow_follows_reads_and_resyncs() {
+        let mut spi = replay(
+            "
+            T a3 00 -> 00 30                            # CTRL_REG4: 2000 dps
+            W 23 00                                     # CTRL_REG4: 250 dps
+            T e0 00 00 00 00 00 -> 00 cf 00 00 10 00    # modified behind our back
+            W 20 df                                     # CTRL_REG1: medium bandwidth
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(l3gd20.cached_scale(), Some(Scale::Dps250));
+            assert_eq!(l3gd20.scale().unwrap(), Scale::Dps2000);
+            assert_eq!(l3gd20.cached_scale(), Some(Scale::Dps2000));
+            l3gd20.set_scale(Scale::Dps250).unwrap();
+
+            l3gd20.resync_shadow().unwrap();
+            assert_eq!(l3gd20.cached_scale(), Some(Scale::Dps500));
+            l3gd20.set_bandwidth(Bandwidth::Medium).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn config_is_written_in_one_burst() {
+        let config = Config::default()
+            .odr(Odr::Hz760)
+            .bandwidth(Bandwidth::High)
+            .scale(Scale::Dps2000)
+            .bdu(true)
+            .data_path(DataPath::Lpf2);
+        let regs = config.registers();
+        assert_eq!(regs, [0xef, 0x00, 0x00, 0xb0, 0x02]);
+
+        let mut spi = replay(&std::format!(
+            "
+            W 60        # CTRL_REG1, auto-increment
+            W {}
+            W 20 ff     # CTRL_REG1: maximum bandwidth, from the shadow
+            ",
+            hex(&regs)
+        ));
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.apply_config(&config).unwrap();
+            assert_eq!(l3gd20.cached_scale(), Some(Scale::Dps2000));
+            l3gd20.set_bandwidth(Bandwidth::Maximum).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn presets_are_valid_for_each_variant() {
+        let presets = [Preset::FlightController, Preset::LowPower, Preset::VibrationAnalysis];
+        for &variant in [Variant::L3gd20, Variant::L3gd20h].iter() {
+            for &preset in presets.iter() {
+                let config = Config::preset_for(preset, variant);
+                assert!(config.odr.is_supported_by(variant), "{:?}", preset);
+                assert!(config.bandwidth.is_normalized(config.odr), "{:?}", preset);
+            }
+        }
+        assert_eq!(Config::preset(Preset::LowPower).odr, Odr::Hz95);
+
+        let config = Config::preset_for(Preset::LowPower, Variant::L3gd20h);
+        let mut spi = replay_h(&std::format!(
+            "
+            T b9 00 -> 00 00
+            W 39 01     # LOW_ODR: low data rates
+            W 60        # CTRL_REG1, auto-increment
+            W {}
+            ",
+            hex(&config.registers())
+        ));
+        {
+            let mut l3gd20 = L3gd20::new_l3gd20h(&mut spi, NoCs).unwrap();
+            l3gd20.set_bandwidth_policy(BandwidthPolicy::Strict);
+            l3gd20.apply_config(&config).unwrap();
+            assert_eq!(l3gd20.odr_of(config.registers()[0]), Odr::Hz12_5);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn new_with_config_initialises_the_detected_variant() {
+        let config = Config::default().odr(Odr::Hz25).scale(Scale::Dps500);
+        let mut spi = replay_h(&std::format!(
+            "
+            T b9 00 -> 00 00
+            W 39 01     # LOW_ODR: low data rates
+            W 60        # CTRL_REG1, auto-increment
+            W {}
+            ",
+            hex(&config.registers())
+        ));
+        {
+            let l3gd20 = L3gd20::new_with_config(&mut spi, NoCs, config).unwrap();
+            assert_eq!(l3gd20.variant(), Variant::L3gd20h);
+            assert_eq!(l3gd20.cached_scale(), Some(Scale::Dps500));
+        }
+        spi.finish().unwrap();
+
+        let mut spi = replay("");
+        let error = Error::InvalidConfig("the low data rates need a L3GD20H");
+        let result = L3gd20::new_with_config(&mut spi, NoCs, config).map(|_| ());
+        assert_eq!(result, Err(error));
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn duplicate_bandwidths_are_normalized_at_95_hz() {
+        let odrs = [Odr::Hz95, Odr::Hz190, Odr::Hz380, Odr::Hz760];
+        let bws = [Bandwidth::Low, Bandwidth::Medium, Bandwidth::High, Bandwidth::Maximum];
+        for &odr in odrs.iter() {
+            for &bw in bws.iter() {
+                let duplicate = odr == Odr::Hz95 && bw as u8 > Bandwidth::Medium as u8;
+                let normalized = if duplicate { Bandwidth::Medium } else { bw };
+                assert_eq!(bw.normalized(odr), normalized);
+                assert_eq!(bw.is_normalized(odr), !duplicate);
+            }
+        }
+    }
+
+    #[test]
+    fn data_rates_are_displayed_in_hz() {
+        assert_eq!(std::format!("{}", Odr::Hz12_5), "12.5 Hz");
+        assert_eq!(std::format!("{}", Odr::Hz50), "50 Hz");
+        assert_eq!(std::format!("{}", Odr::Hz760), "760 Hz");
+    }
+
+    #[test]
+    fn bandwidth_is_ignored_at_the_low_data_rates() {
+        let bws = [Bandwidth::Low, Bandwidth::Medium, Bandwidth::High, Bandwidth::Maximum];
+        for &odr in [Odr::Hz12_5, Odr::Hz25, Odr::Hz50].iter() {
+            for &bw in bws.iter() {
+                assert_eq!(bw.normalized(odr), Bandwidth::Low);
+                assert_eq!(bw.is_normalized(odr), bw == Bandwidth::Low);
+            }
+        }
+    }
+
+    #[test]
+    fn bandwidth_policy_applies_to_the_data_rate() {
+        let mut spi = replay(
+            "
+            W 20 ff     # 760 Hz, maximum bandwidth
+            W 20 ff     # same, kept as is
+            W 20 ff     # maximum bandwidth, distinct at 760 Hz
+            W 20 1f     # 95 Hz, normalized to medium bandwidth
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(l3gd20.bandwidth_policy(), BandwidthPolicy::Keep);
+            let all = AxesEnable::ALL;
+            l3gd20.set_ctrl1(Odr::Hz760, Bandwidth::Maximum, PowerMode::Normal, all).unwrap();
+            l3gd20.set_ctrl1(Odr::Hz760, Bandwidth::Maximum, PowerMode::Normal, all).unwrap();
+
+            l3gd20.set_bandwidth_policy(BandwidthPolicy::Strict);
+            let error =
+                Error::InvalidConfig("the bandwidth duplicates a lower setting at this data rate");
+            assert_eq!(l3gd20.set_odr(Odr::Hz95).map(|_| ()), Err(error));
+            let config = Config::default().odr(Odr::Hz95).bandwidth(Bandwidth::High);
+            assert_eq!(l3gd20.apply_config(&config).map(|_| ()), Err(error));
+            l3gd20.set_bandwidth(Bandwidth::Maximum).unwrap();
+
+            l3gd20.set_bandwidth_policy(BandwidthPolicy::Normalize);
+            l3gd20.set_odr(Odr::Hz95).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn staged_bandwidth_is_checked_before_writing() {
+        let mut spi = replay("W 23 10\nW 20 1f");
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_bandwidth_policy(BandwidthPolicy::Strict);
+            let mut staged = l3gd20.begin_config();
+            staged.scale(Scale::Dps500).odr(Odr::Hz95).bandwidth(Bandwidth::High);
+            assert!(matches!(staged.commit(), Err(Error::InvalidConfig(_))));
+
+            l3gd20.set_bandwidth_policy(BandwidthPolicy::Normalize);
+            let mut staged = l3gd20.begin_config();
+            staged.scale(Scale::Dps500).bandwidth(Bandwidth::Maximum);
+            staged.commit().unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn mismatched_readback_fails_verification() {
+        let mut spi = replay(
+            "
+            W 23 10             # CTRL_REG4: 500 dps
+            T a3 00 -> 00 00    # stuck at the power-on value
+            W 23 10
+            T a3 00 -> 00 10
+            W 20 07             # CTRL_REG1: power down
+            T a0 00 -> 00 0f
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_write_verification(true);
+            let error = Error::VerifyFailed {
+                reg: Register::CTRL_REG4,
+                wrote: 0x10,
+                read: 0x00,
+            };
+            assert_eq!(l3gd20.set_scale(Scale::Dps500).map(|_| ()), Err(error));
+            assert_eq!(l3gd20.verify_failures(), 1);
+            l3gd20.set_scale(Scale::Dps500).unwrap();
+            assert_eq!(l3gd20.verify_failures(), 1);
+
+            let error = Error::VerifyFailed {
+                reg: Register::CTRL_REG1,
+                wrote: 0x07,
+                read: 0x0f,
+            };
+            let powered_down = power::L3gd20::powered_down(l3gd20);
+            assert_eq!(powered_down.map(|_| ()).err(), Some(error));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn verify_config_reports_the_field_that_differs() {
+        let expected = Config::default().data_path(DataPath::Hpf);
+        let registers = expected.registers();
+        let matching = |actual| ConfigMismatch {
+            odr: false,
+            bandwidth: false,
+            scale: false,
+            bdu: false,
+            axes: false,
+            power: false,
+            data_path: false,
+            other: false,
+            actual,
+        };
+        type Flag = fn(&mut ConfigMismatch);
+        // register, bits flipped in it, and the resulting mismatch
+        let flips: [(usize, u8, Flag); 6] = [
+            (0, 0x40, |m| m.odr = true),       // DR: 190 Hz
+            (0, 0x10, |m| m.bandwidth = true), // BW: Medium
+            (3, 0x10, |m| m.scale = true),     // FS: 500 dps
+            (3, 0x80, |m| m.bdu = true),       // BDU
+            (4, 0x10, |m| m.data_path = true), // Hpen: LPF1 only
+            (4, 0x40, |m| m.other = true),     // FIFO_EN
+        ];
+        for &(register, bits, flag) in flips.iter() {
+            let mut actual = registers;
+            actual[register] ^= bits;
+            let mut spi = replay(&std::format!(
+                "
+                T e0 00 00 00 00 00 -> 00 {}
+                T e0 00 00 00 00 00 -> 00 {}
+                ",
+                hex(&actual),
+                hex(&actual),
+            ));
+            let mut mismatch = matching(actual);
+            flag(&mut mismatch);
+            {
+                let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+                assert_eq!(l3gd20.config_diff(&expected), Ok(Some(mismatch)));
+                let error = Error::ConfigMismatch(mismatch);
+                assert_eq!(l3gd20.verify_config(&expected), Err(error));
+            }
+            spi.finish().unwrap();
+        }
+
+        let mut spi = replay(&std::format!(
+            "T e0 00 00 00 00 00 -> 00 {}",
+            hex(&registers)
+        ));
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(l3gd20.verify_config(&expected), Ok(()));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn read_block_ends_at_int1_duration() {
+        let mut spi = replay(
+            "
+            W cf                    # WHO_AM_I, auto-increment
+            T 00 -> d4
+            W f6                    # INT1_TSH_ZH..INT1_DURATION
+            T 00 00 00 -> 12 34 05
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let mut buf = [0; 1];
+            l3gd20.read_block(Register::WHO_AM_I, &mut buf).unwrap();
+            assert_eq!(buf, [0xd4]);
+
+            let mut buf = [0; 3];
+            l3gd20.read_block(Register::INT1_TSH_ZH, &mut buf).unwrap();
+            assert_eq!(buf, [0x12, 0x34, 0x05]);
+
+            // nothing is sent for these
+            let mut buf = [0; 4];
+            let error = Error::BlockOutOfRange {
+                start: Register::INT1_TSH_ZH,
+                len: 4,
+            };
+            assert_eq!(l3gd20.read_block(Register::INT1_TSH_ZH, &mut buf), Err(error));
+            l3gd20.read_block(Register::INT1_DURATION, &mut []).unwrap();
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn check_comms_passes_and_restores_reference() {
+        let mut spi = replay(
+            "
+            T a5 00 -> 00 07    # REFERENCE
+            W 25 a5
+            T a5 00 -> 00 a5
+            W 25 5a
+            T a5 00 -> 00 5a
+            W 25 07             # restored
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(l3gd20.check_comms(), Ok(()));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn check_comms_catches_a_bit_stuck_at_0() {
+        // bit 7 of MISO stuck low
+        let mut spi = replay(
+            "
+            T a5 00 -> 00 07    # REFERENCE
+            W 25 a5
+            T a5 00 -> 00 25
+            W 25 07             # restored
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let error = Error::CommFault {
+                wrote: 0xa5,
+                read: 0x25,
+            };
+            assert_eq!(l3gd20.check_comms(), Err(error));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn check_comms_catches_a_bit_stuck_at_1() {
+        // bit 0 of MISO stuck high, which the first pattern doesn't show
+        let mut spi = replay(
+            "
+            T a5 00 -> 00 07    # REFERENCE
+            W 25 a5
+            T a5 00 -> 00 a5
+            W 25 5a
+            T a5 00 -> 00 5b
+            W 25 07             # restored
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let error = Error::CommFault {
+                wrote: 0x5a,
+                read: 0x5b,
+            };
+            assert_eq!(l3gd20.check_comms(), Err(error));
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn check_comms_verifies_the_restore() {
+        let mut spi = replay(
+            "
+            T a5 00 -> 00 07    # REFERENCE
+            W 25 a5
+            T a5 00 -> 00 a5    # verification
+            T a5 00 -> 00 a5    # check
+            W 25 5a
+            T a5 00 -> 00 5a
+            T a5 00 -> 00 5a
+            W 25 07
+            T a5 00 -> 00 5a
+            ",
+        );
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_write_verification(true);
+            l3gd20.verify_failures = u32::MAX;
+            let error = Error::VerifyFailed {
+                reg: Register::REFERENCE,
+                wrote: 0x07,
+                read: 0x5a,
+            };
+            assert_eq!(l3gd20.check_comms(), Err(error));
+            assert_eq!(l3gd20.verify_failures(), u32::MAX);
         }
+        spi.finish().unwrap();
     }
 }
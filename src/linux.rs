@@ -0,0 +1,314 @@
+//! Linux host support: spidev and sysfs GPIO (`linux` feature)
+//!
+//! For prototyping on e.g. a Raspberry Pi. `open` creates a driver from a
+//! spidev device and a GPIO line used as chip select:
+//!
+//! ``` ignore
+//! let mut l3gd20 = l3gd20::linux::open("/dev/spidev0.0", 25, 1_000_000)?;
+//! ```
+//!
+//! The chip select is driven by this crate, so the sensor's `CS` has to be
+//! wired to the GPIO line, not to the chip select of the SPI controller.
+//! The GPIO line is driven through the sysfs ABI, which recent kernels only
+//! provide with `CONFIG_GPIO_SYSFS`. The bus and pin types of
+//! `linux-embedded-hal` implement the same `embedded-hal` traits, so a driver
+//! can also be created from them with `L3gd20::new`:
+//!
+//! ``` ignore
+//! let spi = linux_embedded_hal::Spidev::open("/dev/spidev0.0")?;
+//! let cs = linux_embedded_hal::CdevPin::new(line.request(LineRequestFlags::OUTPUT, 1, "cs")?)?;
+//! let mut l3gd20 = L3gd20::new(spi, cs)?;
+//! ```
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::raw::{c_int, c_ulong};
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::string::{String, ToString};
+use std::thread;
+use std::time::Duration;
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::spi::{Phase, Polarity};
+
+use crate::{CsPolarity, L3gd20, MODE};
+
+// `_IOC_WRITE << _IOC_DIRSHIFT`: these architectures have 3 direction bits
+// and 13 size bits, the others follow `<asm-generic/ioctl.h>`
+#[cfg(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc",
+    target_arch = "sparc64"
+))]
+const IOC_WRITE: c_ulong = 4 << 29;
+#[cfg(not(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc",
+    target_arch = "sparc64"
+)))]
+const IOC_WRITE: c_ulong = 1 << 30;
+
+// _IOW('k', nr, size)
+const fn spi_iow(nr: c_ulong, size: c_ulong) -> c_ulong {
+    IOC_WRITE | (size << 16) | ((b'k' as c_ulong) << 8) | nr
+}
+
+const SPI_IOC_MESSAGE_1: c_ulong = spi_iow(0, 32);
+const SPI_IOC_WR_MODE: c_ulong = spi_iow(1, 1);
+const SPI_IOC_WR_BITS_PER_WORD: c_ulong = spi_iow(3, 1);
+const SPI_IOC_WR_MAX_SPEED_HZ: c_ulong = spi_iow(4, 4);
+
+const SPI_CPHA: u8 = 0x01;
+const SPI_CPOL: u8 = 0x02;
+
+extern "C" {
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+/// `struct spi_ioc_transfer` of `<linux/spi/spidev.h>`
+#[repr(C)]
+#[derive(Default)]
+struct SpiIocTransfer {
+    tx_buf: u64,
+    rx_buf: u64,
+    len: u32,
+    speed_hz: u32,
+    delay_usecs: u16,
+    bits_per_word: u8,
+    cs_change: u8,
+    tx_nbits: u8,
+    rx_nbits: u8,
+    word_delay_usecs: u8,
+    pad: u8,
+}
+
+/// Error opening a device
+#[derive(Debug)]
+pub enum OpenError {
+    /// The device or GPIO line doesn't exist
+    NotFound {
+        /// Path that was opened
+        path: String,
+    },
+    /// The device or GPIO line can't be accessed by the current user
+    PermissionDenied {
+        /// Path that was opened
+        path: String,
+    },
+    /// Any other I/O error
+    Io {
+        /// Path that was being accessed
+        path: String,
+        /// The error
+        error: io::Error,
+    },
+}
+
+impl OpenError {
+    fn new(path: &str, error: io::Error) -> Self {
+        let path = path.to_string();
+        match error.kind() {
+            io::ErrorKind::NotFound => OpenError::NotFound { path },
+            io::ErrorKind::PermissionDenied => OpenError::PermissionDenied { path },
+            _ => OpenError::Io { path, error },
+        }
+    }
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OpenError::NotFound { path } => write!(f, "{}: no such device", path),
+            OpenError::PermissionDenied { path } => write!(
+                f,
+                "{}: permission denied (is the user in the `spi` / `gpio` group?)",
+                path
+            ),
+            OpenError::Io { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Creates a driver from the spidev device at `spidev` (e.g.
+/// `/dev/spidev0.0`) clocked at `speed_hz`, with the GPIO line `cs_gpio` as
+/// chip select
+///
+/// The sensor is initialised like `L3gd20::new` does.
+pub fn open(
+    spidev: &str,
+    cs_gpio: u32,
+    speed_hz: u32,
+) -> Result<L3gd20<Spidev, SysfsPin>, OpenError> {
+    let spi = Spidev::open(spidev, speed_hz)?;
+    let cs = SysfsPin::export(cs_gpio)?;
+    L3gd20::connect(spi, cs, CsPolarity::ActiveLow).map_err(|e| OpenError::new(spidev, e))
+}
+
+/// SPI bus of a spidev device, in the mode required by the sensor
+pub struct Spidev {
+    file: File,
+    speed_hz: u32,
+}
+
+impl Spidev {
+    /// Open the spidev device at `path` and configure it for the sensor,
+    /// clocked at `speed_hz`
+    pub fn open(path: &str, speed_hz: u32) -> Result<Self, OpenError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(|e| OpenError::new(path, e))?;
+
+        let mut mode = 0;
+        if MODE.polarity == Polarity::IdleHigh {
+            mode |= SPI_CPOL;
+        }
+        if MODE.phase == Phase::CaptureOnSecondTransition {
+            mode |= SPI_CPHA;
+        }
+        let bits: u8 = 8;
+
+        let fd = file.as_raw_fd();
+        // SAFETY: each request is passed a pointer to a value of the size
+        // encoded in the request
+        let configured = unsafe {
+            ioctl(fd, SPI_IOC_WR_MODE, &mode as *const u8) >= 0
+                && ioctl(fd, SPI_IOC_WR_BITS_PER_WORD, &bits as *const u8) >= 0
+                && ioctl(fd, SPI_IOC_WR_MAX_SPEED_HZ, &speed_hz as *const u32) >= 0
+        };
+        if !configured {
+            return Err(OpenError::new(path, io::Error::last_os_error()));
+        }
+
+        Ok(Spidev { file, speed_hz })
+    }
+
+    /// One full duplex transfer of `tx.len()` bytes
+    fn message(&mut self, tx: &[u8], rx: Option<&mut [u8]>) -> io::Result<()> {
+        let transfer = SpiIocTransfer {
+            tx_buf: tx.as_ptr() as u64,
+            rx_buf: rx.map_or(0, |rx| rx.as_mut_ptr() as u64),
+            len: tx.len() as u32,
+            speed_hz: self.speed_hz,
+            ..SpiIocTransfer::default()
+        };
+
+        // SAFETY: `transfer` points to buffers of `len` bytes that outlive
+        // the call; `rx`, if any, has the same length as `tx`
+        let fd = self.file.as_raw_fd();
+        let result = unsafe { ioctl(fd, SPI_IOC_MESSAGE_1, &transfer as *const SpiIocTransfer) };
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Transfer<u8> for Spidev {
+    type Error = io::Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> io::Result<&'w [u8]> {
+        let mut rx = [0; 64];
+        for chunk in words.chunks_mut(rx.len()) {
+            let rx = &mut rx[..chunk.len()];
+            self.message(chunk, Some(&mut *rx))?;
+            chunk.copy_from_slice(rx);
+        }
+        Ok(words)
+    }
+}
+
+impl Write<u8> for Spidev {
+    type Error = io::Error;
+
+    fn write(&mut self, words: &[u8]) -> io::Result<()> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        self.message(words, None)
+    }
+}
+
+/// GPIO line driven through `/sys/class/gpio`
+pub struct SysfsPin {
+    value: File,
+}
+
+impl SysfsPin {
+    /// Export GPIO line `line` and configure it as an output, initially high
+    ///
+    /// A line that is already exported is reused.
+    pub fn export(line: u32) -> Result<Self, OpenError> {
+        let dir = std::format!("/sys/class/gpio/gpio{}", line);
+        if fs::metadata(&dir).is_err() {
+            let export = "/sys/class/gpio/export";
+            fs::write(export, line.to_string()).map_err(|e| OpenError::new(export, e))?;
+        }
+
+        // udev may take a moment to grant access to a newly exported line
+        let direction = std::format!("{}/direction", dir);
+        let mut attempts = 0;
+        while let Err(e) = fs::write(&direction, "high") {
+            attempts += 1;
+            if e.kind() != io::ErrorKind::PermissionDenied || attempts == 10 {
+                return Err(OpenError::new(&direction, e));
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        let path = std::format!("{}/value", dir);
+        let value = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .map_err(|e| OpenError::new(&path, e))?;
+        Ok(SysfsPin { value })
+    }
+}
+
+impl OutputPin for SysfsPin {
+    type Error = io::Error;
+
+    fn set_low(&mut self) -> io::Result<()> {
+        self.value.write_at(b"0", 0).map(|_| ())
+    }
+
+    fn set_high(&mut self) -> io::Result<()> {
+        self.value.write_at(b"1", 0).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ioctls_match_the_kernel_headers() {
+        // `SPI_IOC_MESSAGE(1)`, `SPI_IOC_WR_MODE` and `SPI_IOC_WR_MAX_SPEED_HZ`
+        assert_eq!(SPI_IOC_MESSAGE_1, IOC_WRITE | 0x0020_6b00);
+        assert_eq!(SPI_IOC_WR_MODE, IOC_WRITE | 0x0001_6b01);
+        assert_eq!(SPI_IOC_WR_MAX_SPEED_HZ, IOC_WRITE | 0x0004_6b04);
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(SPI_IOC_WR_MODE, 0x4001_6b01);
+        assert_eq!(core::mem::size_of::<SpiIocTransfer>(), 32);
+    }
+}
@@ -0,0 +1,85 @@
+//! CSV formatting of measurements for data logging
+//!
+//! The layouts are stable: they only change with a major version of this
+//! crate. Every line, the header included, ends with `\n`, and fields are
+//! separated by `,` without spaces.
+//!
+//! - `write_csv_header` / `write_csv_line` (`float` feature):
+//!   `t_us,gx_dps,gy_dps,gz_dps,temp_raw,flags`, with the rates printed with
+//!   three decimals, e.g. `1000,0.438,-0.035,12.250,25,0`
+//! - `write_csv_header_mdps` / `write_csv_line_mdps`:
+//!   `t_us,gx_mdps,gy_mdps,gz_mdps,temp_raw,flags`, with integer rates, e.g.
+//!   `1000,438,-35,12250,25,0`
+//!
+//! `t_us` is the timestamp passed by the caller. `temp_raw` is the
+//! temperature reading as returned by `L3gd20::temp`: the sensor has no
+//! absolute temperature reference, so it is not converted to degrees
+//! Celsius. `flags` is a decimal bit field: bits 0, 1 and 2 are set when the
+//! X, Y and Z axes are saturated (see `L3gd20::gyro_checked`); the other bits
+//! are currently always zero.
+
+use core::fmt::{Result, Write};
+
+use crate::{Measurements, Scale};
+
+/// Write the header of the `write_csv_line` layout
+#[cfg(feature = "float")]
+pub fn write_csv_header<W: Write>(w: &mut W) -> Result {
+    w.write_str("t_us,gx_dps,gy_dps,gz_dps,temp_raw,flags\n")
+}
+
+/// Write a measurement taken at `scale` as a line of the degrees per second
+/// layout
+#[cfg(feature = "float")]
+pub fn write_csv_line<W: Write>(
+    w: &mut W,
+    timestamp: u32,
+    measurements: &Measurements,
+    scale: Scale,
+) -> Result {
+    let dps = scale.degrees_vec(&measurements.gyro);
+    writeln!(
+        w,
+        "{},{:.3},{:.3},{:.3},{},{}",
+        timestamp,
+        dps.x,
+        dps.y,
+        dps.z,
+        measurements.temp,
+        flags(measurements)
+    )
+}
+
+/// Write the header of the `write_csv_line_mdps` layout
+pub fn write_csv_header_mdps<W: Write>(w: &mut W) -> Result {
+    w.write_str("t_us,gx_mdps,gy_mdps,gz_mdps,temp_raw,flags\n")
+}
+
+/// Write a measurement taken at `scale` as a line of the milli-degrees per
+/// second layout; uses integer math only
+pub fn write_csv_line_mdps<W: Write>(
+    w: &mut W,
+    timestamp: u32,
+    measurements: &Measurements,
+    scale: Scale,
+) -> Result {
+    let mdps = scale.mdps_vec(&measurements.gyro);
+    writeln!(
+        w,
+        "{},{},{},{},{},{}",
+        timestamp,
+        mdps.x,
+        mdps.y,
+        mdps.z,
+        measurements.temp,
+        flags(measurements)
+    )
+}
+
+fn flags(measurements: &Measurements) -> u8 {
+    let saturated = measurements.gyro.saturated_axes();
+    saturated
+        .iter()
+        .enumerate()
+        .fold(0, |flags, (i, &s)| flags | (u8::from(s) << i))
+}
@@ -0,0 +1,180 @@
+//! Axis remapping
+
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::I16x3;
+
+/// Physical axis of the sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// X-axis
+    X,
+    /// Y-axis
+    Y,
+    /// Z-axis
+    Z,
+}
+
+/// Source of one output axis: a physical axis and whether it's negated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisSource {
+    /// Physical axis the output axis is taken from
+    pub axis: Axis,
+    /// Invert the sign of the physical axis
+    pub negate: bool,
+}
+
+impl AxisSource {
+    /// Physical `axis`, unchanged
+    pub const fn pos(axis: Axis) -> Self {
+        AxisSource {
+            axis,
+            negate: false,
+        }
+    }
+
+    /// Physical `axis`, negated
+    pub const fn neg(axis: Axis) -> Self {
+        AxisSource { axis, negate: true }
+    }
+}
+
+/// Mapping from the physical sensor axes to the output axes
+///
+/// Use this to express measurements in the frame of whatever the sensor is
+/// mounted on. A mapping is always a permutation of the physical axes, with
+/// optional sign inversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisMapping {
+    sources: [AxisSource; 3],
+}
+
+impl Default for AxisMapping {
+    fn default() -> Self {
+        AxisMapping::IDENTITY
+    }
+}
+
+impl AxisMapping {
+    /// Output axes equal to the physical axes
+    pub const IDENTITY: AxisMapping = AxisMapping {
+        sources: [
+            AxisSource::pos(Axis::X),
+            AxisSource::pos(Axis::Y),
+            AxisSource::pos(Axis::Z),
+        ],
+    };
+
+    /// Creates a mapping from the sources of the output X, Y and Z axes
+    ///
+    /// Returns `None` if the sources are not a permutation of the physical
+    /// axes, i.e. if a physical axis is used more than once.
+    pub fn new(x: AxisSource, y: AxisSource, z: AxisSource) -> Option<Self> {
+        if x.axis == y.axis || x.axis == z.axis || y.axis == z.axis {
+            return None;
+        }
+
+        Some(AxisMapping {
+            sources: [x, y, z],
+        })
+    }
+
+    /// Sources of the output X, Y and Z axes
+    pub fn sources(&self) -> [AxisSource; 3] {
+        self.sources
+    }
+
+    /// Apply the mapping to a measurement
+    ///
+    /// Negating `i16::MIN` saturates to `i16::MAX`.
+    pub fn apply(&self, v: I16x3) -> I16x3 {
+        let pick = |src: AxisSource| {
+            let val = match src.axis {
+                Axis::X => v.x,
+                Axis::Y => v.y,
+                Axis::Z => v.z,
+            };
+            if src.negate {
+                val.saturating_neg()
+            } else {
+                val
+            }
+        };
+
+        I16x3 {
+            x: pick(self.sources[0]),
+            y: pick(self.sources[1]),
+            z: pick(self.sources[2]),
+        }
+    }
+
+    /// Apply the mapping to a scaled measurement
+    #[cfg(feature = "float")]
+    pub fn apply_f32(&self, v: F32x3) -> F32x3 {
+        let pick = |src: AxisSource| {
+            let val = match src.axis {
+                Axis::X => v.x,
+                Axis::Y => v.y,
+                Axis::Z => v.z,
+            };
+            if src.negate {
+                -val
+            } else {
+                val
+            }
+        };
+
+        F32x3 {
+            x: pick(self.sources[0]),
+            y: pick(self.sources[1]),
+            z: pick(self.sources[2]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // output (x, y, z) = (-z, x, y)
+    fn mapping() -> AxisMapping {
+        AxisMapping::new(
+            AxisSource::neg(Axis::Z),
+            AxisSource::pos(Axis::X),
+            AxisSource::pos(Axis::Y),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn new_rejects_repeated_axes() {
+        let x = AxisSource::pos(Axis::X);
+        let y = AxisSource::neg(Axis::Y);
+        assert_eq!(AxisMapping::new(x, y, AxisSource::neg(Axis::X)), None);
+        assert_eq!(AxisMapping::new(x, x, AxisSource::pos(Axis::Z)), None);
+        assert_eq!(
+            AxisMapping::new(x, AxisSource::pos(Axis::Y), AxisSource::pos(Axis::Z)),
+            Some(AxisMapping::IDENTITY)
+        );
+    }
+
+    #[test]
+    fn apply_permutes_and_negates() {
+        let v = I16x3 { x: 1, y: 2, z: 3 };
+        assert_eq!(AxisMapping::default().apply(v), v);
+        assert_eq!(mapping().apply(v), I16x3 { x: -3, y: 1, z: 2 });
+    }
+
+    #[test]
+    fn negating_min_saturates() {
+        let v = I16x3 { x: 0, y: 0, z: i16::MIN };
+        assert_eq!(mapping().apply(v).x, i16::MAX);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn apply_f32_matches_apply() {
+        let v = F32x3 { x: 1.5, y: -2.0, z: 0.25 };
+        assert_eq!(mapping().apply_f32(v), F32x3 { x: -0.25, y: 1.5, z: -2.0 });
+    }
+}
@@ -0,0 +1,142 @@
+//! Noise and vibration measurement
+
+use crate::{Accumulator, Bandwidth, F32x3, I16x3, Odr, Scale};
+
+/// Per-axis noise statistics of a series of measurements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseReport {
+    /// Data rate the measurements were taken at
+    pub odr: Odr,
+    /// Low-pass cut-off the measurements were taken with
+    pub bandwidth: Bandwidth,
+    /// Full scale the measurements were taken at
+    pub scale: Scale,
+    /// Number of measurements
+    pub samples: u16,
+    /// Mean, in counts
+    pub mean: F32x3,
+    /// Variance, in counts squared
+    pub variance: F32x3,
+    /// Difference between the largest and the smallest measurement, in
+    /// counts
+    pub peak_to_peak: [u16; 3],
+}
+
+impl NoiseReport {
+    /// Mean, in degrees per second
+    pub fn mean_dps(&self) -> F32x3 {
+        self.mean.scale(self.scale.degrees_per_count())
+    }
+
+    /// Variance, in degrees per second squared
+    pub fn variance_dps(&self) -> F32x3 {
+        let k = self.scale.degrees_per_count();
+        self.variance.scale(k * k)
+    }
+
+    /// Peak-to-peak, in degrees per second
+    pub fn peak_to_peak_dps(&self) -> F32x3 {
+        let k = self.scale.degrees_per_count();
+        F32x3 {
+            x: self.peak_to_peak[0] as f32 * k,
+            y: self.peak_to_peak[1] as f32 * k,
+            z: self.peak_to_peak[2] as f32 * k,
+        }
+    }
+}
+
+/// RMS vibration over a window of measurements, see
+/// `L3gd20::measure_vibration`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VibrationReport {
+    /// Data rate the measurements were taken at
+    pub odr: Odr,
+    /// Low-pass cut-off the measurements were taken with
+    pub bandwidth: Bandwidth,
+    /// Full scale the measurements were taken at
+    pub scale: Scale,
+    /// Number of measurements
+    pub samples: u16,
+    /// Mean of the window, which was removed, in degrees per second
+    pub mean_dps: F32x3,
+    /// RMS of each axis around its mean, in degrees per second
+    pub rms_dps: F32x3,
+    /// RMS of the combined rate, i.e. the norm of `rms_dps`, in degrees per
+    /// second
+    pub total_rms_dps: f32,
+}
+
+/// Build a vibration report from the statistics of a window
+pub(crate) fn vibration_report(
+    acc: &Accumulator,
+    odr: Odr,
+    bandwidth: Bandwidth,
+    scale: Scale,
+) -> VibrationReport {
+    let k = scale.degrees_per_count();
+    let variance = acc.variance_f32().unwrap_or_default();
+    let rms_dps = F32x3 {
+        x: libm::sqrtf(variance.x),
+        y: libm::sqrtf(variance.y),
+        z: libm::sqrtf(variance.z),
+    }
+    .scale(k);
+
+    VibrationReport {
+        odr,
+        bandwidth,
+        scale,
+        samples: acc.count() as u16,
+        mean_dps: acc.mean_f32().unwrap_or_default().scale(k),
+        rms_dps,
+        total_rms_dps: rms_dps.norm(),
+    }
+}
+
+/// Accumulates the statistics of all three axes
+#[derive(Clone, Copy)]
+pub(crate) struct NoiseStats {
+    acc: Accumulator,
+    min: [i16; 3],
+    max: [i16; 3],
+}
+
+impl NoiseStats {
+    pub(crate) const fn new() -> Self {
+        NoiseStats {
+            acc: Accumulator::with_variance(),
+            min: [i16::MAX; 3],
+            max: [i16::MIN; 3],
+        }
+    }
+
+    /// Push a measurement into the statistics
+    pub(crate) fn push(&mut self, v: I16x3) {
+        self.acc.push(v);
+        for (i, &c) in [v.x, v.y, v.z].iter().enumerate() {
+            self.min[i] = self.min[i].min(c);
+            self.max[i] = self.max[i].max(c);
+        }
+    }
+
+    /// Build a report from the statistics
+    pub(crate) fn report(&self, odr: Odr, bandwidth: Bandwidth, scale: Scale) -> NoiseReport {
+        let peak_to_peak = |i: usize| {
+            if self.max[i] < self.min[i] {
+                0
+            } else {
+                (i32::from(self.max[i]) - i32::from(self.min[i])) as u16
+            }
+        };
+
+        NoiseReport {
+            odr,
+            bandwidth,
+            scale,
+            samples: self.acc.count() as u16,
+            mean: self.acc.mean_f32().unwrap_or_default(),
+            variance: self.acc.variance_f32().unwrap_or_default(),
+            peak_to_peak: [peak_to_peak(0), peak_to_peak(1), peak_to_peak(2)],
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Rate-limited polling
+
+use core::convert::TryFrom;
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::CtrlReg5;
+use crate::{Clock, Error, L3gd20, Measurements, WaitTarget};
+
+/// A measurement read by `L3gd20::poll_at_hz`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolledSample {
+    /// The freshest measurement, with the stored bias subtracted
+    pub measurements: Measurements,
+    /// Samples produced by the sensor since the previous poll and not
+    /// returned
+    pub skipped: u32,
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Read one measurement every `1 / hz` seconds, sleeping in between
+    ///
+    /// Sleeps for the polling interval minus one sample period, then waits
+    /// for the next sample, so that the freshest one is returned without
+    /// polling `STATUS_REG` for the whole interval. The time spent between
+    /// two calls is not known, so the effective rate is a bit lower than
+    /// `hz`; see `poll_at_hz_with_clock`. If `hz` is at least the data rate,
+    /// every call simply waits for the next sample.
+    ///
+    /// When the FIFO is enabled (`FIFO_EN` in the cached `CTRL_REG5`) it is
+    /// drained, the last sample is returned and the others are counted as
+    /// skipped; that count is exact unless the FIFO overflowed. Otherwise
+    /// the samples overwritten while sleeping are estimated from the sleep
+    /// time. Fails with `Error::InvalidConfig` if `hz` is not positive.
+    pub fn poll_at_hz<D: DelayUs<u32>>(
+        &mut self,
+        hz: f32,
+        delay: &mut D,
+    ) -> Result<PolledSample, Error<E>> {
+        let interval_us = interval_us(hz)?;
+        let period_us = self.dt_us()?;
+        let sleep_us = interval_us.saturating_sub(period_us);
+        if sleep_us > 0 {
+            delay.delay_us(sleep_us);
+        }
+        self.freshest(delay, sleep_us / period_us)
+    }
+
+    /// Same as `poll_at_hz`, subtracting the time elapsed since the previous
+    /// call from the sleep
+    ///
+    /// `clock` counts `ticks_per_second` ticks per second. The first call
+    /// sleeps for the whole interval. Without a FIFO the skipped samples are
+    /// estimated from the time elapsed since the previous call.
+    pub fn poll_at_hz_with_clock<D, C>(
+        &mut self,
+        hz: f32,
+        delay: &mut D,
+        clock: &mut C,
+        ticks_per_second: u64,
+    ) -> Result<PolledSample, Error<E>>
+    where
+        D: DelayUs<u32>,
+        C: Clock,
+    {
+        let interval_us = interval_us(hz)?;
+        if ticks_per_second == 0 {
+            return Err(Error::InvalidConfig("the clock must count ticks"));
+        }
+        let period_us = self.dt_us()?;
+
+        let elapsed_us = |now: u64, last: Option<u64>| {
+            let ticks = last.map_or(0, |last| now.saturating_sub(last));
+            let us = u128::from(ticks) * 1_000_000 / u128::from(ticks_per_second);
+            u32::try_from(us).unwrap_or(u32::MAX)
+        };
+        let last_poll = self.last_poll;
+        let elapsed = elapsed_us(clock.now(), last_poll);
+        let sleep_us = interval_us
+            .saturating_sub(elapsed)
+            .saturating_sub(period_us);
+        if sleep_us > 0 {
+            delay.delay_us(sleep_us);
+        }
+
+        let now = clock.now();
+        let estimate = match last_poll {
+            Some(_) => (elapsed_us(now, last_poll) / period_us).saturating_sub(1),
+            None => sleep_us / period_us,
+        };
+        let sample = self.freshest(delay, estimate)?;
+        self.last_poll = Some(now);
+        Ok(sample)
+    }
+
+    /// Wait for data and read the freshest sample; `estimate` is the number
+    /// of skipped samples reported without a FIFO
+    fn freshest<D: DelayUs<u32>>(
+        &mut self,
+        delay: &mut D,
+        estimate: u32,
+    ) -> Result<PolledSample, Error<E>> {
+        let (gyro, skipped) = if CtrlReg5::from_bits(self.shadow[4]).fifo_en() {
+            self.wait_until(delay, WaitTarget::FifoSamples, |l3gd20| {
+                Ok(!l3gd20.fifo_status()?.empty())
+            })?;
+            let stored = u32::from(self.fifo_status()?.fss()).max(1);
+            let mut gyro = self.gyro_raw()?;
+            for _ in 1..stored {
+                gyro = self.gyro_raw()?;
+            }
+            (gyro, stored - 1)
+        } else {
+            self.wait_data_ready(delay)?;
+            let (status, gyro) = self.read_status_and_gyro()?;
+            let skipped = if status.overrun() {
+                estimate.max(1)
+            } else {
+                estimate
+            };
+            (gyro, skipped)
+        };
+
+        Ok(PolledSample {
+            measurements: Measurements {
+                gyro: self.adjust(gyro),
+                temp: self.temp()?,
+            },
+            skipped,
+        })
+    }
+}
+
+/// Polling interval for `hz`, in microseconds
+fn interval_us<E>(hz: f32) -> Result<u32, Error<E>> {
+    if hz > 0.0 && hz.is_finite() {
+        // `as` saturates, e.g. for rates below 1 / 4295 Hz
+        Ok((1_000_000.0 / hz) as u32)
+    } else {
+        Err(Error::InvalidConfig("the polling rate must be positive"))
+    }
+}
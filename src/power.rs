@@ -0,0 +1,224 @@
+//! Type-state power management
+//!
+//! `power::L3gd20` wraps the driver and tracks in its type whether the
+//! sensor is powered down or active, so measurements can't be requested from
+//! a sensor that is powered down. The plain `crate::L3gd20` API is not
+//! affected.
+//!
+//! ``` ignore
+//! let l3gd20 = power::L3gd20::powered_down(l3gd20)?;
+//! let mut l3gd20 = l3gd20.power_up()?;
+//! let gyro = l3gd20.gyro()?;
+//! ```
+//!
+//! Measuring and powering down need an `Active` sensor:
+//!
+//! ```
+//! # use embedded_hal::blocking::spi::{Transfer, Write};
+//! # use embedded_hal::digital::v2::OutputPin;
+//! # use l3gd20::power::{Active, L3gd20};
+//! # fn measure<SPI, CS>(mut l3gd20: L3gd20<SPI, CS, Active>)
+//! # where
+//! #     SPI: Transfer<u8, Error = ()> + Write<u8, Error = ()>,
+//! #     CS: OutputPin,
+//! # {
+//! let gyro = l3gd20.gyro();
+//! let l3gd20 = l3gd20.power_down();
+//! # }
+//! ```
+//!
+//! so reading a sensor that is powered down doesn't compile:
+//!
+//! ``` compile_fail
+//! # use embedded_hal::blocking::spi::{Transfer, Write};
+//! # use embedded_hal::digital::v2::OutputPin;
+//! # use l3gd20::power::{L3gd20, PoweredDown};
+//! # fn measure<SPI, CS>(mut l3gd20: L3gd20<SPI, CS, PoweredDown>)
+//! # where
+//! #     SPI: Transfer<u8, Error = ()> + Write<u8, Error = ()>,
+//! #     CS: OutputPin,
+//! # {
+//! let gyro = l3gd20.gyro();
+//! # }
+//! ```
+//!
+//! and neither does using the `Active` driver once it's powered down:
+//!
+//! ``` compile_fail
+//! # use embedded_hal::blocking::spi::{Transfer, Write};
+//! # use embedded_hal::digital::v2::OutputPin;
+//! # use l3gd20::power::{Active, L3gd20};
+//! # fn measure<SPI, CS>(mut l3gd20: L3gd20<SPI, CS, Active>)
+//! # where
+//! #     SPI: Transfer<u8, Error = ()> + Write<u8, Error = ()>,
+//! #     CS: OutputPin,
+//! # {
+//! let powered_down = l3gd20.power_down();
+//! let gyro = l3gd20.gyro();
+//! # }
+//! ```
+
+use core::marker::PhantomData;
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::{CtrlReg1, Register};
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::{
+    AngularRate, Bandwidth, Error, I16x3, I32x3, Measurements, Odr, PowerMode, Scale, Status,
+};
+
+/// Power state of a sensor that is powered down
+pub enum PoweredDown {}
+
+/// Power state of a sensor that is measuring
+pub enum Active {}
+
+/// L3GD20 driver whose power state is tracked by `STATE`
+pub struct L3gd20<SPI, CS, STATE> {
+    l3gd20: crate::L3gd20<SPI, CS>,
+    _state: PhantomData<STATE>,
+}
+
+impl<SPI, CS, E, STATE> L3gd20<SPI, CS, STATE>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    fn into_state<S>(mut self, power: PowerMode) -> Result<L3gd20<SPI, CS, S>, Error<E>> {
+        self.l3gd20.verified(|l3gd20| {
+            l3gd20.change_config(Register::CTRL_REG1, |r| {
+                CtrlReg1::from_bits(r).with_power(power).bits()
+            })?;
+            Ok(())
+        })?;
+        Ok(L3gd20 {
+            l3gd20: self.l3gd20,
+            _state: PhantomData,
+        })
+    }
+
+    /// Release the underlying driver
+    pub fn release(self) -> crate::L3gd20<SPI, CS> {
+        self.l3gd20
+    }
+
+    /// See `crate::L3gd20::odr`
+    pub fn odr(&mut self) -> Result<Odr, Error<E>> {
+        self.l3gd20.odr()
+    }
+
+    /// See `crate::L3gd20::set_odr`
+    pub fn set_odr(&mut self, odr: Odr) -> Result<&mut Self, Error<E>> {
+        self.l3gd20.set_odr(odr)?;
+        Ok(self)
+    }
+
+    /// See `crate::L3gd20::bandwidth`
+    pub fn bandwidth(&mut self) -> Result<Bandwidth, Error<E>> {
+        self.l3gd20.bandwidth()
+    }
+
+    /// See `crate::L3gd20::set_bandwidth`
+    pub fn set_bandwidth(&mut self, bw: Bandwidth) -> Result<&mut Self, Error<E>> {
+        self.l3gd20.set_bandwidth(bw)?;
+        Ok(self)
+    }
+
+    /// See `crate::L3gd20::scale`
+    pub fn scale(&mut self) -> Result<Scale, Error<E>> {
+        self.l3gd20.scale()
+    }
+
+    /// See `crate::L3gd20::set_scale`
+    pub fn set_scale(&mut self, scale: Scale) -> Result<&mut Self, Error<E>> {
+        self.l3gd20.set_scale(scale)?;
+        Ok(self)
+    }
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS, PoweredDown>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Power down the sensor and track its power state from now on
+    pub fn powered_down(l3gd20: crate::L3gd20<SPI, CS>) -> Result<Self, Error<E>> {
+        L3gd20 {
+            l3gd20,
+            _state: PhantomData::<PoweredDown>,
+        }
+        .into_state(PowerMode::PowerDown)
+    }
+
+    /// Power up the sensor
+    ///
+    /// Note that the first measurements after power-up are not valid yet;
+    /// see the datasheet for the turn-on time.
+    pub fn power_up(self) -> Result<L3gd20<SPI, CS, Active>, Error<E>> {
+        self.into_state(PowerMode::Normal)
+    }
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS, Active>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Power up the sensor and track its power state from now on
+    pub fn active(l3gd20: crate::L3gd20<SPI, CS>) -> Result<Self, Error<E>> {
+        L3gd20 {
+            l3gd20,
+            _state: PhantomData::<Active>,
+        }
+        .into_state(PowerMode::Normal)
+    }
+
+    /// Power down the sensor
+    pub fn power_down(self) -> Result<L3gd20<SPI, CS, PoweredDown>, Error<E>> {
+        self.into_state(PowerMode::PowerDown)
+    }
+
+    /// See `crate::L3gd20::all`
+    pub fn all(&mut self) -> Result<Measurements, E> {
+        self.l3gd20.all()
+    }
+
+    /// See `crate::L3gd20::gyro`
+    pub fn gyro(&mut self) -> Result<I16x3, E> {
+        self.l3gd20.gyro()
+    }
+
+    /// See `crate::L3gd20::gyro_raw`
+    pub fn gyro_raw(&mut self) -> Result<I16x3, E> {
+        self.l3gd20.gyro_raw()
+    }
+
+    /// See `crate::L3gd20::gyro_mdps`
+    pub fn gyro_mdps(&mut self) -> Result<I32x3, Error<E>> {
+        self.l3gd20.gyro_mdps()
+    }
+
+    /// See `crate::L3gd20::gyro_rate`
+    pub fn gyro_rate(&mut self) -> Result<AngularRate, Error<E>> {
+        self.l3gd20.gyro_rate()
+    }
+
+    /// See `crate::L3gd20::gyro_dps`
+    #[cfg(feature = "float")]
+    pub fn gyro_dps(&mut self) -> Result<F32x3, Error<E>> {
+        self.l3gd20.gyro_dps()
+    }
+
+    /// See `crate::L3gd20::temp`
+    pub fn temp(&mut self) -> Result<i8, E> {
+        self.l3gd20.temp()
+    }
+
+    /// See `crate::L3gd20::status`
+    pub fn status(&mut self) -> Result<Status, E> {
+        self.l3gd20.status()
+    }
+}
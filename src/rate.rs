@@ -0,0 +1,140 @@
+//! Angular rate with on-demand unit conversions
+
+use core::fmt;
+
+#[cfg(feature = "float")]
+use crate::float::DEG_TO_RAD;
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::{I16x3, I32x3, Measurements, Scale};
+
+/// Angular rate measurement, kept in raw counts together with the `Scale` it
+/// was taken at
+///
+/// No precision is lost until one of the unit accessors is called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AngularRate {
+    raw: I16x3,
+    scale: Scale,
+}
+
+impl AngularRate {
+    /// Creates an angular rate from a measurement in counts taken at `scale`
+    pub const fn new(raw: I16x3, scale: Scale) -> Self {
+        AngularRate { raw, scale }
+    }
+
+    /// Measurement in counts
+    pub const fn raw(&self) -> I16x3 {
+        self.raw
+    }
+
+    /// Full Scale Selection the measurement was taken at
+    pub const fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    /// Rate in milli-degrees per second, using integer math only
+    pub const fn mdps(&self) -> I32x3 {
+        self.scale.mdps_vec(&self.raw)
+    }
+
+    /// Rate in degrees per second
+    #[cfg(feature = "float")]
+    pub fn dps(&self) -> F32x3 {
+        self.scale.degrees_vec(&self.raw)
+    }
+
+    /// Rate in radians per second
+    #[cfg(feature = "float")]
+    pub fn rad_s(&self) -> F32x3 {
+        F32x3::from(self.raw).scale(self.scale.degrees_per_count() * DEG_TO_RAD)
+    }
+
+    /// Rate in revolutions per minute
+    #[cfg(feature = "float")]
+    pub fn rpm(&self) -> F32x3 {
+        // 1 rpm = 360 degrees / 60 seconds
+        F32x3::from(self.raw).scale(self.scale.degrees_per_count() / 6.0)
+    }
+}
+
+impl fmt::Display for AngularRate {
+    /// Rate in degrees per second with one decimal, e.g.
+    /// `[+12.3, -0.4, +998.1] dps`
+    ///
+    /// Without the `float` feature the rate is printed in milli-degrees per
+    /// second instead, e.g. `[+12300, -400, +998100] mdps`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(feature = "float")]
+        return write!(f, "{:.1} dps", self.dps());
+        #[cfg(not(feature = "float"))]
+        return write!(f, "{} mdps", self.mdps());
+    }
+}
+
+/// `Measurements` together with the `Scale` they were taken at, see
+/// `Measurements::at_scale`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScaledMeasurements {
+    /// Gyroscope measurements
+    pub gyro: AngularRate,
+    /// Temperature sensor measurement
+    pub temp: i8,
+}
+
+impl From<ScaledMeasurements> for Measurements {
+    fn from(scaled: ScaledMeasurements) -> Self {
+        Measurements {
+            gyro: scaled.gyro.raw(),
+            temp: scaled.temp,
+        }
+    }
+}
+
+impl fmt::Display for ScaledMeasurements {
+    /// Rate as for `AngularRate`, e.g.
+    /// `gyro: [+12.3, -0.4, +998.1] dps, temp: 27`
+    ///
+    /// The temperature reading has no absolute reference, so no unit is
+    /// printed for it.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "gyro: {}, temp: {}", self.gyro, self.temp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: I16x3 = I16x3 {
+        x: 1406,
+        y: -46,
+        z: 14_263,
+    };
+
+    #[test]
+    fn rates_are_displayed_with_their_unit() {
+        let rate = AngularRate::new(RAW, Scale::Dps250);
+        let measurements = Measurements {
+            gyro: RAW,
+            temp: 27,
+        };
+        let expected = "gyro: [+1406, -46, +14263] counts, temp: 27";
+        assert_eq!(std::format!("{}", measurements), expected);
+        let scaled = measurements.at_scale(Scale::Dps250);
+        #[cfg(feature = "float")]
+        {
+            assert_eq!(std::format!("{}", rate), "[+12.3, -0.4, +124.8] dps");
+            let expected = "gyro: [+12.3, -0.4, +124.8] dps, temp: 27";
+            assert_eq!(std::format!("{}", scaled), expected);
+        }
+        #[cfg(not(feature = "float"))]
+        {
+            assert_eq!(std::format!("{}", rate), "[+12303, -403, +124801] mdps");
+            let expected = "gyro: [+12303, -403, +124801] mdps, temp: 27";
+            assert_eq!(std::format!("{}", scaled), expected);
+        }
+        assert_eq!(Measurements::from(scaled).gyro, RAW);
+    }
+}
@@ -0,0 +1,698 @@
+//! Typed register layer
+//!
+//! Each register is represented by a newtype around its `u8` value with named
+//! accessors for its bits and fields. The accessors are pure functions, so
+//! they can be used (and tested) without a sensor.
+
+use crate::{AxesEnable, Bandwidth, DataPath, InvalidValue, Odr, PowerMode, Scale};
+use core::convert::TryFrom;
+
+/// Register addresses
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    /// Device identification
+    WHO_AM_I = 0x0F,
+    /// Data rate, bandwidth, power mode and axes enable
+    CTRL_REG1 = 0x20,
+    /// High-pass filter configuration
+    CTRL_REG2 = 0x21,
+    /// Interrupt pin configuration
+    CTRL_REG3 = 0x22,
+    /// Block data update, endianness, full scale and SPI mode
+    CTRL_REG4 = 0x23,
+    /// Boot, FIFO enable and filter selection
+    CTRL_REG5 = 0x24,
+    /// Reference value for the high-pass filter
+    REFERENCE = 0x25,
+    /// Temperature data
+    OUT_TEMP = 0x26,
+    /// Data status
+    STATUS_REG = 0x27,
+    /// X-axis data, low byte
+    OUT_X_L = 0x28,
+    /// X-axis data, high byte
+    OUT_X_H = 0x29,
+    /// Y-axis data, low byte
+    OUT_Y_L = 0x2A,
+    /// Y-axis data, high byte
+    OUT_Y_H = 0x2B,
+    /// Z-axis data, low byte
+    OUT_Z_L = 0x2C,
+    /// Z-axis data, high byte
+    OUT_Z_H = 0x2D,
+    /// FIFO mode and watermark
+    FIFO_CTRL_REG = 0x2E,
+    /// FIFO status
+    FIFO_SRC_REG = 0x2F,
+    /// Interrupt 1 configuration
+    INT1_CFG = 0x30,
+    /// Interrupt 1 source
+    INT1_SRC = 0x31,
+    /// Interrupt 1 X-axis threshold, high byte
+    INT1_TSH_XH = 0x32,
+    /// Interrupt 1 X-axis threshold, low byte
+    INT1_TSH_XL = 0x33,
+    /// Interrupt 1 Y-axis threshold, high byte
+    INT1_TSH_YH = 0x34,
+    /// Interrupt 1 Y-axis threshold, low byte
+    INT1_TSH_YL = 0x35,
+    /// Interrupt 1 Z-axis threshold, high byte
+    INT1_TSH_ZH = 0x36,
+    /// Interrupt 1 Z-axis threshold, low byte
+    INT1_TSH_ZL = 0x37,
+    /// Interrupt 1 duration
+    INT1_DURATION = 0x38,
+    /// Low data rate selection; L3GD20H only
+    LOW_ODR = 0x39,
+}
+
+impl Register {
+    /// Address of the register
+    pub fn addr(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for Register {
+    type Error = InvalidValue;
+
+    /// Register at address `value`
+    fn try_from(value: u8) -> Result<Self, InvalidValue> {
+        match value {
+            x if x == Register::WHO_AM_I as u8 => Ok(Register::WHO_AM_I),
+            x if x == Register::CTRL_REG1 as u8 => Ok(Register::CTRL_REG1),
+            x if x == Register::CTRL_REG2 as u8 => Ok(Register::CTRL_REG2),
+            x if x == Register::CTRL_REG3 as u8 => Ok(Register::CTRL_REG3),
+            x if x == Register::CTRL_REG4 as u8 => Ok(Register::CTRL_REG4),
+            x if x == Register::CTRL_REG5 as u8 => Ok(Register::CTRL_REG5),
+            x if x == Register::REFERENCE as u8 => Ok(Register::REFERENCE),
+            x if x == Register::OUT_TEMP as u8 => Ok(Register::OUT_TEMP),
+            x if x == Register::STATUS_REG as u8 => Ok(Register::STATUS_REG),
+            x if x == Register::OUT_X_L as u8 => Ok(Register::OUT_X_L),
+            x if x == Register::OUT_X_H as u8 => Ok(Register::OUT_X_H),
+            x if x == Register::OUT_Y_L as u8 => Ok(Register::OUT_Y_L),
+            x if x == Register::OUT_Y_H as u8 => Ok(Register::OUT_Y_H),
+            x if x == Register::OUT_Z_L as u8 => Ok(Register::OUT_Z_L),
+            x if x == Register::OUT_Z_H as u8 => Ok(Register::OUT_Z_H),
+            x if x == Register::FIFO_CTRL_REG as u8 => Ok(Register::FIFO_CTRL_REG),
+            x if x == Register::FIFO_SRC_REG as u8 => Ok(Register::FIFO_SRC_REG),
+            x if x == Register::INT1_CFG as u8 => Ok(Register::INT1_CFG),
+            x if x == Register::INT1_SRC as u8 => Ok(Register::INT1_SRC),
+            x if x == Register::INT1_TSH_XH as u8 => Ok(Register::INT1_TSH_XH),
+            x if x == Register::INT1_TSH_XL as u8 => Ok(Register::INT1_TSH_XL),
+            x if x == Register::INT1_TSH_YH as u8 => Ok(Register::INT1_TSH_YH),
+            x if x == Register::INT1_TSH_YL as u8 => Ok(Register::INT1_TSH_YL),
+            x if x == Register::INT1_TSH_ZH as u8 => Ok(Register::INT1_TSH_ZH),
+            x if x == Register::INT1_TSH_ZL as u8 => Ok(Register::INT1_TSH_ZL),
+            x if x == Register::INT1_DURATION as u8 => Ok(Register::INT1_DURATION),
+            x if x == Register::LOW_ODR as u8 => Ok(Register::LOW_ODR),
+            _ => Err(InvalidValue { value }),
+        }
+    }
+}
+
+/// Generates a getter and a builder-style setter for a single bit
+macro_rules! bit {
+    ($(#[$doc:meta])* $get:ident, $with:ident, $bit:expr) => {
+        $(#[$doc])*
+        pub const fn $get(&self) -> bool {
+            self.0 & (1 << $bit) != 0
+        }
+
+        $(#[$doc])*
+        ///
+        /// Returns a copy of the register with this bit set to `value`
+        pub const fn $with(self, value: bool) -> Self {
+            Self((self.0 & !(1 << $bit)) | ((value as u8) << $bit))
+        }
+    };
+}
+
+/// Generates a getter and a builder-style setter for a multi-bit field
+macro_rules! field {
+    ($(#[$doc:meta])* $get:ident, $with:ident, $shift:expr, $width:expr) => {
+        $(#[$doc])*
+        pub const fn $get(&self) -> u8 {
+            (self.0 >> $shift) & ((1 << $width) - 1)
+        }
+
+        $(#[$doc])*
+        ///
+        /// Returns a copy of the register with this field set to `value`;
+        /// bits of `value` that don't fit in the field are ignored
+        pub const fn $with(self, value: u8) -> Self {
+            let mask = ((1 << $width) - 1) << $shift;
+            Self((self.0 & !mask) | ((value << $shift) & mask))
+        }
+    };
+}
+
+/// Generates the newtype of a register, with `from_bits` and `bits`
+macro_rules! register {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $name(u8);
+
+        impl $name {
+            /// Creates the register from its raw value
+            pub const fn from_bits(bits: u8) -> Self {
+                $name(bits)
+            }
+
+            /// Raw value of the register
+            pub const fn bits(&self) -> u8 {
+                self.0
+            }
+        }
+    };
+}
+
+register!(
+    /// `CTRL_REG1`: data rate, bandwidth, power mode and axes enable
+    CtrlReg1
+);
+
+impl CtrlReg1 {
+    field!(
+        /// `DR`: output data rate selection
+        dr, with_dr, 6, 2
+    );
+    field!(
+        /// `BW`: bandwidth selection
+        bw, with_bw, 4, 2
+    );
+    bit!(
+        /// `PD`: power mode, `false` is power-down
+        pd, with_pd, 3
+    );
+    bit!(
+        /// `Zen`: Z-axis enable
+        zen, with_zen, 2
+    );
+    bit!(
+        /// `Yen`: Y-axis enable
+        yen, with_yen, 1
+    );
+    bit!(
+        /// `Xen`: X-axis enable
+        xen, with_xen, 0
+    );
+
+    /// Output data rate, assuming `Low_ODR` is clear in `LOW_ODR`; see
+    /// `odr_for`
+    pub fn odr(&self) -> Result<Odr, InvalidValue> {
+        Odr::try_from(self.dr())
+    }
+
+    /// Output data rate, given the `Low_ODR` bit of `LOW_ODR`
+    pub fn odr_for(&self, low_odr: bool) -> Odr {
+        Odr::from_dr(self.dr(), low_odr)
+    }
+
+    /// Returns a copy of the register with the output data rate set to `odr`
+    ///
+    /// Only `DR` is set; a low data rate also needs `Low_ODR` in `LOW_ODR`.
+    pub const fn with_odr(self, odr: Odr) -> Self {
+        self.with_dr(odr as u8)
+    }
+
+    /// Bandwidth
+    pub fn bandwidth(&self) -> Result<Bandwidth, InvalidValue> {
+        Bandwidth::try_from(self.bw())
+    }
+
+    /// Returns a copy of the register with the bandwidth set to `bw`
+    pub const fn with_bandwidth(self, bw: Bandwidth) -> Self {
+        self.with_bw(bw as u8)
+    }
+
+    /// Power mode
+    pub const fn power(&self) -> PowerMode {
+        if self.pd() {
+            PowerMode::Normal
+        } else {
+            PowerMode::PowerDown
+        }
+    }
+
+    /// Returns a copy of the register with the power mode set to `power`
+    pub const fn with_power(self, power: PowerMode) -> Self {
+        self.with_pd(matches!(power, PowerMode::Normal))
+    }
+
+    /// Enabled axes
+    pub const fn axes(&self) -> AxesEnable {
+        AxesEnable {
+            x: self.xen(),
+            y: self.yen(),
+            z: self.zen(),
+        }
+    }
+
+    /// Returns a copy of the register with the enabled axes set to `axes`
+    pub const fn with_axes(self, axes: AxesEnable) -> Self {
+        self.with_xen(axes.x).with_yen(axes.y).with_zen(axes.z)
+    }
+}
+
+register!(
+    /// `CTRL_REG2`: high-pass filter configuration
+    CtrlReg2
+);
+
+impl CtrlReg2 {
+    field!(
+        /// `HPM`: high-pass filter mode
+        hpm, with_hpm, 4, 2
+    );
+    field!(
+        /// `HPCF`: high-pass filter cut-off frequency
+        hpcf, with_hpcf, 0, 4
+    );
+}
+
+register!(
+    /// `CTRL_REG3`: interrupt pin configuration
+    CtrlReg3
+);
+
+impl CtrlReg3 {
+    bit!(
+        /// `I1_Int1`: interrupt enable on INT1
+        i1_int1, with_i1_int1, 7
+    );
+    bit!(
+        /// `I1_Boot`: boot status available on INT1
+        i1_boot, with_i1_boot, 6
+    );
+    bit!(
+        /// `H_Lactive`: interrupt active low on INT1
+        h_lactive, with_h_lactive, 5
+    );
+    bit!(
+        /// `PP_OD`: open drain outputs
+        pp_od, with_pp_od, 4
+    );
+    bit!(
+        /// `I2_DRDY`: data ready on DRDY/INT2
+        i2_drdy, with_i2_drdy, 3
+    );
+    bit!(
+        /// `I2_WTM`: FIFO watermark interrupt on DRDY/INT2
+        i2_wtm, with_i2_wtm, 2
+    );
+    bit!(
+        /// `I2_ORun`: FIFO overrun interrupt on DRDY/INT2
+        i2_orun, with_i2_orun, 1
+    );
+    bit!(
+        /// `I2_Empty`: FIFO empty interrupt on DRDY/INT2
+        i2_empty, with_i2_empty, 0
+    );
+}
+
+register!(
+    /// `CTRL_REG4`: block data update, endianness, full scale and SPI mode
+    CtrlReg4
+);
+
+impl CtrlReg4 {
+    bit!(
+        /// `BDU`: block data update
+        bdu, with_bdu, 7
+    );
+    bit!(
+        /// `BLE`: big endian data
+        ble, with_ble, 6
+    );
+    field!(
+        /// `FS`: full scale selection
+        fs, with_fs, 4, 2
+    );
+    bit!(
+        /// `IMPen`: level sensitive latched interrupts; L3GD20H only
+        impen, with_impen, 3
+    );
+    bit!(
+        /// `SIM`: 3-wire SPI interface
+        sim, with_sim, 0
+    );
+
+    /// Full scale
+    pub fn scale(&self) -> Result<Scale, InvalidValue> {
+        Scale::try_from(self.fs())
+    }
+
+    /// Returns a copy of the register with the full scale set to `scale`
+    pub const fn with_scale(self, scale: Scale) -> Self {
+        self.with_fs(scale as u8)
+    }
+}
+
+register!(
+    /// `CTRL_REG5`: boot, FIFO enable and filter selection
+    CtrlReg5
+);
+
+impl CtrlReg5 {
+    bit!(
+        /// `BOOT`: reboot memory content
+        boot, with_boot, 7
+    );
+    bit!(
+        /// `FIFO_EN`: FIFO enable
+        fifo_en, with_fifo_en, 6
+    );
+    bit!(
+        /// `HPen`: high-pass filter enable
+        hpen, with_hpen, 4
+    );
+    field!(
+        /// `INT1_Sel`: INT1 generator selection
+        int1_sel, with_int1_sel, 2, 2
+    );
+    field!(
+        /// `Out_Sel`: output selection
+        out_sel, with_out_sel, 0, 2
+    );
+
+    /// Filters in the output data path
+    ///
+    /// `Out_Sel = 1x` selects low-pass filter 2 and the high-pass filter is
+    /// only reported when it's part of the output path.
+    pub const fn data_path(&self) -> DataPath {
+        match (self.out_sel(), self.hpen()) {
+            (0b00, _) => DataPath::Lpf1,
+            (0b01, true) => DataPath::Hpf,
+            (0b01, false) => DataPath::Lpf1,
+            (_, true) => DataPath::HpfLpf2,
+            (_, false) => DataPath::Lpf2,
+        }
+    }
+
+    /// Returns a copy of the register with the output data path set to
+    /// `path`
+    pub const fn with_data_path(self, path: DataPath) -> Self {
+        let (hpen, out_sel) = match path {
+            DataPath::Lpf1 => (false, 0b00),
+            DataPath::Hpf => (true, 0b01),
+            DataPath::Lpf2 => (false, 0b10),
+            DataPath::HpfLpf2 => (true, 0b10),
+        };
+        self.with_hpen(hpen).with_out_sel(out_sel)
+    }
+}
+
+register!(
+    /// `FIFO_CTRL_REG`: FIFO mode and watermark
+    FifoCtrl
+);
+
+impl FifoCtrl {
+    field!(
+        /// `FM`: FIFO mode
+        fm, with_fm, 5, 3
+    );
+    field!(
+        /// `WTM`: FIFO watermark level
+        wtm, with_wtm, 0, 5
+    );
+}
+
+register!(
+    /// `FIFO_SRC_REG`: FIFO status
+    FifoSrc
+);
+
+impl FifoSrc {
+    bit!(
+        /// `WTM`: FIFO filling is equal to or higher than the watermark level
+        wtm, with_wtm, 7
+    );
+    bit!(
+        /// `OVRN`: FIFO is full and at least one sample was overwritten
+        ovrn, with_ovrn, 6
+    );
+    bit!(
+        /// `EMPTY`: FIFO is empty
+        empty, with_empty, 5
+    );
+    field!(
+        /// `FSS`: number of unread samples in the FIFO
+        fss, with_fss, 0, 5
+    );
+}
+
+register!(
+    /// `INT1_CFG`: interrupt 1 configuration
+    Int1Cfg
+);
+
+impl Int1Cfg {
+    bit!(
+        /// `AND/OR`: AND combination of the interrupt events
+        and_or, with_and_or, 7
+    );
+    bit!(
+        /// `LIR`: latch the interrupt request
+        lir, with_lir, 6
+    );
+    bit!(
+        /// `ZHIE`: interrupt on Z-axis high event
+        zhie, with_zhie, 5
+    );
+    bit!(
+        /// `ZLIE`: interrupt on Z-axis low event
+        zlie, with_zlie, 4
+    );
+    bit!(
+        /// `YHIE`: interrupt on Y-axis high event
+        yhie, with_yhie, 3
+    );
+    bit!(
+        /// `YLIE`: interrupt on Y-axis low event
+        ylie, with_ylie, 2
+    );
+    bit!(
+        /// `XHIE`: interrupt on X-axis high event
+        xhie, with_xhie, 1
+    );
+    bit!(
+        /// `XLIE`: interrupt on X-axis low event
+        xlie, with_xlie, 0
+    );
+}
+
+register!(
+    /// `INT1_SRC`: interrupt 1 source
+    Int1Src
+);
+
+impl Int1Src {
+    bit!(
+        /// `IA`: one or more interrupts have been generated
+        ia, with_ia, 6
+    );
+    bit!(
+        /// `ZH`: Z-axis high event
+        zh, with_zh, 5
+    );
+    bit!(
+        /// `ZL`: Z-axis low event
+        zl, with_zl, 4
+    );
+    bit!(
+        /// `YH`: Y-axis high event
+        yh, with_yh, 3
+    );
+    bit!(
+        /// `YL`: Y-axis low event
+        yl, with_yl, 2
+    );
+    bit!(
+        /// `XH`: X-axis high event
+        xh, with_xh, 1
+    );
+    bit!(
+        /// `XL`: X-axis low event
+        xl, with_xl, 0
+    );
+}
+
+register!(
+    /// `LOW_ODR`: low data rate selection and software reset; L3GD20H only
+    LowOdr
+);
+
+impl LowOdr {
+    bit!(
+        /// `DRDY_HL`: DRDY/INT2 pin active low
+        drdy_hl, with_drdy_hl, 5
+    );
+    bit!(
+        /// `I2C_dis`: I2C interface disabled
+        i2c_dis, with_i2c_dis, 3
+    );
+    bit!(
+        /// `SW_RES`: software reset, cleared by the sensor when done
+        sw_res, with_sw_res, 2
+    );
+    bit!(
+        /// `Low_ODR`: low speed data rates selected
+        low_odr, with_low_odr, 0
+    );
+}
+
+register!(
+    /// `INT1_TSH_XH`: interrupt 1 X-axis threshold, high byte
+    Int1TshXh
+);
+
+impl Int1TshXh {
+    bit!(
+        /// `DCRM`: the duration counter decrements instead of resetting;
+        /// L3GD20H only
+        dcrm, with_dcrm, 7
+    );
+    field!(
+        /// `THSX`: high bits of the X-axis threshold
+        thsx, with_thsx, 0, 7
+    );
+}
+
+register!(
+    /// `INT1_DURATION`: interrupt 1 duration
+    Int1Duration
+);
+
+impl Int1Duration {
+    bit!(
+        /// `WAIT`: the interrupt request falls only after the duration has
+        /// elapsed again
+        wait, with_wait, 7
+    );
+    field!(
+        /// `D`: minimum duration of an event, in samples
+        d, with_d, 0, 7
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_address_round_trips() {
+        let mut count = 0;
+        for addr in 0..=0xff {
+            if let Ok(register) = Register::try_from(addr) {
+                assert_eq!(register.addr(), addr);
+                count += 1;
+            }
+        }
+        assert_eq!(count, 27);
+        assert_eq!(Register::try_from(0x10), Err(InvalidValue { value: 0x10 }));
+        assert_eq!(Register::try_from(0x3A), Err(InvalidValue { value: 0x3A }));
+    }
+
+    #[test]
+    fn setters_leave_the_other_bits_alone() {
+        let reg = CtrlReg1::from_bits(0xFF).with_bw(0b00).with_pd(false);
+        assert_eq!(reg.bits(), 0b1100_0111);
+        let reg = CtrlReg1::from_bits(0x00).with_bw(0b11).with_xen(true);
+        assert_eq!(reg.bits(), 0b0011_0001);
+        assert_eq!(reg.bw(), 0b11);
+        assert!(reg.xen() && !reg.yen());
+    }
+
+    #[test]
+    fn oversized_field_values_are_masked() {
+        assert_eq!(CtrlReg1::default().with_dr(0xFF).bits(), 0b1100_0000);
+        assert_eq!(FifoCtrl::default().with_wtm(0xFF).bits(), 0b0001_1111);
+        assert_eq!(Int1Duration::default().with_d(0xFF).bits(), 0x7F);
+        assert_eq!(Int1TshXh::default().with_thsx(0xFF).bits(), 0x7F);
+    }
+
+    #[test]
+    fn ctrl_reg1_round_trips() {
+        let axes = AxesEnable {
+            x: true,
+            y: false,
+            z: true,
+        };
+        let reg = CtrlReg1::default()
+            .with_odr(Odr::Hz380)
+            .with_bandwidth(Bandwidth::Medium)
+            .with_power(PowerMode::Normal)
+            .with_axes(axes);
+        assert_eq!(reg.bits(), 0b1001_1101);
+        assert_eq!(reg.odr(), Ok(Odr::Hz380));
+        assert_eq!(reg.bandwidth(), Ok(Bandwidth::Medium));
+        assert_eq!(reg.power(), PowerMode::Normal);
+        assert_eq!(reg.axes(), axes);
+        assert_eq!(
+            reg.with_power(PowerMode::PowerDown).power(),
+            PowerMode::PowerDown
+        );
+    }
+
+    #[test]
+    fn low_data_rates_only_set_dr() {
+        let reg = CtrlReg1::default().with_odr(Odr::Hz50);
+        assert_eq!(reg.dr(), 0b10);
+        assert_eq!(reg.odr_for(true), Odr::Hz50);
+        assert_eq!(reg.odr_for(false), Odr::Hz380);
+        assert_eq!(CtrlReg1::from_bits(0xC0).odr_for(true), Odr::Hz50);
+    }
+
+    #[test]
+    fn ctrl_reg4_scale() {
+        for &scale in &[Scale::Dps250, Scale::Dps500, Scale::Dps2000] {
+            let reg = CtrlReg4::from_bits(0x81).with_scale(scale);
+            assert_eq!(reg.scale(), Ok(scale));
+            assert_eq!(reg.bits() & !0x30, 0x81);
+        }
+        assert_eq!(
+            CtrlReg4::default().with_fs(0b10).scale(),
+            Ok(Scale::Dps2000)
+        );
+    }
+
+    #[test]
+    fn ctrl_reg5_data_path() {
+        for &path in &[
+            DataPath::Lpf1,
+            DataPath::Hpf,
+            DataPath::Lpf2,
+            DataPath::HpfLpf2,
+        ] {
+            let reg = CtrlReg5::from_bits(0xC0).with_data_path(path);
+            assert_eq!(reg.data_path(), path);
+            assert_eq!(reg.bits() & 0xC0, 0xC0);
+        }
+        let reg = CtrlReg5::default().with_out_sel(0b11);
+        assert_eq!(reg.data_path(), DataPath::Lpf2);
+        assert_eq!(reg.with_hpen(true).data_path(), DataPath::HpfLpf2);
+        let reg = CtrlReg5::default().with_out_sel(0b01);
+        assert_eq!(reg.data_path(), DataPath::Lpf1);
+        assert_eq!(
+            CtrlReg5::default().with_hpen(true).data_path(),
+            DataPath::Lpf1
+        );
+    }
+
+    #[test]
+    fn interrupt_duration_and_threshold_fields() {
+        let duration = Int1Duration::default().with_wait(true).with_d(0x2A);
+        assert_eq!(duration.bits(), 0xAA);
+        assert!(duration.wait());
+        assert_eq!(duration.d(), 0x2A);
+
+        let threshold = Int1TshXh::from_bits(0xFF).with_dcrm(false);
+        assert_eq!(threshold.bits(), 0x7F);
+        assert_eq!(threshold.thsx(), 0x7F);
+    }
+}
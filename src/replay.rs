@@ -0,0 +1,369 @@
+//! Replay of recorded SPI traffic
+//!
+//! `ReplaySpi` plays back a transaction log, e.g. one captured with a logic
+//! analyzer, as the SPI peripheral of the driver: every transaction the
+//! driver makes is checked against the next step of the log and answered
+//! with the recorded bytes. Use it with `NoCs` as the chip select to
+//! reproduce field failures on a host. Requires the `std` feature.
+//!
+//! # Log format
+//!
+//! One transaction per line, bytes in hexadecimal separated by spaces.
+//! Blank lines and everything after a `#` are ignored.
+//!
+//! - `W 20 0f`: a write of the bytes `20 0f`
+//! - `T a8 00 00 -> 00 34 12`: a transfer sending `a8 00 00` and receiving
+//!   `00 34 12`; both sides have the same length
+//! - `E`: the next transaction fails with `ReplayError::Injected`
+//!
+//! In the bytes sent by the driver, `xx` matches any byte.
+//!
+//! ```
+//! # use l3gd20::replay::{ReplayError, ReplaySpi};
+//! # use l3gd20::{I16x3, L3gd20};
+//! # fn main() -> Result<(), ReplayError> {
+//! let log = include_str!("../tests/replay/gyro-read.log");
+//! let mut spi = ReplaySpi::parse(log).expect("invalid log");
+//! let mut l3gd20 = L3gd20::new_hw_cs(&mut spi)?;
+//! assert_eq!(l3gd20.gyro()?, I16x3 { x: 0x1234, y: 0, z: -1 });
+//! drop(l3gd20);
+//! spi.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The logs in `tests/replay` are replayed by the unit tests of this
+//! module.
+
+use std::vec::Vec;
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+
+/// Error parsing a transaction log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line of the log, starting at 1
+    pub line: usize,
+}
+
+/// Error returned by `ReplaySpi` instead of the recorded answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The transaction doesn't match the log
+    Mismatch {
+        /// Index of the step in the log, starting at 0
+        step: usize,
+    },
+    /// The driver made more transactions than the log holds
+    Exhausted,
+    /// Error injected by an `E` line of the log
+    Injected,
+    /// Steps of the log were not replayed, see `ReplaySpi::finish`
+    Unused {
+        /// Number of steps left
+        steps: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Write(Vec<Option<u8>>),
+    Transfer(Vec<Option<u8>>, Vec<u8>),
+    Error,
+}
+
+/// SPI peripheral answering with a recorded transaction log
+#[derive(Debug, Clone)]
+pub struct ReplaySpi {
+    steps: Vec<Step>,
+    next: usize,
+}
+
+impl ReplaySpi {
+    /// Parse a transaction log, see the log format
+    pub fn parse(log: &str) -> Result<Self, ParseError> {
+        let mut steps = Vec::new();
+        for (i, line) in log.lines().enumerate() {
+            let error = ParseError { line: i + 1 };
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let kind = line.chars().next().unwrap_or(' ');
+            let rest = &line[kind.len_utf8()..];
+            let step = match kind {
+                'W' => Step::Write(pattern(rest).ok_or(error)?),
+                'T' => {
+                    let mut sides = rest.split("->");
+                    let sent = sides.next().and_then(pattern).ok_or(error)?;
+                    let received = sides.next().and_then(bytes).ok_or(error)?;
+                    if sides.next().is_some() || sent.len() != received.len() {
+                        return Err(error);
+                    }
+                    Step::Transfer(sent, received)
+                }
+                'E' if rest.trim().is_empty() => Step::Error,
+                _ => return Err(error),
+            };
+            steps.push(step);
+        }
+        Ok(ReplaySpi { steps, next: 0 })
+    }
+
+    /// Number of steps replayed so far
+    pub fn position(&self) -> usize {
+        self.next
+    }
+
+    /// Check that the whole log has been replayed
+    pub fn finish(&self) -> Result<(), ReplayError> {
+        match self.steps.len() - self.next {
+            0 => Ok(()),
+            steps => Err(ReplayError::Unused { steps }),
+        }
+    }
+
+    fn step(&mut self) -> Result<(usize, &Step), ReplayError> {
+        let index = self.next;
+        let step = self.steps.get(index).ok_or(ReplayError::Exhausted)?;
+        self.next += 1;
+        match step {
+            Step::Error => Err(ReplayError::Injected),
+            step => Ok((index, step)),
+        }
+    }
+}
+
+impl Transfer<u8> for ReplaySpi {
+    type Error = ReplayError;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], ReplayError> {
+        match self.step()? {
+            (_, Step::Transfer(sent, received)) if matches(sent, words) => {
+                words.copy_from_slice(received);
+                Ok(words)
+            }
+            (step, _) => Err(ReplayError::Mismatch { step }),
+        }
+    }
+}
+
+impl Write<u8> for ReplaySpi {
+    type Error = ReplayError;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), ReplayError> {
+        match self.step()? {
+            (_, Step::Write(sent)) if matches(sent, words) => Ok(()),
+            (step, _) => Err(ReplayError::Mismatch { step }),
+        }
+    }
+}
+
+impl Transfer<u8> for &mut ReplaySpi {
+    type Error = ReplayError;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], ReplayError> {
+        (**self).transfer(words)
+    }
+}
+
+impl Write<u8> for &mut ReplaySpi {
+    type Error = ReplayError;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), ReplayError> {
+        (**self).write(words)
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal_1::spi::Error for ReplayError {
+    fn kind(&self) -> embedded_hal_1::spi::ErrorKind {
+        embedded_hal_1::spi::ErrorKind::Other
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal_1::spi::ErrorType for ReplaySpi {
+    type Error = ReplayError;
+}
+
+/// With the `eh1` feature, each call is a step of the log, as with the
+/// embedded-hal 0.2 traits; `read` sends zeros
+#[cfg(feature = "eh1")]
+impl embedded_hal_1::spi::SpiBus for ReplaySpi {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), ReplayError> {
+        words.fill(0);
+        Transfer::transfer(self, words).map(|_| ())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), ReplayError> {
+        Write::write(self, words)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), ReplayError> {
+        let mut words = write.to_vec();
+        words.resize(read.len().max(write.len()), 0);
+        Transfer::transfer(self, &mut words)?;
+        read.copy_from_slice(&words[..read.len()]);
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), ReplayError> {
+        Transfer::transfer(self, words).map(|_| ())
+    }
+
+    fn flush(&mut self) -> Result<(), ReplayError> {
+        Ok(())
+    }
+}
+
+/// `true` if `words` match the recorded `sent` bytes
+fn matches(sent: &[Option<u8>], words: &[u8]) -> bool {
+    sent.len() == words.len()
+        && sent
+            .iter()
+            .zip(words)
+            .all(|(s, &w)| s.map_or(true, |s| s == w))
+}
+
+/// Bytes sent by the driver; `xx` is any byte
+fn pattern(s: &str) -> Option<Vec<Option<u8>>> {
+    s.split_whitespace()
+        .map(|b| match b {
+            "xx" => Some(None),
+            b => u8::from_str_radix(b, 16).ok().map(Some),
+        })
+        .collect()
+}
+
+fn bytes(s: &str) -> Option<Vec<u8>> {
+    s.split_whitespace()
+        .map(|b| u8::from_str_radix(b, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, I16x3, L3gd20, NoCs, RetryPolicy, Variant};
+
+    const GYRO_READ: &str = include_str!("../tests/replay/gyro-read.log");
+    const BUS_ERROR: &str = include_str!("../tests/replay/bus-error.log");
+    const OVERRUN: &str = include_str!("../tests/replay/overrun.log");
+    const FLOATING_BUS: &str = include_str!("../tests/replay/floating-bus.log");
+
+    #[test]
+    fn parse_rejects_malformed_lines() {
+        let line = |log| ReplaySpi::parse(log).unwrap_err().line;
+        assert_eq!(line("W 20\nX 00"), 2);
+        assert_eq!(line("T 8f 00 -> d4"), 1);
+        assert_eq!(line("T 8f 00 -> 00 d4 -> 00 d4"), 1);
+        assert_eq!(line("W 2g"), 1);
+        assert_eq!(line("E 00"), 1);
+        ReplaySpi::parse("# only a comment\n\n")
+            .unwrap()
+            .finish()
+            .unwrap();
+    }
+
+    #[test]
+    fn gyro_read_log() {
+        let mut spi = ReplaySpi::parse(GYRO_READ).unwrap();
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(
+                l3gd20.gyro().unwrap(),
+                I16x3 {
+                    x: 0x1234,
+                    y: 0,
+                    z: -1
+                }
+            );
+        }
+        assert_eq!(spi.position(), 4);
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn injected_error_is_retried() {
+        let mut spi = ReplaySpi::parse(BUS_ERROR).unwrap();
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_retry(RetryPolicy { attempts: 1 });
+            assert_eq!(l3gd20.gyro().unwrap(), I16x3 { x: 1, y: 2, z: 3 });
+            assert_eq!(l3gd20.retry_count(), 1);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn injected_error_is_returned() {
+        let mut spi = ReplaySpi::parse(BUS_ERROR).unwrap();
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(l3gd20.gyro(), Err(ReplayError::Injected));
+            assert_eq!(l3gd20.gyro().unwrap(), I16x3 { x: 1, y: 2, z: 3 });
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn overrun_log() {
+        let mut spi = ReplaySpi::parse(OVERRUN).unwrap();
+        {
+            let mut l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            assert_eq!(
+                l3gd20.gyro_strict(),
+                Err(Error::Overrun {
+                    axes: [false, true, false]
+                })
+            );
+            assert_eq!(l3gd20.missed_samples(), 1);
+            assert_eq!(l3gd20.gyro_strict().unwrap(), I16x3 { x: 4, y: 5, z: 6 });
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn floating_bus_log() {
+        let mut spi = ReplaySpi::parse(FLOATING_BUS).unwrap();
+        assert_eq!(
+            L3gd20::new_l3gd20(&mut spi, NoCs).map(|_| ()),
+            Err(Error::WrongDevice {
+                expected: Variant::L3gd20,
+                who_am_i: 0xff
+            })
+        );
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn mismatch_and_leftovers_are_reported() {
+        let mut spi = ReplaySpi::parse("W 20 0f\nT 8f 00 -> 00 d4").unwrap();
+        assert_eq!(
+            spi.write(&[0x20, 0x0e]),
+            Err(ReplayError::Mismatch { step: 0 })
+        );
+        assert_eq!(spi.finish(), Err(ReplayError::Unused { steps: 1 }));
+        let mut buf = [0x8f, 0];
+        assert_eq!(spi.transfer(&mut buf).unwrap(), [0x00, 0xd4]);
+        assert_eq!(spi.write(&[0]), Err(ReplayError::Exhausted));
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn wildcards_match_any_byte() {
+        let mut spi = ReplaySpi::parse("W 20 xx\nW 20 xx").unwrap();
+        spi.write(&[0x20, 0x00]).unwrap();
+        spi.write(&[0x20, 0xff]).unwrap();
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn unreplayed_steps_are_reported() {
+        let mut spi = ReplaySpi::parse(GYRO_READ).unwrap();
+        L3gd20::new(&mut spi, NoCs).unwrap();
+        assert_eq!(spi.finish(), Err(ReplayError::Unused { steps: 1 }));
+    }
+}
@@ -0,0 +1,182 @@
+//! Type-state full scale
+//!
+//! `scale::L3gd20` wraps the driver and fixes the Full Scale Selection in its
+//! type, so measurements are always converted with the scale the sensor is
+//! actually programmed with, and without reading it back. The plain
+//! `crate::L3gd20` API is not affected.
+//!
+//! ``` ignore
+//! let l3gd20 = scale::L3gd20::<_, _, Dps250>::new(l3gd20)?;
+//! let mut l3gd20 = l3gd20.with_scale::<Dps2000>()?;
+//! let mdps = l3gd20.gyro_mdps()?;
+//! ```
+
+use core::marker::PhantomData;
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::{AngularRate, Error, I16x3, I32x3, Scale};
+
+/// Full scale known at compile time
+///
+/// Implemented by `Dps250`, `Dps500` and `Dps2000`.
+pub trait FullScale: private::Sealed {
+    /// The corresponding runtime `Scale`
+    const SCALE: Scale;
+    /// Sensitivity in degrees per second per LSB
+    #[cfg(feature = "float")]
+    const DPS_PER_COUNT: f32;
+}
+
+/// ±250 dps full scale
+pub enum Dps250 {}
+
+/// ±500 dps full scale
+pub enum Dps500 {}
+
+/// ±2000 dps full scale
+pub enum Dps2000 {}
+
+impl FullScale for Dps250 {
+    const SCALE: Scale = Scale::Dps250;
+    #[cfg(feature = "float")]
+    const DPS_PER_COUNT: f32 = crate::SENSITIVITY_DPS250;
+}
+
+impl FullScale for Dps500 {
+    const SCALE: Scale = Scale::Dps500;
+    #[cfg(feature = "float")]
+    const DPS_PER_COUNT: f32 = crate::SENSITIVITY_DPS500;
+}
+
+impl FullScale for Dps2000 {
+    const SCALE: Scale = Scale::Dps2000;
+    #[cfg(feature = "float")]
+    const DPS_PER_COUNT: f32 = crate::SENSITIVITY_DPS2000;
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::Dps250 {}
+    impl Sealed for super::Dps500 {}
+    impl Sealed for super::Dps2000 {}
+}
+
+/// L3GD20 driver whose Full Scale Selection is fixed by `S`
+pub struct L3gd20<SPI, CS, S> {
+    l3gd20: crate::L3gd20<SPI, CS>,
+    _scale: PhantomData<S>,
+}
+
+impl<SPI, CS, E, S> L3gd20<SPI, CS, S>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+    S: FullScale,
+{
+    /// Program the full scale `S` and track it from now on
+    pub fn new(mut l3gd20: crate::L3gd20<SPI, CS>) -> Result<Self, Error<E>> {
+        l3gd20.set_scale(S::SCALE)?;
+        Ok(L3gd20 {
+            l3gd20,
+            _scale: PhantomData,
+        })
+    }
+
+    /// Program the full scale `T`, changing the type of the driver
+    ///
+    /// The scale is changed through `crate::L3gd20::set_scale`, so the stored
+    /// bias is rescaled or cleared according to the `BiasPolicy`.
+    pub fn with_scale<T: FullScale>(self) -> Result<L3gd20<SPI, CS, T>, Error<E>> {
+        L3gd20::new(self.l3gd20)
+    }
+
+    /// Release the underlying driver
+    pub fn release(self) -> crate::L3gd20<SPI, CS> {
+        self.l3gd20
+    }
+
+    /// The full scale the sensor is programmed with
+    pub const fn scale(&self) -> Scale {
+        S::SCALE
+    }
+
+    /// See `crate::L3gd20::gyro`
+    pub fn gyro(&mut self) -> Result<I16x3, E> {
+        self.l3gd20.gyro()
+    }
+
+    /// Gyroscope measurements converted to milli-degrees per second
+    ///
+    /// Same as `crate::L3gd20::gyro_mdps`, including the `AxisCorrection`,
+    /// with the scale `S`.
+    pub fn gyro_mdps(&mut self) -> Result<I32x3, E> {
+        self.l3gd20.gyro_mdps_at(S::SCALE)
+    }
+
+    /// Gyroscope measurements as an `AngularRate`
+    pub fn gyro_rate(&mut self) -> Result<AngularRate, E> {
+        Ok(AngularRate::new(self.gyro()?, S::SCALE))
+    }
+
+    /// Gyroscope measurements converted to degrees per second
+    ///
+    /// Same as `crate::L3gd20::gyro_dps`, including the `BiasModel` and the
+    /// `AxisCorrection`, with the scale `S`.
+    #[cfg(feature = "float")]
+    pub fn gyro_dps(&mut self) -> Result<F32x3, E> {
+        self.l3gd20.gyro_dps_at(S::SCALE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::replay;
+    use crate::Q15_ONE;
+
+    #[test]
+    fn typed_reads_share_the_adjustments_of_the_driver() {
+        let mut spi = replay(
+            "
+            W 23 00             # CTRL_REG4: 250 dps
+            T e8 00 00 00 00 00 00 -> 00 b0 04 c8 00 c8 00
+            W 23 30             # CTRL_REG4: 2000 dps
+            ",
+        );
+        {
+            let mut l3gd20 = crate::L3gd20::new_hw_cs(&mut spi).unwrap();
+            l3gd20.set_bias(I16x3 {
+                x: 200,
+                y: 200,
+                z: 200,
+            });
+            // swap X and Y
+            let swap = [[0, Q15_ONE, 0], [Q15_ONE, 0, 0], [0, 0, Q15_ONE]];
+            l3gd20.set_calibration_q15(swap, I32x3::default());
+
+            let mut l3gd20 = L3gd20::<_, _, Dps250>::new(l3gd20).unwrap();
+            let mdps = I32x3 {
+                x: 0,
+                y: 8750,
+                z: 0,
+            };
+            assert_eq!(l3gd20.gyro_mdps(), Ok(mdps));
+
+            let l3gd20 = l3gd20.with_scale::<Dps2000>().unwrap();
+            assert_eq!(
+                l3gd20.release().bias(),
+                I16x3 {
+                    x: 25,
+                    y: 25,
+                    z: 25
+                }
+            );
+        }
+        spi.finish().unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+//! Driver shared between execution contexts
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::{AngularRate, Error, I16x3, I32x3, L3gd20, Measurements, Status};
+
+/// Driver that can be shared between execution contexts (e.g. tasks or
+/// interrupt handlers)
+///
+/// Every method runs in a critical section, see the `critical-section`
+/// crate, so calls from different contexts never interleave; `with` groups
+/// several operations that must not be interleaved either (e.g. changing
+/// the scale then reading).
+///
+/// Interrupts (on single core targets) are masked for the whole duration of
+/// the SPI transactions, which delays every other interrupt handler, however
+/// high its priority. Keep the work done in `with` short; contexts that only
+/// need to be serialized with each other may be better served by a mutex
+/// of their executor.
+pub struct SharedL3gd20<SPI, CS> {
+    l3gd20: Mutex<RefCell<L3gd20<SPI, CS>>>,
+}
+
+impl<SPI, CS, E> SharedL3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Wrap a driver
+    pub const fn new(l3gd20: L3gd20<SPI, CS>) -> Self {
+        SharedL3gd20 {
+            l3gd20: Mutex::new(RefCell::new(l3gd20)),
+        }
+    }
+
+    /// Release the driver
+    pub fn into_inner(self) -> L3gd20<SPI, CS> {
+        self.l3gd20.into_inner().into_inner()
+    }
+
+    /// Run `f` with exclusive access to the driver, in a single critical
+    /// section
+    ///
+    /// Panics if called from within `f`.
+    pub fn with<R>(&self, f: impl FnOnce(&mut L3gd20<SPI, CS>) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.l3gd20.borrow_ref_mut(cs)))
+    }
+
+    /// See `L3gd20::all`
+    pub fn all(&self) -> Result<Measurements, E> {
+        self.with(|l3gd20| l3gd20.all())
+    }
+
+    /// See `L3gd20::gyro`
+    pub fn gyro(&self) -> Result<I16x3, E> {
+        self.with(|l3gd20| l3gd20.gyro())
+    }
+
+    /// See `L3gd20::gyro_mdps`
+    pub fn gyro_mdps(&self) -> Result<I32x3, Error<E>> {
+        self.with(|l3gd20| l3gd20.gyro_mdps())
+    }
+
+    /// See `L3gd20::gyro_rate`
+    pub fn gyro_rate(&self) -> Result<AngularRate, Error<E>> {
+        self.with(|l3gd20| l3gd20.gyro_rate())
+    }
+
+    /// See `L3gd20::gyro_dps`
+    #[cfg(feature = "float")]
+    pub fn gyro_dps(&self) -> Result<F32x3, Error<E>> {
+        self.with(|l3gd20| l3gd20.gyro_dps())
+    }
+
+    /// See `L3gd20::temp`
+    pub fn temp(&self) -> Result<i8, E> {
+        self.with(|l3gd20| l3gd20.temp())
+    }
+
+    /// See `L3gd20::status`
+    pub fn status(&self) -> Result<Status, E> {
+        self.with(|l3gd20| l3gd20.status())
+    }
+}
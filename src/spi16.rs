@@ -0,0 +1,502 @@
+//! SPI transfers in 16-bit words
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::interface::{CsInterface, SpiInterface};
+use crate::registers::Register;
+use crate::{CsPolarity, L3gd20, NoCs, MULTI, READ};
+
+/// Number of words sent per call to the wrapped peripheral
+const CHUNK: usize = 32;
+
+/// SPI peripheral with 16-bit frames used through the 8-bit SPI traits
+///
+/// Each word carries two bytes of the transaction, the first one in the
+/// most significant half, so the sensor sees the same bit stream as with
+/// 8-bit frames. `Spi16` drives the (active low) chip select itself, so it
+/// can frame transactions of odd length:
+///
+/// - a burst read is extended by one register, preferably the one before the
+///   first register read, whose value is dropped; registers that change when
+///   read (`INT1_SRC`, and the output registers that pop a sample from the
+///   FIFO) are never added
+/// - other transactions, e.g. writes, are split in two: all but the last
+///   register, then the last register on its own
+///
+/// Only a write of the command byte alone, or a read of registers that
+/// change when read that can't be extended, fails with
+/// `Spi16Error::OddLength`, before anything is sent.
+///
+/// A single byte write, which is how the driver sends the command byte of a
+/// burst, is held back and packed with the first byte of the next call, so
+/// a transaction is either one call or a single byte write followed by one
+/// call.
+pub struct Spi16<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+    pending: Option<u8>,
+}
+
+/// Error of a `Spi16` transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spi16Error<E> {
+    /// Error of the wrapped peripheral
+    Spi(E),
+    /// The transaction has an odd number of bytes and can be neither
+    /// extended nor split without side effects
+    OddLength,
+}
+
+impl<E> From<E> for Spi16Error<E> {
+    fn from(e: E) -> Self {
+        Spi16Error::Spi(e)
+    }
+}
+
+/// Bytes of a call, after the pending byte
+enum Bytes<'a> {
+    Write(&'a [u8]),
+    Transfer(&'a mut [u8]),
+}
+
+impl Bytes<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Bytes::Write(b) => b.len(),
+            Bytes::Transfer(b) => b.len(),
+        }
+    }
+
+    fn get(&self, i: usize) -> u8 {
+        match self {
+            Bytes::Write(b) => b[i],
+            Bytes::Transfer(b) => b[i],
+        }
+    }
+}
+
+impl<SPI, CS> Spi16<SPI, CS> {
+    /// Wrap `spi`, which must be configured for 16-bit frames, and the NCS
+    /// pin `cs`
+    ///
+    /// With a chip select managed by the peripheral, pass `NoCs`; the
+    /// peripheral must then deassert NCS between calls, as a transaction of
+    /// odd length is sent in two calls.
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        Spi16 {
+            spi,
+            cs,
+            pending: None,
+        }
+    }
+
+    /// Release the wrapped peripheral and NCS pin
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+}
+
+impl<SPI, CS, E> Spi16<SPI, CS>
+where
+    SPI: Transfer<u16, Error = E> + Write<u16, Error = E>,
+    CS: OutputPin,
+{
+    /// Send the pending byte, if any, followed by `bytes`, as one
+    /// transaction or, if it has an odd length, as described on `Spi16`
+    fn run(&mut self, bytes: Bytes) -> Result<(), Spi16Error<E>> {
+        let pending = self.pending.take();
+        let total = pending.is_some() as usize + bytes.len();
+        if total & 1 == 0 {
+            return self.framed(pending, bytes, None);
+        }
+
+        let command = pending.unwrap_or_else(|| bytes.get(0));
+        let registers = total - 1;
+        let read = matches!(bytes, Bytes::Transfer(_));
+        match extension(command, registers) {
+            Some(extension) if read => return self.framed(pending, bytes, Some(extension)),
+            _ if registers == 0 || (read && any_clears(command, registers)) => {
+                return Err(Spi16Error::OddLength)
+            }
+            _ => {}
+        }
+
+        // The last register on its own; without auto-increment, every byte
+        // goes to the first register
+        let offset = if command & MULTI != 0 {
+            registers - 1
+        } else {
+            0
+        };
+        let last = (command & READ) | ((command & !(READ | MULTI)) + offset as u8);
+        let split = bytes.len() - 1;
+        match bytes {
+            Bytes::Write(b) => {
+                self.framed(pending, Bytes::Write(&b[..split]), None)?;
+                self.framed(Some(last), Bytes::Write(&b[split..]), None)
+            }
+            Bytes::Transfer(b) => {
+                let (first, rest) = b.split_at_mut(split);
+                self.framed(pending, Bytes::Transfer(first), None)?;
+                self.framed(Some(last), Bytes::Transfer(rest), None)
+            }
+        }
+    }
+
+    /// Send `pending`, if any, followed by `bytes` and the `extension`, if
+    /// any, with NCS asserted, `CHUNK` words at a time
+    fn framed(
+        &mut self,
+        pending: Option<u8>,
+        bytes: Bytes,
+        extension: Option<Extension>,
+    ) -> Result<(), Spi16Error<E>> {
+        let _ = self.cs.set_low();
+        let result = self.words(pending, bytes, extension);
+        let _ = self.cs.set_high();
+        result
+    }
+
+    fn words(
+        &mut self,
+        pending: Option<u8>,
+        mut bytes: Bytes,
+        extension: Option<Extension>,
+    ) -> Result<(), Spi16Error<E>> {
+        // The transaction is `head`, then `bytes[skip..]`, then a padding
+        // byte if `pad` is set
+        let mut head = [pending.unwrap_or(0), 0];
+        let mut head_len = pending.is_some() as usize;
+        let mut skip = 0;
+        let mut pad = false;
+        match extension {
+            Some(Extension::Before) => {
+                let command = pending.unwrap_or_else(|| bytes.get(0));
+                head = [command - 1, 0];
+                skip = 1 - head_len;
+                head_len = 2;
+            }
+            Some(Extension::After) => pad = true,
+            None => {}
+        }
+        let len = bytes.len();
+        let total = head_len + len - skip + pad as usize;
+        // index in `bytes` of byte `k` of the transaction, if any
+        let index = |k: usize| match k.checked_sub(head_len) {
+            Some(i) => Some(i + skip).filter(|&i| i < len),
+            None if k == 0 && skip == 1 => Some(0),
+            None => None,
+        };
+        let byte = |bytes: &Bytes, k: usize| match k.checked_sub(head_len) {
+            Some(_) => index(k).map_or(0, |i| bytes.get(i)),
+            None => head[k],
+        };
+
+        let mut start = 0;
+        while start < total {
+            let end = total.min(start + 2 * CHUNK);
+            let mut words = [0u16; CHUNK];
+            for k in start..end {
+                let shift = if (k - start) % 2 == 0 { 8 } else { 0 };
+                words[(k - start) / 2] |= u16::from(byte(&bytes, k)) << shift;
+            }
+            let words = &mut words[..(end - start) / 2];
+
+            match &mut bytes {
+                Bytes::Write(_) => self.spi.write(words)?,
+                Bytes::Transfer(data) => {
+                    self.spi.transfer(words)?;
+                    for k in start..end {
+                        if let Some(i) = index(k) {
+                            let shift = if (k - start) % 2 == 0 { 8 } else { 0 };
+                            data[i] = (words[(k - start) / 2] >> shift) as u8;
+                        }
+                    }
+                }
+            }
+            start = end;
+        }
+        Ok(())
+    }
+}
+
+/// Register added to make a read of even length
+enum Extension {
+    /// The one before the first register read
+    Before,
+    /// The one after the last register read
+    After,
+}
+
+/// `true` for the output registers
+fn output(addr: usize) -> bool {
+    (Register::OUT_X_L as usize..=Register::OUT_Z_H as usize).contains(&addr)
+}
+
+/// `true` for the registers that change when read
+fn clears(addr: usize) -> bool {
+    addr == Register::INT1_SRC as usize || output(addr)
+}
+
+/// `true` if one of the `registers` registers accessed by `command` changes
+/// when read
+fn any_clears(command: u8, registers: usize) -> bool {
+    let first = usize::from(command & !(READ | MULTI));
+    if command & MULTI == 0 {
+        return clears(first);
+    }
+    (first..first + registers).any(clears)
+}
+
+/// Where a register can be added to the read of `registers` registers
+/// started by `command` without side effects
+fn extension(command: u8, registers: usize) -> Option<Extension> {
+    if command & READ == 0 {
+        return None;
+    }
+    let first = usize::from(command & !(READ | MULTI));
+    let multi = command & MULTI != 0;
+
+    // reading an output register before the others of the same sample
+    // doesn't pop it
+    let before = first.wrapping_sub(1);
+    if multi && first > 0 && (!clears(before) || (output(before) && output(first))) {
+        return Some(Extension::Before);
+    }
+    // past `OUT_Z_H` the address wraps around to `OUT_X_L` if the FIFO is
+    // enabled
+    let after = if multi { first + registers } else { first };
+    let wraps = multi && after == Register::OUT_Z_H as usize + 1;
+    if after <= Register::INT1_DURATION as usize && !clears(after) && !wraps {
+        return Some(Extension::After);
+    }
+    None
+}
+
+impl<SPI, CS, E> Transfer<u8> for Spi16<SPI, CS>
+where
+    SPI: Transfer<u16, Error = E> + Write<u16, Error = E>,
+    CS: OutputPin,
+{
+    type Error = Spi16Error<E>;
+
+    fn transfer<'w>(&mut self, bytes: &'w mut [u8]) -> Result<&'w [u8], Spi16Error<E>> {
+        self.run(Bytes::Transfer(bytes))?;
+        Ok(bytes)
+    }
+}
+
+impl<SPI, CS, E> Write<u8> for Spi16<SPI, CS>
+where
+    SPI: Transfer<u16, Error = E> + Write<u16, Error = E>,
+    CS: OutputPin,
+{
+    type Error = Spi16Error<E>;
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Spi16Error<E>> {
+        if bytes.len() == 1 && self.pending.is_none() {
+            self.pending = Some(bytes[0]);
+            return Ok(());
+        }
+        self.run(Bytes::Write(bytes))
+    }
+}
+
+impl<SPI, CS, E> L3gd20<Spi16<SPI, CS>, NoCs>
+where
+    SPI: Transfer<u16, Error = E> + Write<u16, Error = E>,
+    CS: OutputPin,
+{
+    /// Creates a new driver from a SPI peripheral configured for 16-bit
+    /// frames and a NCS pin
+    ///
+    /// Same as `new`, including the peripherals taken; the NCS pin is
+    /// driven by the `Spi16` transport, see there for how the transactions
+    /// are packed.
+    pub fn new_u16<S, P>(spi: S, cs: P) -> Result<Self, Spi16Error<E>>
+    where
+        S: SpiInterface<u16, Spi = SPI>,
+        P: CsInterface<Cs = CS>,
+    {
+        let spi = Spi16::new(spi.into_spi(), cs.into_cs());
+        L3gd20::connect(spi, NoCs, CsPolarity::ActiveLow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::testing::{Bus16, Bus8, Cs, Sensor};
+    use crate::{Axis, I16x3, Scale};
+
+    #[test]
+    fn threshold_writes_leave_the_next_register_alone() {
+        let sensor = Sensor::l3gd20();
+        let mut l3gd20 = L3gd20::new_u16(Bus16(sensor.clone()), Cs(sensor.clone())).unwrap();
+        {
+            let mut sensor = sensor.borrow_mut();
+            sensor.regs[0x34..0x36].copy_from_slice(&[0x12, 0x34]); // INT1_TSH_Y*
+            sensor.regs[0x38] = 0x05; // INT1_DURATION
+        }
+
+        l3gd20.set_interrupt_threshold(Axis::X, 0x1abc).unwrap();
+        assert_eq!(sensor.borrow().regs[0x32..0x36], [0x1a, 0xbc, 0x12, 0x34]);
+
+        // 100 dps at 250 dps: 11429 counts
+        l3gd20
+            .set_interrupt_threshold_mdps(Axis::Z, 100_000)
+            .unwrap();
+        assert_eq!(sensor.borrow().regs[0x36..0x39], [0x2c, 0xa5, 0x05]);
+        // converted to 5714 counts
+        l3gd20.set_scale(Scale::Dps500).unwrap();
+        assert_eq!(sensor.borrow().regs[0x36..0x39], [0x16, 0x52, 0x05]);
+        assert!(sensor.borrow().writes.iter().all(|&(addr, _)| addr != 0x38));
+    }
+
+    #[test]
+    fn odd_reads_are_extended_without_side_effects() {
+        let sensor = Sensor::l3gd20();
+        let mut l3gd20 = L3gd20::new_u16(Bus16(sensor.clone()), Cs(sensor.clone())).unwrap();
+        {
+            let mut sensor = sensor.borrow_mut();
+            sensor.regs[0x26..0x2e].copy_from_slice(&[25, 0x0f, 1, 0, 2, 0, 0xfd, 0xff]);
+            sensor.reads.clear();
+        }
+
+        // OUT_X_L..OUT_Z_H, starting at STATUS_REG
+        assert_eq!(l3gd20.gyro().unwrap(), I16x3 { x: 1, y: 2, z: -3 });
+        let reads: Vec<_> = (0x27..=0x2d).collect();
+        assert_eq!(sensor.borrow_mut().reads.split_off(0), reads);
+
+        // OUT_TEMP..OUT_Z_H, starting at REFERENCE
+        let all = l3gd20.all().unwrap();
+        assert_eq!((all.gyro, all.temp), (I16x3 { x: 1, y: 2, z: -3 }, 25));
+        let reads: Vec<_> = (0x25..=0x2d).collect();
+        assert_eq!(sensor.borrow_mut().reads.split_off(0), reads);
+    }
+
+    #[test]
+    fn odd_writes_are_split() {
+        let sensor = Sensor::l3gd20();
+        let mut spi = Spi16::new(Bus16(sensor.clone()), Cs(sensor.clone()));
+        // INT1_TSH_XH.., then INT1_TSH_XL
+        spi.write(&[0x72, 0x01, 0x02]).unwrap();
+        spi.write(&[0x72]).unwrap();
+        spi.write(&[0x03, 0x04]).unwrap();
+        // a single register is repeated
+        spi.write(&[0x32, 0x05, 0x06]).unwrap();
+        assert_eq!(
+            take_words(&sensor),
+            [
+                [0x72, 0x01],
+                [0x33, 0x02],
+                [0x72, 0x03],
+                [0x33, 0x04],
+                [0x32, 0x05],
+                [0x32, 0x06],
+            ]
+        );
+        assert_eq!(sensor.borrow().regs[0x32..0x34], [0x06, 0x04]);
+
+        assert_eq!(spi.write(&[0x72]), Ok(()));
+        assert_eq!(spi.write(&[]), Err(Spi16Error::OddLength));
+        assert_eq!(spi.transfer(&mut [0x20]), Err(Spi16Error::OddLength));
+        assert!(take_words(&sensor).is_empty());
+    }
+
+    /// Transactions received by `sensor` since the last call
+    fn take(sensor: &Rc<RefCell<Sensor>>) -> Vec<Vec<u8>> {
+        sensor.borrow_mut().transactions.split_off(0)
+    }
+
+    /// Transactions received by `sensor` through a `Bus16` since the last
+    /// call, after checking they match the words received
+    fn take_words(sensor: &Rc<RefCell<Sensor>>) -> Vec<Vec<u8>> {
+        let transactions = take(sensor);
+        let words = sensor.borrow_mut().words.split_off(0);
+        let bytes: Vec<_> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+        assert_eq!(bytes, transactions.concat());
+        transactions
+    }
+
+    #[test]
+    fn words_carry_the_transactions_of_the_byte_driver() {
+        let bytes = Sensor::l3gd20();
+        let words = Sensor::l3gd20();
+        for sensor in [&bytes, &words].iter() {
+            let mut sensor = sensor.borrow_mut();
+            sensor.regs[0x26..0x2e].copy_from_slice(&[25, 0x0f, 1, 0, 2, 0, 0xfd, 0xff]);
+        }
+        let mut reference = L3gd20::new(Bus8(bytes.clone()), Cs(bytes.clone())).unwrap();
+        let mut l3gd20 = L3gd20::new_u16(Bus16(words.clone()), Cs(words.clone())).unwrap();
+        assert_eq!(take_words(&words), take(&bytes));
+
+        // OUT_X_L..OUT_Z_H, and STATUS_REG before
+        assert_eq!(l3gd20.gyro().unwrap(), reference.gyro().unwrap());
+        assert_eq!(take(&bytes), [[0xe8, 0, 0, 0, 0, 0, 0]]);
+        assert_eq!(take_words(&words), [[0xe7, 0, 0, 0, 0, 0, 0, 0]]);
+
+        // OUT_TEMP..OUT_Z_H, and REFERENCE before
+        assert_eq!(l3gd20.all().unwrap(), reference.all().unwrap());
+        assert_eq!(take(&bytes), [[0xe6, 0, 0, 0, 0, 0, 0, 0, 0]]);
+        assert_eq!(take_words(&words), [[0xe5, 0, 0, 0, 0, 0, 0, 0, 0, 0]]);
+
+        // INT1_TSH_XH, then INT1_TSH_XH..INT1_TSH_XL, split
+        l3gd20.set_interrupt_threshold(Axis::X, 0x1abc).unwrap();
+        reference.set_interrupt_threshold(Axis::X, 0x1abc).unwrap();
+        assert_eq!(take(&bytes), [&[0xb2, 0][..], &[0x72, 0x1a, 0xbc]]);
+        assert_eq!(take_words(&words), [[0xb2, 0], [0x72, 0x1a], [0x33, 0xbc]]);
+
+        // CTRL_REG4, and the thresholds converted to the new scale
+        l3gd20
+            .set_interrupt_threshold_mdps(Axis::Z, 100_000)
+            .unwrap();
+        reference
+            .set_interrupt_threshold_mdps(Axis::Z, 100_000)
+            .unwrap();
+        l3gd20.set_scale(Scale::Dps500).unwrap();
+        reference.set_scale(Scale::Dps500).unwrap();
+        let transactions = take(&bytes);
+        assert!(transactions.contains(&std::vec![0x23, 0x10]));
+        let split: Vec<Vec<u8>> = transactions
+            .iter()
+            .flat_map(|t| match t.len() % 2 {
+                0 => std::vec![t.clone()],
+                _ => {
+                    let last = (t[0] & READ) | ((t[0] & !(READ | MULTI)) + (t.len() - 2) as u8);
+                    std::vec![t[..t.len() - 1].to_vec(), std::vec![last, t[t.len() - 1]]]
+                }
+            })
+            .collect();
+        assert_eq!(take_words(&words), split);
+        assert_eq!(bytes.borrow().regs, words.borrow().regs);
+    }
+
+    #[test]
+    fn reads_are_extended_away_from_side_effects() {
+        let before = |command, len| matches!(extension(command, len), Some(Extension::Before));
+        let after = |command, len| matches!(extension(command, len), Some(Extension::After));
+
+        // STATUS_REG before OUT_X_L..OUT_Z_H
+        assert!(before(0xe8, 6));
+        // OUT_Y_H before OUT_Z_L, of the same sample
+        assert!(before(0xec, 2));
+        // not INT1_SRC before INT1_TSH_XH
+        assert!(after(0xf2, 2));
+        // not OUT_Z_H before FIFO_CTRL_REG
+        assert!(after(0xee, 2));
+        // neither INT1_SRC nor the register after INT1_DURATION
+        assert!(extension(0xf2, 7).is_none());
+        // a single register read repeats the register
+        assert!(after(0xa0, 2));
+        assert!(extension(0xb1, 2).is_none());
+        assert!(extension(0xa8, 2).is_none());
+        // writes
+        assert!(extension(0x72, 2).is_none());
+    }
+}
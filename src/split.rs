@@ -0,0 +1,214 @@
+//! Separate handles for configuring the sensor and reading measurements
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+#[cfg(feature = "float")]
+use crate::F32x3;
+use crate::{
+    AngularRate, AxisMapping, Bandwidth, Config, ConfigMismatch, Error, I16x3, I32x3, L3gd20,
+    Measurements, Odr, Scale, SharedL3gd20, Status,
+};
+
+/// Handle exposing the register-writing half of the driver
+///
+/// Created by `SharedL3gd20::split`.
+pub struct ConfigHandle<'a, SPI, CS> {
+    driver: &'a SharedL3gd20<SPI, CS>,
+}
+
+/// Handle exposing the data path half of the driver
+///
+/// Created by `SharedL3gd20::split`.
+pub struct Reader<'a, SPI, CS> {
+    driver: &'a SharedL3gd20<SPI, CS>,
+}
+
+impl<SPI, CS, E> SharedL3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Split a shared driver into a `ConfigHandle` and a `Reader`
+    ///
+    /// Both handles access the same driver, so they always agree on its
+    /// state (e.g. the cached `Scale`). Every method runs in a critical
+    /// section, as those of `SharedL3gd20`, so the handles can be used from
+    /// different execution contexts (e.g. a task and an interrupt handler)
+    /// without their calls interleaving.
+    pub fn split(&self) -> (ConfigHandle<'_, SPI, CS>, Reader<'_, SPI, CS>) {
+        (ConfigHandle { driver: self }, Reader { driver: self })
+    }
+}
+
+impl<'a, SPI, CS, E> ConfigHandle<'a, SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    fn with<R>(&mut self, f: impl FnOnce(&mut L3gd20<SPI, CS>) -> R) -> R {
+        self.driver.with(f)
+    }
+
+    /// See `L3gd20::apply_config`
+    pub fn apply_config(&mut self, config: &Config) -> Result<(), Error<E>> {
+        self.with(|l3gd20| l3gd20.apply_config(config).map(|_| ()))
+    }
+
+    /// See `L3gd20::verify_config`
+    pub fn verify_config(&mut self, expected: &Config) -> Result<(), Error<E>> {
+        self.with(|l3gd20| l3gd20.verify_config(expected))
+    }
+
+    /// See `L3gd20::config_diff`
+    pub fn config_diff(&mut self, expected: &Config) -> Result<Option<ConfigMismatch>, E> {
+        self.with(|l3gd20| l3gd20.config_diff(expected))
+    }
+
+    /// See `L3gd20::odr`
+    pub fn odr(&mut self) -> Result<Odr, Error<E>> {
+        self.with(|l3gd20| l3gd20.odr())
+    }
+
+    /// See `L3gd20::set_odr`
+    pub fn set_odr(&mut self, odr: Odr) -> Result<(), Error<E>> {
+        self.with(|l3gd20| l3gd20.set_odr(odr).map(|_| ()))
+    }
+
+    /// See `L3gd20::bandwidth`
+    pub fn bandwidth(&mut self) -> Result<Bandwidth, Error<E>> {
+        self.with(|l3gd20| l3gd20.bandwidth())
+    }
+
+    /// See `L3gd20::set_bandwidth`
+    pub fn set_bandwidth(&mut self, bw: Bandwidth) -> Result<(), Error<E>> {
+        self.with(|l3gd20| l3gd20.set_bandwidth(bw).map(|_| ()))
+    }
+
+    /// See `L3gd20::scale`
+    pub fn scale(&mut self) -> Result<Scale, Error<E>> {
+        self.with(|l3gd20| l3gd20.scale())
+    }
+
+    /// See `L3gd20::set_scale`
+    pub fn set_scale(&mut self, scale: Scale) -> Result<(), Error<E>> {
+        self.with(|l3gd20| l3gd20.set_scale(scale).map(|_| ()))
+    }
+
+    /// See `L3gd20::set_bias`
+    pub fn set_bias(&mut self, bias: I16x3) {
+        self.with(|l3gd20| {
+            l3gd20.set_bias(bias);
+        })
+    }
+
+    /// See `L3gd20::set_axis_mapping`
+    pub fn set_axis_mapping(&mut self, mapping: AxisMapping) {
+        self.with(|l3gd20| {
+            l3gd20.set_axis_mapping(mapping);
+        })
+    }
+}
+
+impl<'a, SPI, CS, E> Reader<'a, SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    fn with<R>(&mut self, f: impl FnOnce(&mut L3gd20<SPI, CS>) -> R) -> R {
+        self.driver.with(f)
+    }
+
+    /// See `L3gd20::all`
+    pub fn all(&mut self) -> Result<Measurements, E> {
+        self.with(|l3gd20| l3gd20.all())
+    }
+
+    /// See `L3gd20::gyro`
+    pub fn gyro(&mut self) -> Result<I16x3, E> {
+        self.with(|l3gd20| l3gd20.gyro())
+    }
+
+    /// See `L3gd20::gyro_raw`
+    pub fn gyro_raw(&mut self) -> Result<I16x3, E> {
+        self.with(|l3gd20| l3gd20.gyro_raw())
+    }
+
+    /// See `L3gd20::gyro_mdps`
+    pub fn gyro_mdps(&mut self) -> Result<I32x3, Error<E>> {
+        self.with(|l3gd20| l3gd20.gyro_mdps())
+    }
+
+    /// See `L3gd20::gyro_rate`
+    pub fn gyro_rate(&mut self) -> Result<AngularRate, Error<E>> {
+        self.with(|l3gd20| l3gd20.gyro_rate())
+    }
+
+    /// See `L3gd20::gyro_dps`
+    #[cfg(feature = "float")]
+    pub fn gyro_dps(&mut self) -> Result<F32x3, Error<E>> {
+        self.with(|l3gd20| l3gd20.gyro_dps())
+    }
+
+    /// See `L3gd20::temp`
+    pub fn temp(&mut self) -> Result<i8, E> {
+        self.with(|l3gd20| l3gd20.temp())
+    }
+
+    /// See `L3gd20::status`
+    pub fn status(&mut self) -> Result<Status, E> {
+        self.with(|l3gd20| l3gd20.status())
+    }
+
+    /// Read the samples stored in the FIFO into `out`, without waiting
+    ///
+    /// Reads as many samples as `FSS` in `FIFO_SRC_REG` reports, at most
+    /// `out.len()`, and returns that number. The stored bias is subtracted,
+    /// as for `gyro`. The whole drain runs in a single critical section.
+    pub fn drain_fifo(&mut self, out: &mut [I16x3]) -> Result<usize, E> {
+        self.with(|l3gd20| {
+            let stored = usize::from(l3gd20.fifo_status()?.fss());
+            let count = stored.min(out.len());
+            for sample in out[..count].iter_mut() {
+                *sample = l3gd20.gyro()?;
+            }
+            Ok(count)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::replay;
+
+    #[test]
+    fn handles_share_the_driver() {
+        let mut spi = replay(
+            "
+            W 23 10                                 # CTRL_REG4: 500 dps
+            T af 00 -> 00 03                        # FIFO_SRC_REG: 3 samples
+            T e8 00 00 00 00 00 00 -> 00 01 00 02 00 03 00
+            T e8 00 00 00 00 00 00 -> 00 04 00 05 00 06 00
+            T af 00 -> 00 20                        # empty
+            ",
+        );
+        {
+            let shared = SharedL3gd20::new(L3gd20::new_hw_cs(&mut spi).unwrap());
+            let (mut config, mut reader) = shared.split();
+            config.set_scale(Scale::Dps500).unwrap();
+            config.set_bias(I16x3 { x: 1, y: 1, z: 1 });
+
+            let mut out = [I16x3::default(); 2];
+            assert_eq!(reader.drain_fifo(&mut out), Ok(2));
+            assert_eq!(out[0], I16x3 { x: 0, y: 1, z: 2 });
+            assert_eq!(out[1], I16x3 { x: 3, y: 4, z: 5 });
+            assert_eq!(reader.drain_fifo(&mut out), Ok(0));
+            assert_eq!(
+                shared.with(|l3gd20| l3gd20.cached_scale()),
+                Some(Scale::Dps500)
+            );
+        }
+        spi.finish().unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+//! Staged configuration changes
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::{CtrlReg1, CtrlReg4, CtrlReg5, Register};
+use crate::{AxesEnable, Bandwidth, DataPath, Error, L3gd20, Odr, PowerMode, Scale};
+
+const CTRL_REG1: u8 = 1 << 0;
+const CTRL_REG4: u8 = 1 << 3;
+const CTRL_REG5: u8 = 1 << 4;
+
+/// Configuration changes that are written to the sensor together
+///
+/// Created by `L3gd20::begin_config`. The setters only update a copy of the
+/// cached control registers; nothing is written until `commit`. Dropping
+/// the transaction without committing it discards the changes.
+#[must_use = "nothing is written to the sensor until `commit` is called"]
+pub struct StagedConfig<'a, SPI, CS> {
+    l3gd20: &'a mut L3gd20<SPI, CS>,
+    regs: [u8; 5],
+    // Bit `i` is set if the register at index `i` of `regs` was changed
+    touched: u8,
+    // `Low_ODR` of the staged data rate
+    low_odr: bool,
+}
+
+impl<SPI, CS, E> L3gd20<SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Start staging configuration changes, see `StagedConfig`
+    ///
+    /// The changes are applied on top of the cached register values (see
+    /// `resync_shadow`).
+    pub fn begin_config(&mut self) -> StagedConfig<'_, SPI, CS> {
+        let regs = self.shadow;
+        let low_odr = self.low_odr;
+        StagedConfig {
+            l3gd20: self,
+            regs,
+            touched: 0,
+            low_odr,
+        }
+    }
+}
+
+impl<'a, SPI, CS, E> StagedConfig<'a, SPI, CS>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+{
+    fn ctrl1(&mut self, update: impl FnOnce(CtrlReg1) -> CtrlReg1) -> &mut Self {
+        self.regs[0] = update(CtrlReg1::from_bits(self.regs[0])).bits();
+        self.touched |= CTRL_REG1;
+        self
+    }
+
+    fn ctrl4(&mut self, update: impl FnOnce(CtrlReg4) -> CtrlReg4) -> &mut Self {
+        self.regs[3] = update(CtrlReg4::from_bits(self.regs[3])).bits();
+        self.touched |= CTRL_REG4;
+        self
+    }
+
+    fn ctrl5(&mut self, update: impl FnOnce(CtrlReg5) -> CtrlReg5) -> &mut Self {
+        self.regs[4] = update(CtrlReg5::from_bits(self.regs[4])).bits();
+        self.touched |= CTRL_REG5;
+        self
+    }
+
+    /// Stage the Output Data Rate
+    pub fn odr(&mut self, odr: Odr) -> &mut Self {
+        self.low_odr = odr.low_odr();
+        self.ctrl1(|r| r.with_odr(odr))
+    }
+
+    /// Stage the low-pass cut-off
+    pub fn bandwidth(&mut self, bw: Bandwidth) -> &mut Self {
+        self.ctrl1(|r| r.with_bandwidth(bw))
+    }
+
+    /// Stage the power mode
+    pub fn power(&mut self, power: PowerMode) -> &mut Self {
+        self.ctrl1(|r| r.with_power(power))
+    }
+
+    /// Stage the enabled axes
+    pub fn axes(&mut self, axes: AxesEnable) -> &mut Self {
+        self.ctrl1(|r| r.with_axes(axes))
+    }
+
+    /// Stage the Full Scale Selection
+    pub fn scale(&mut self, scale: Scale) -> &mut Self {
+        self.ctrl4(|r| r.with_scale(scale))
+    }
+
+    /// Stage Block Data Update
+    pub fn bdu(&mut self, bdu: bool) -> &mut Self {
+        self.ctrl4(|r| r.with_bdu(bdu))
+    }
+
+    /// Stage the filters applied to the output registers
+    pub fn data_path(&mut self, data_path: DataPath) -> &mut Self {
+        self.ctrl5(|r| r.with_data_path(data_path))
+    }
+
+    /// Write the staged changes
+    ///
+    /// Each register touched by a setter is written exactly once, whether
+    /// or not its value changed. `CTRL_REG4` and `CTRL_REG5` are written
+    /// while the sensor is still running with its old data rate, and
+    /// `CTRL_REG1` last; except when the sensor is being powered down, in
+    /// which case `CTRL_REG1` is written first. A change of scale is handled
+    /// like in `L3gd20::set_scale`. A staged data rate or bandwidth is checked
+    /// according to the `BandwidthPolicy`, and the interrupt thresholds
+    /// according to the `ThresholdPolicy`, before anything is written; so
+    /// is the support of a low data rate by the variant, whose `LOW_ODR` is
+    /// written right before `CTRL_REG1`.
+    pub fn commit(self) -> Result<(), Error<E>> {
+        let StagedConfig {
+            l3gd20,
+            mut regs,
+            touched,
+            low_odr,
+        } = self;
+
+        if touched & CTRL_REG1 != 0 {
+            let reg1 = CtrlReg1::from_bits(regs[0]);
+            l3gd20.check_odr(reg1.odr_for(low_odr))?;
+            regs[0] = l3gd20.check_ctrl1(reg1, low_odr)?.bits();
+        }
+
+        let powering_down = CtrlReg1::from_bits(regs[0]).power() == PowerMode::PowerDown;
+        let previous_scale = l3gd20.cached_scale();
+        if touched & CTRL_REG4 != 0 {
+            if let Ok(scale) = CtrlReg4::from_bits(regs[3]).scale() {
+                if Some(scale) != previous_scale {
+                    l3gd20.check_thresholds(scale)?;
+                }
+            }
+        }
+
+        l3gd20.verified(|l3gd20| {
+            if touched & CTRL_REG1 != 0 && powering_down {
+                l3gd20.write_low_odr(low_odr)?;
+                l3gd20.write_register(Register::CTRL_REG1, regs[0])?;
+            }
+            if touched & CTRL_REG4 != 0 {
+                l3gd20.write_register(Register::CTRL_REG4, regs[3])?;
+                let scale = CtrlReg4::from_bits(regs[3]).scale().ok();
+                if let Some(scale) = scale.filter(|s| Some(*s) != previous_scale) {
+                    l3gd20.bias_scale_changed(previous_scale, scale);
+                    l3gd20.rescale_thresholds(scale)?;
+                }
+            }
+            if touched & CTRL_REG5 != 0 {
+                l3gd20.write_register(Register::CTRL_REG5, regs[4])?;
+            }
+            if touched & CTRL_REG1 != 0 && !powering_down {
+                l3gd20.write_low_odr(low_odr)?;
+                l3gd20.write_register(Register::CTRL_REG1, regs[0])?;
+            }
+            Ok(())
+        })
+    }
+}
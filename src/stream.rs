@@ -0,0 +1,261 @@
+//! Measurements paced by the data-ready pin
+
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::digital::Wait;
+
+use crate::registers::{CtrlReg3, CtrlReg5, FifoCtrl, Register};
+use crate::{Error, FifoMode, I16x3, L3gd20};
+
+/// Error of a `SampleStream`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamError<E, P> {
+    /// SPI bus error
+    Bus(E),
+    /// Error waiting on the data-ready pin
+    Pin(P),
+}
+
+/// Sequence of measurements, one per assertion of the DRDY/INT2 pin
+///
+/// The pin must be configured as a data-ready output (`I2_DRDY` in
+/// `CTRL_REG3`) and active high. Waiting is asynchronous, e.g. on an
+/// Embassy `ExtiInput`; the measurement itself is read with a (short)
+/// blocking SPI transaction.
+///
+/// `SampleStream` doesn't implement `futures_core::Stream`: that would
+/// require storing the future returned by `Wait`, which borrows the pin.
+/// Use `next` in a loop instead.
+pub struct SampleStream<SPI, CS, DRDY> {
+    l3gd20: L3gd20<SPI, CS>,
+    drdy: DRDY,
+}
+
+impl<SPI, CS, E, DRDY> SampleStream<SPI, CS, DRDY>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+    DRDY: Wait,
+{
+    /// Creates a stream from a driver and its data-ready pin
+    pub fn new(l3gd20: L3gd20<SPI, CS>, drdy: DRDY) -> Self {
+        SampleStream { l3gd20, drdy }
+    }
+
+    /// Wait for the next measurement
+    ///
+    /// The pin level is awaited rather than an edge, so a measurement that
+    /// became ready before this call is returned immediately instead of
+    /// being missed. The stored bias is subtracted, as for `L3gd20::gyro`.
+    /// The stream never ends: this always returns `Some`, which allows
+    /// `while let Some(sample) = stream.next().await`.
+    ///
+    /// The wait is not bounded by the driver timeout; cancel the future
+    /// (e.g. with `embassy_time::with_timeout`) to give up on the sensor.
+    pub async fn next(&mut self) -> Option<Result<I16x3, StreamError<E, DRDY::Error>>> {
+        if let Err(e) = self.drdy.wait_for_high().await {
+            return Some(Err(StreamError::Pin(e)));
+        }
+        Some(self.l3gd20.gyro().map_err(StreamError::Bus))
+    }
+
+    /// Release the driver and the data-ready pin
+    pub fn into_inner(self) -> (L3gd20<SPI, CS>, DRDY) {
+        (self.l3gd20, self.drdy)
+    }
+}
+
+/// Error of a `FifoStream`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FifoError<E, P> {
+    /// SPI bus error
+    Bus(E),
+    /// Error waiting on the watermark pin
+    Pin(P),
+    /// The FIFO overflowed and samples were lost; the FIFO has been emptied
+    /// and the stream can be used again
+    Overrun,
+    /// Any other driver error, e.g. `Error::VerifyFailed` when the FIFO
+    /// is put back in stream mode after an overrun
+    Driver(Error<E>),
+}
+
+impl<E, P> From<Error<E>> for FifoError<E, P> {
+    fn from(error: Error<E>) -> Self {
+        match error {
+            Error::Bus(e) => FifoError::Bus(e),
+            error => FifoError::Driver(error),
+        }
+    }
+}
+
+/// Sequence of batches of measurements, drained from the FIFO whenever its
+/// watermark is reached
+///
+/// The core can sleep while the FIFO fills up, which makes this the most
+/// power efficient acquisition mode. `new` puts the FIFO in stream mode and
+/// routes the watermark to the DRDY/INT2 pin, which must be active high.
+/// Samples are drained into an internal buffer of `N` samples.
+pub struct FifoStream<SPI, CS, INT2, const N: usize> {
+    l3gd20: L3gd20<SPI, CS>,
+    int2: INT2,
+    buffer: [I16x3; N],
+}
+
+impl<SPI, CS, E, INT2, const N: usize> FifoStream<SPI, CS, INT2, N>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+    INT2: Wait,
+{
+    /// Creates a stream yielding batches of `watermark` samples and more
+    ///
+    /// `watermark` is clamped to 1..=31 and to `N`.
+    pub fn new(mut l3gd20: L3gd20<SPI, CS>, int2: INT2, watermark: u8) -> Result<Self, Error<E>> {
+        let watermark = watermark.clamp(1, 31).min(N.min(31) as u8);
+
+        l3gd20.verified(|l3gd20| {
+            l3gd20.change_config(Register::FIFO_CTRL_REG, |r| {
+                FifoCtrl::from_bits(r)
+                    .with_fm(FifoMode::Stream as u8)
+                    .with_wtm(watermark)
+                    .bits()
+            })?;
+            l3gd20.change_config(Register::CTRL_REG3, |r| {
+                CtrlReg3::from_bits(r).with_i2_wtm(true).bits()
+            })?;
+            l3gd20.change_config(Register::CTRL_REG5, |r| {
+                CtrlReg5::from_bits(r).with_fifo_en(true).bits()
+            })?;
+            Ok(())
+        })?;
+
+        Ok(FifoStream {
+            l3gd20,
+            int2,
+            buffer: [I16x3::default(); N],
+        })
+    }
+
+    /// Wait for the watermark and drain the FIFO
+    ///
+    /// Returns the drained samples, oldest first, with the stored bias
+    /// subtracted (as for `L3gd20::gyro`); at most `N` of them, the rest
+    /// being left for the next call. The FIFO status is checked before
+    /// waiting, so a watermark that was reached before this call is not
+    /// missed. After a `FifoError::Overrun` the FIFO is empty and the next
+    /// call waits for a full batch again.
+    ///
+    /// Like `SampleStream::next`, the wait is not bounded by the driver
+    /// timeout.
+    pub async fn next_batch(&mut self) -> Result<&[I16x3], FifoError<E, INT2::Error>> {
+        let src = loop {
+            let src = self.l3gd20.fifo_status().map_err(FifoError::Bus)?;
+            if src.ovrn() {
+                self.recover()?;
+                return Err(FifoError::Overrun);
+            }
+            if src.wtm() {
+                break src;
+            }
+            self.int2.wait_for_high().await.map_err(FifoError::Pin)?;
+        };
+
+        let len = usize::from(src.fss()).min(N);
+        for sample in self.buffer[..len].iter_mut() {
+            let raw = self.l3gd20.gyro_raw().map_err(FifoError::Bus)?;
+            *sample = self.l3gd20.adjust(raw);
+        }
+        Ok(&self.buffer[..len])
+    }
+
+    /// Empty the FIFO by going through bypass mode
+    fn recover(&mut self) -> Result<(), Error<E>> {
+        for mode in [FifoMode::Bypass, FifoMode::Stream] {
+            self.l3gd20.set_fifo_mode_raw(mode)?;
+        }
+        Ok(())
+    }
+
+    /// Release the driver and the watermark pin
+    ///
+    /// The FIFO configuration is left as is.
+    pub fn into_inner(self) -> (L3gd20<SPI, CS>, INT2) {
+        (self.l3gd20, self.int2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{block_on, replay, WaitPin};
+    use embedded_hal_1::digital::ErrorKind;
+
+    #[test]
+    fn sample_stream_waits_for_each_sample() {
+        let mut spi = replay(
+            "
+            T e8 00 00 00 00 00 00 -> 00 01 00 02 00 03 00
+            T e8 00 00 00 00 00 00 -> 00 ff ff fe ff fd ff
+            ",
+        );
+        {
+            let l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let mut stream = SampleStream::new(l3gd20, WaitPin::default());
+            let first = block_on(stream.next()).unwrap().unwrap();
+            assert_eq!((first.x, first.y, first.z), (1, 2, 3));
+
+            stream.drdy.fail = true;
+            let error = block_on(stream.next());
+            assert_eq!(error, Some(Err(StreamError::Pin(ErrorKind::Other))));
+
+            let second = block_on(stream.next()).unwrap().unwrap();
+            assert_eq!((second.x, second.y, second.z), (-1, -2, -3));
+            let (_, drdy) = stream.into_inner();
+            assert_eq!(drdy.waits, 3);
+        }
+        spi.finish().unwrap();
+    }
+
+    #[test]
+    fn fifo_stream_drains_batches_and_recovers_from_overruns() {
+        let mut spi = replay(
+            "
+            T ae 00 -> 00 00
+            W 2e 42             # stream mode, watermark 2
+            W 22 04             # I2_WTM
+            W 24 40             # FIFO_EN
+            T af 00 -> 00 01    # below the watermark
+            T af 00 -> 00 83    # watermark, 3 samples
+            T e8 00 00 00 00 00 00 -> 00 01 00 00 00 00 00
+            T e8 00 00 00 00 00 00 -> 00 02 00 00 00 00 00
+            T e8 00 00 00 00 00 00 -> 00 03 00 00 00 00 00
+            T af 00 -> 00 df    # overrun
+            T ae 00 -> 00 42
+            W 2e 02             # bypass
+            T ae 00 -> 00 02
+            W 2e 42             # stream
+            T af 00 -> 00 86    # watermark, 6 samples but only 4 buffered
+            T e8 00 00 00 00 00 00 -> 00 04 00 00 00 00 00
+            T e8 00 00 00 00 00 00 -> 00 05 00 00 00 00 00
+            T e8 00 00 00 00 00 00 -> 00 06 00 00 00 00 00
+            T e8 00 00 00 00 00 00 -> 00 07 00 00 00 00 00
+            ",
+        );
+        {
+            let l3gd20 = L3gd20::new_hw_cs(&mut spi).unwrap();
+            let mut stream = FifoStream::<_, _, _, 4>::new(l3gd20, WaitPin::default(), 2).unwrap();
+            let xs = |batch: &[I16x3]| batch.iter().map(|s| s.x).collect::<std::vec::Vec<_>>();
+
+            let batch = block_on(stream.next_batch()).map(xs);
+            assert_eq!(batch, Ok(std::vec![1, 2, 3]));
+            let error = block_on(stream.next_batch()).map(xs);
+            assert_eq!(error, Err(FifoError::Overrun));
+            let batch = block_on(stream.next_batch()).map(xs);
+            assert_eq!(batch, Ok(std::vec![4, 5, 6, 7]));
+            let (_, int2) = stream.into_inner();
+            assert_eq!(int2.waits, 1);
+        }
+        spi.finish().unwrap();
+    }
+}
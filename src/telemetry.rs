@@ -0,0 +1,25 @@
+//! `postcard` encoding of measurements
+//!
+//! Requires the `telemetry` feature. The fields are encoded in declaration
+//! order, the gyroscope components as zig-zag varints, so small rates take
+//! fewer bytes.
+
+use crate::Measurements;
+
+/// Largest possible encoding of a `Measurements`, in bytes
+///
+/// Each `i16` takes at most 3 bytes as a varint and the `i8` temperature 1
+/// byte.
+pub const MAX_ENCODED_SIZE: usize = 10;
+
+/// Encode `sample` into `buf`, returning the number of bytes used
+///
+/// `buf` should be at least `MAX_ENCODED_SIZE` bytes long.
+pub fn encode_sample(sample: &Measurements, buf: &mut [u8]) -> Result<usize, postcard::Error> {
+    Ok(postcard::to_slice(sample, buf)?.len())
+}
+
+/// Decode a `Measurements` encoded by `encode_sample`
+pub fn decode_sample(bytes: &[u8]) -> Result<Measurements, postcard::Error> {
+    postcard::from_bytes(bytes)
+}
@@ -0,0 +1,384 @@
+//! Helpers shared by the unit tests
+
+use core::convert::Infallible;
+use std::cell::RefCell;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::rc::Rc;
+use std::string::String;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::vec::Vec;
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::replay::ReplaySpi;
+
+/// Transactions of `L3gd20::new_hw_cs` on a L3GD20 in its power-on state
+pub const INIT: &str = "
+    T 8f 00 -> 00 d4                            # WHO_AM_I
+    W 20 0f                                     # CTRL_REG1: power up
+    T e0 00 00 00 00 00 -> 00 0f 00 00 00 00    # CTRL_REG1..CTRL_REG5
+";
+
+/// Transactions of `L3gd20::new_l3gd20h` on a L3GD20H in its power-on state
+pub const INIT_H: &str = "
+    T 8f 00 -> 00 d7                            # WHO_AM_I
+    W 20 0f                                     # CTRL_REG1: power up
+    W 39 00                                     # LOW_ODR: high data rates
+    T e0 00 00 00 00 00 -> 00 0f 00 00 00 00    # CTRL_REG1..CTRL_REG5
+";
+
+/// `ReplaySpi` playing back `INIT` followed by `log`
+pub fn replay(log: &str) -> ReplaySpi {
+    let mut full = String::from(INIT);
+    full.push_str(log);
+    ReplaySpi::parse(&full).unwrap()
+}
+
+/// `ReplaySpi` playing back `INIT_H` followed by `log`
+pub fn replay_h(log: &str) -> ReplaySpi {
+    let mut full = String::from(INIT_H);
+    full.push_str(log);
+    ReplaySpi::parse(&full).unwrap()
+}
+
+/// `bytes` in the format of the replay logs, e.g. `20 0f`
+pub fn hex(bytes: &[u8]) -> String {
+    let bytes: Vec<_> = bytes.iter().map(|b| std::format!("{:02x}", b)).collect();
+    bytes.join(" ")
+}
+
+/// Register file model of the sensor, shared by a bus and a chip select
+///
+/// Follows the protocol of the sensor: the first byte of a transaction is
+/// the address with the read and auto-increment flags, every following byte
+/// reads or writes one register. Setting the chip select low starts a new
+/// transaction.
+#[derive(Debug)]
+pub struct Sensor {
+    pub regs: [u8; 64],
+    /// Address of every register read
+    pub reads: Vec<u8>,
+    /// `(address, value)` of every register written
+    pub writes: Vec<(u8, u8)>,
+    /// Bytes received in every transaction
+    pub transactions: Vec<Vec<u8>>,
+    /// Words received by a `Bus16`
+    pub words: Vec<u16>,
+    // address, read, auto-increment
+    command: Option<(u8, bool, bool)>,
+}
+
+impl Sensor {
+    /// A L3GD20 in its power-on state
+    pub fn l3gd20() -> Rc<RefCell<Sensor>> {
+        let mut regs = [0; 64];
+        regs[0x0f] = 0xd4;
+        regs[0x20] = 0x07;
+        Rc::new(RefCell::new(Sensor {
+            regs,
+            reads: Vec::new(),
+            writes: Vec::new(),
+            transactions: Vec::new(),
+            words: Vec::new(),
+            command: None,
+        }))
+    }
+
+    fn byte(&mut self, byte: u8) -> u8 {
+        match self.transactions.last_mut() {
+            Some(transaction) if self.command.is_some() => transaction.push(byte),
+            _ => self.transactions.push(std::vec![byte]),
+        }
+        let (addr, read, multi) = match self.command {
+            Some(command) => command,
+            None => {
+                self.command = Some((byte & 0x3f, byte & 0x80 != 0, byte & 0x40 != 0));
+                return 0xff;
+            }
+        };
+        let out = if read {
+            self.reads.push(addr);
+            self.regs[usize::from(addr)]
+        } else {
+            self.writes.push((addr, byte));
+            self.regs[usize::from(addr)] = byte;
+            0xff
+        };
+        if multi {
+            self.command = Some(((addr + 1) & 0x3f, read, multi));
+        }
+        out
+    }
+}
+
+/// SPI bus with 8-bit frames connected to a `Sensor`
+pub struct Bus8(pub Rc<RefCell<Sensor>>);
+
+impl Transfer<u8> for Bus8 {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Infallible> {
+        let mut sensor = self.0.borrow_mut();
+        for word in words.iter_mut() {
+            *word = sensor.byte(*word);
+        }
+        Ok(words)
+    }
+}
+
+impl Write<u8> for Bus8 {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        let mut sensor = self.0.borrow_mut();
+        for &word in words {
+            sensor.byte(word);
+        }
+        Ok(())
+    }
+}
+
+/// SPI bus with 16-bit frames connected to a `Sensor`
+pub struct Bus16(pub Rc<RefCell<Sensor>>);
+
+impl Transfer<u16> for Bus16 {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u16]) -> Result<&'w [u16], Infallible> {
+        let mut sensor = self.0.borrow_mut();
+        sensor.words.extend_from_slice(words);
+        for word in words.iter_mut() {
+            let high = sensor.byte((*word >> 8) as u8);
+            let low = sensor.byte(*word as u8);
+            *word = u16::from(high) << 8 | u16::from(low);
+        }
+        Ok(words)
+    }
+}
+
+impl Write<u16> for Bus16 {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u16]) -> Result<(), Infallible> {
+        let mut sensor = self.0.borrow_mut();
+        sensor.words.extend_from_slice(words);
+        for &word in words {
+            sensor.byte((word >> 8) as u8);
+            sensor.byte(word as u8);
+        }
+        Ok(())
+    }
+}
+
+/// Active low chip select of a `Sensor`
+pub struct Cs(pub Rc<RefCell<Sensor>>);
+
+impl OutputPin for Cs {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().command = None;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Chip select recording every level it's driven to, `true` for high
+#[derive(Default)]
+pub struct PinLog(pub Rc<RefCell<Vec<bool>>>);
+
+impl OutputPin for PinLog {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().push(true);
+        Ok(())
+    }
+}
+
+/// embedded-hal 1.0 `SpiBus` with `$word` frames, for a type implementing
+/// the embedded-hal 0.2 traits; `read` sends zeros
+#[cfg(feature = "eh1")]
+macro_rules! spi_bus {
+    ($bus:ty, $word:ty) => {
+        impl embedded_hal_1::spi::ErrorType for $bus {
+            type Error = Infallible;
+        }
+
+        impl embedded_hal_1::spi::SpiBus<$word> for $bus {
+            fn read(&mut self, words: &mut [$word]) -> Result<(), Infallible> {
+                words.fill(0);
+                Transfer::transfer(self, words).map(|_| ())
+            }
+
+            fn write(&mut self, words: &[$word]) -> Result<(), Infallible> {
+                Write::write(self, words)
+            }
+
+            fn transfer(&mut self, read: &mut [$word], write: &[$word]) -> Result<(), Infallible> {
+                let mut words = write.to_vec();
+                words.resize(read.len().max(write.len()), 0);
+                Transfer::transfer(self, &mut words)?;
+                read.copy_from_slice(&words[..read.len()]);
+                Ok(())
+            }
+
+            fn transfer_in_place(&mut self, words: &mut [$word]) -> Result<(), Infallible> {
+                Transfer::transfer(self, words).map(|_| ())
+            }
+
+            fn flush(&mut self) -> Result<(), Infallible> {
+                Ok(())
+            }
+        }
+    };
+}
+
+/// embedded-hal 1.0 `OutputPin`, for a type implementing the embedded-hal
+/// 0.2 trait
+#[cfg(feature = "eh1")]
+macro_rules! output_pin {
+    ($pin:ty) => {
+        impl embedded_hal_1::digital::ErrorType for $pin {
+            type Error = Infallible;
+        }
+
+        impl embedded_hal_1::digital::OutputPin for $pin {
+            fn set_low(&mut self) -> Result<(), Infallible> {
+                OutputPin::set_low(self)
+            }
+
+            fn set_high(&mut self) -> Result<(), Infallible> {
+                OutputPin::set_high(self)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "eh1")]
+spi_bus!(Bus8, u8);
+#[cfg(feature = "eh1")]
+spi_bus!(Bus16, u16);
+#[cfg(feature = "eh1")]
+output_pin!(Cs);
+#[cfg(feature = "eh1")]
+output_pin!(PinLog);
+
+/// Delay that returns immediately
+pub struct NoDelay;
+
+impl DelayUs<u32> for NoDelay {
+    fn delay_us(&mut self, _us: u32) {}
+}
+
+/// Waker that does nothing; `Waker::noop` is newer than the supported Rust
+#[cfg(feature = "async")]
+fn noop_waker() -> Waker {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(core::ptr::null(), &VTABLE),
+        |_| {},
+        |_| {},
+        |_| {},
+    );
+    // SAFETY: the vtable functions ignore the data pointer
+    unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) }
+}
+
+/// Poll `future` with a no-op waker until it completes
+#[cfg(feature = "async")]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Data-ready or interrupt pin whose waits, of any kind, are pending once,
+/// then complete
+#[cfg(feature = "async")]
+#[derive(Default)]
+pub struct WaitPin {
+    /// Number of waits started
+    pub waits: usize,
+    /// Make the next wait fail
+    pub fail: bool,
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_1::digital::ErrorType for WaitPin {
+    type Error = embedded_hal_1::digital::ErrorKind;
+}
+
+#[cfg(feature = "async")]
+impl WaitPin {
+    /// Every kind of wait is pending once, then completes or fails
+    async fn wait(&mut self) -> Result<(), embedded_hal_1::digital::ErrorKind> {
+        self.waits += 1;
+        PendingOnce(false).await;
+        if core::mem::take(&mut self.fail) {
+            Err(embedded_hal_1::digital::ErrorKind::Other)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::digital::Wait for WaitPin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait().await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait().await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait().await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait().await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait().await
+    }
+}
+
+/// Future that is pending the first time it's polled
+#[cfg(feature = "async")]
+struct PendingOnce(bool);
+
+#[cfg(feature = "async")]
+impl Future for PendingOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
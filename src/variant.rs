@@ -0,0 +1,29 @@
+//! Sensor variants
+
+/// Members of the L3GD20 family supported by the driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// L3GD20
+    L3gd20,
+    /// L3GD20H, which adds the `LOW_ODR` register and the low data rates
+    L3gd20h,
+}
+
+impl Variant {
+    /// Expected value of the `WHO_AM_I` register
+    pub const fn who_am_i(self) -> u8 {
+        match self {
+            Variant::L3gd20 => 0xD4,
+            Variant::L3gd20h => 0xD7,
+        }
+    }
+
+    /// Identify the variant from the value of the `WHO_AM_I` register
+    pub const fn from_who_am_i(id: u8) -> Option<Variant> {
+        match id {
+            0xD4 => Some(Variant::L3gd20),
+            0xD7 => Some(Variant::L3gd20h),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Detection of frozen outputs
+
+use crate::I16x3;
+
+/// Detects a sensor whose outputs froze
+///
+/// Some failures leave the output registers stuck at the same value while
+/// `STATUS_REG` keeps reporting new data. The watchdog counts consecutive
+/// bit-identical samples and reports the sensor as frozen once `limit` of
+/// them have been seen. A still, heavily filtered sensor can legitimately
+/// repeat a few samples, so `limit` should be well above what noise allows
+/// at the configured data rate and bandwidth.
+///
+/// Usually fed by `L3gd20::gyro_watched`.
+#[derive(Debug, Clone)]
+pub struct FreezeWatchdog {
+    limit: u32,
+    last: Option<I16x3>,
+    repeats: u32,
+}
+
+impl FreezeWatchdog {
+    /// Creates a watchdog that reports `limit` consecutive identical samples
+    ///
+    /// A `limit` of 0 or 1 is treated as 2.
+    pub const fn new(limit: u32) -> Self {
+        FreezeWatchdog {
+            limit: if limit < 2 { 2 } else { limit },
+            last: None,
+            repeats: 0,
+        }
+    }
+
+    /// Record a sample; returns `true` if the sensor is considered frozen
+    pub fn feed(&mut self, sample: I16x3) -> bool {
+        if self.last == Some(sample) {
+            self.repeats = self.repeats.saturating_add(1);
+        } else {
+            self.last = Some(sample);
+            self.repeats = 1;
+        }
+        self.is_frozen()
+    }
+
+    /// `true` if the last `limit` samples were identical
+    pub fn is_frozen(&self) -> bool {
+        self.repeats >= self.limit
+    }
+
+    /// Number of consecutive identical samples seen so far
+    pub fn repeats(&self) -> u32 {
+        self.repeats
+    }
+
+    /// Forget the samples seen so far, e.g. after recovering the sensor
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.repeats = 0;
+    }
+}
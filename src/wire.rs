@@ -0,0 +1,131 @@
+//! Compact binary encoding of measurements
+//!
+//! The formats are stable: they only change with a major version of this
+//! crate.
+//!
+//! - `I16x3`: 6 bytes, `x`, `y` and `z` as little endian `i16`
+//! - `Measurements`: 8 bytes, the `I16x3` encoding of `gyro`, `temp` as
+//!   `i8`, then a flags byte that is currently reserved and always zero
+//! - `CalibrationData` (`float` feature): 51 bytes, `bias` as 3 little
+//!   endian `f32`, `matrix` as 9 little endian `f32` in row-major order,
+//!   then one byte per output axis of `mapping`: the physical axis (0 to 2
+//!   for X to Z) in bits 0-1 and the negation in bit 7
+
+#[cfg(feature = "float")]
+use crate::{Axis, AxisMapping, AxisSource, CalibrationData, F32x3};
+use crate::{I16x3, Measurements};
+
+/// Error decoding a measurement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// Reserved bits are set in the flags byte
+    ReservedFlags {
+        /// Value of the flags byte
+        value: u8,
+    },
+    /// An axis mapping byte is invalid or the axes are not a permutation
+    InvalidMapping,
+}
+
+impl I16x3 {
+    /// Encode as 6 bytes, see the `wire` format
+    pub const fn to_bytes(&self) -> [u8; 6] {
+        let x = self.x.to_le_bytes();
+        let y = self.y.to_le_bytes();
+        let z = self.z.to_le_bytes();
+        [x[0], x[1], y[0], y[1], z[0], z[1]]
+    }
+
+    /// Decode from 6 bytes, see the `wire` format
+    pub const fn from_bytes(bytes: [u8; 6]) -> Self {
+        I16x3 {
+            x: i16::from_le_bytes([bytes[0], bytes[1]]),
+            y: i16::from_le_bytes([bytes[2], bytes[3]]),
+            z: i16::from_le_bytes([bytes[4], bytes[5]]),
+        }
+    }
+}
+
+impl Measurements {
+    /// Encode as 8 bytes, see the `wire` format
+    pub const fn to_bytes(&self) -> [u8; 8] {
+        let g = self.gyro.to_bytes();
+        [g[0], g[1], g[2], g[3], g[4], g[5], self.temp as u8, 0]
+    }
+
+    /// Decode from 8 bytes, see the `wire` format
+    pub const fn from_bytes(bytes: [u8; 8]) -> Result<Self, DecodeError> {
+        if bytes[7] != 0 {
+            return Err(DecodeError::ReservedFlags { value: bytes[7] });
+        }
+
+        Ok(Measurements {
+            gyro: I16x3::from_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]]),
+            temp: bytes[6] as i8,
+        })
+    }
+}
+
+#[cfg(feature = "float")]
+impl CalibrationData {
+    /// Size of the encoding, see the `wire` format
+    pub const ENCODED_LEN: usize = 51;
+
+    /// Encode, e.g. to store the calibration in flash; see the `wire` format
+    pub fn to_bytes(&self) -> [u8; CalibrationData::ENCODED_LEN] {
+        let mut bytes = [0u8; CalibrationData::ENCODED_LEN];
+        let b = &self.bias;
+        let m = &self.matrix;
+        let values = [
+            b.x, b.y, b.z, m[0][0], m[0][1], m[0][2], m[1][0], m[1][1], m[1][2], m[2][0], m[2][1],
+            m[2][2],
+        ];
+        for (chunk, value) in bytes.chunks_exact_mut(4).zip(values.iter()) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        for (byte, source) in bytes[48..].iter_mut().zip(self.mapping.sources().iter()) {
+            let axis = match source.axis {
+                Axis::X => 0,
+                Axis::Y => 1,
+                Axis::Z => 2,
+            };
+            *byte = axis | if source.negate { 0x80 } else { 0 };
+        }
+        bytes
+    }
+
+    /// Decode, see the `wire` format
+    pub fn from_bytes(bytes: &[u8; CalibrationData::ENCODED_LEN]) -> Result<Self, DecodeError> {
+        let mut values = [0f32; 12];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(4)) {
+            *value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        let source = |byte: u8| {
+            let axis = match byte & 0x7f {
+                0 => Axis::X,
+                1 => Axis::Y,
+                2 => Axis::Z,
+                _ => return Err(DecodeError::InvalidMapping),
+            };
+            Ok(AxisSource {
+                axis,
+                negate: byte & 0x80 != 0,
+            })
+        };
+        let mapping = AxisMapping::new(source(bytes[48])?, source(bytes[49])?, source(bytes[50])?)
+            .ok_or(DecodeError::InvalidMapping)?;
+
+        let v = values;
+        Ok(CalibrationData {
+            bias: F32x3 {
+                x: v[0],
+                y: v[1],
+                z: v[2],
+            },
+            matrix: [[v[3], v[4], v[5]], [v[6], v[7], v[8]], [v[9], v[10], v[11]]],
+            mapping,
+        })
+    }
+}